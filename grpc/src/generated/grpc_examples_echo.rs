@@ -49,6 +49,7 @@ pub mod echo_client {
         where
             F: tonic::service::Interceptor,
             T::ResponseBody: Default,
+            T: Clone + std::marker::Send + 'static,
             T: tonic::codegen::Service<
                 http::Request<tonic::body::Body>,
                 Response = http::Response<
@@ -66,7 +67,7 @@ pub mod echo_client {
         /// This requires the server to support it otherwise it might respond with an
         /// error.
         #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+        pub fn send_compressed(mut self, encoding: impl Into<SendCompression>) -> Self {
             self.inner = self.inner.send_compressed(encoding);
             self
         }
@@ -288,7 +289,7 @@ pub mod echo_server {
         }
         /// Compress responses with the given encoding, if the client supports it.
         #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+        pub fn send_compressed(mut self, encoding: impl Into<SendCompression>) -> Self {
             self.send_compression_encodings.enable(encoding);
             self
         }
@@ -756,7 +756,7 @@ static void GenerateServer(const Service &service, Printer &printer,
 
             /// Compress responses with the given encoding, if the client supports it.
             #[must_use]
-            pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            pub fn send_compressed(mut self, encoding: impl Into<SendCompression>) -> Self {
                 self.send_compression_encodings.enable(encoding);
                 self
             }
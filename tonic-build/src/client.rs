@@ -86,6 +86,7 @@ pub(crate) fn generate_internal<T: Service>(
                 where
                     F: tonic::service::Interceptor,
                     T::ResponseBody: Default,
+                    T: Clone + std::marker::Send + 'static,
                     T: tonic::codegen::Service<
                         http::Request<tonic::body::Body>,
                         Response = http::Response<<T as tonic::client::GrpcService<tonic::body::Body>>::ResponseBody>
@@ -100,7 +101,7 @@ pub(crate) fn generate_internal<T: Service>(
                 /// This requires the server to support it otherwise it might respond with an
                 /// error.
                 #[must_use]
-                pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+                pub fn send_compressed(mut self, encoding: impl Into<SendCompression>) -> Self {
                     self.inner = self.inner.send_compressed(encoding);
                     self
                 }
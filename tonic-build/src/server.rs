@@ -68,7 +68,7 @@ pub(crate) fn generate_internal<T: Service>(
 
         /// Compress responses with the given encoding, if the client supports it.
         #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+        pub fn send_compressed(mut self, encoding: impl Into<SendCompression>) -> Self {
             self.send_compression_encodings.enable(encoding);
             self
         }
@@ -0,0 +1,326 @@
+//! Contains client-side health checking utilities.
+
+use crate::pb::health_check_response::ServingStatus;
+use crate::pb::{HealthCheckRequest, health_client::HealthClient};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Sender};
+use tokio::task::JoinHandle;
+use tonic::Code;
+use tonic::transport::channel::{Change, LoadBalancingPolicy};
+use tonic::transport::{Channel, Endpoint};
+
+/// How long to wait before watching the health of an endpoint again after the
+/// stream failed.
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Balances calls only across the endpoints reporting a service as serving.
+///
+/// For every endpoint, a `grpc.health.v1.Health/Watch` stream is opened for
+/// the service. The endpoint is added to the balancer once it reports
+/// `SERVING`, and removed when it reports anything else or the stream fails,
+/// until it reports `SERVING` again. Endpoints that do not implement the
+/// health service are considered healthy, as recommended by the [health
+/// checking protocol].
+///
+/// Health checks use a connection of their own to each endpoint, with the
+/// settings of the endpoint.
+///
+/// ```
+/// # use tonic::transport::Endpoint;
+/// # use tonic_health::client::HealthCheck;
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let endpoints = ["http://[::1]:50051", "http://[::1]:50052"].map(Endpoint::from_static);
+/// let channel = HealthCheck::new("helloworld.Greeter").balance_list(endpoints.into_iter());
+/// # }
+/// ```
+///
+/// [health checking protocol]: https://github.com/grpc/grpc/blob/master/doc/health-checking.md
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+    service: String,
+    policy: LoadBalancingPolicy,
+}
+
+impl HealthCheck {
+    /// Check the health of `service` on every endpoint.
+    ///
+    /// The empty service name `""` checks the health of the whole server.
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+            policy: LoadBalancingPolicy::default(),
+        }
+    }
+
+    /// Balance calls across the healthy endpoints according to `policy`.
+    pub fn policy(self, policy: LoadBalancingPolicy) -> Self {
+        HealthCheck { policy, ..self }
+    }
+
+    /// Balance calls across the healthy endpoints of a list.
+    ///
+    /// The settings of the whole channel are taken from the first endpoint,
+    /// see [`Channel::balance_channel`].
+    ///
+    /// This must be called from within a tokio runtime.
+    pub fn balance_list(self, list: impl Iterator<Item = Endpoint>) -> Channel {
+        let list = list.collect::<Vec<_>>();
+        let (channel, tx) = self.balance_with(list.len().max(1), list.first());
+        for endpoint in list {
+            tx.try_send(Change::Insert(endpoint.uri().clone(), endpoint))
+                .unwrap();
+        }
+
+        channel
+    }
+
+    /// Balance calls across the healthy endpoints sent as change events.
+    ///
+    /// The channel uses the default settings, see [`Channel::balance_channel`].
+    ///
+    /// This must be called from within a tokio runtime.
+    pub fn balance_channel<K>(self, capacity: usize) -> (Channel, Sender<Change<K, Endpoint>>)
+    where
+        K: Hash + Eq + Send + Clone + 'static,
+    {
+        self.balance_with(capacity, None)
+    }
+
+    /// Balance calls across the healthy endpoints sent as change events, with
+    /// the settings of the whole channel taken from `settings`.
+    ///
+    /// See [`Channel::balance_channel_with_settings`].
+    ///
+    /// This must be called from within a tokio runtime.
+    pub fn balance_channel_with_settings<K>(
+        self,
+        capacity: usize,
+        settings: &Endpoint,
+    ) -> (Channel, Sender<Change<K, Endpoint>>)
+    where
+        K: Hash + Eq + Send + Clone + 'static,
+    {
+        self.balance_with(capacity, Some(settings))
+    }
+
+    fn balance_with<K>(
+        self,
+        capacity: usize,
+        settings: Option<&Endpoint>,
+    ) -> (Channel, Sender<Change<K, Endpoint>>)
+    where
+        K: Hash + Eq + Send + Clone + 'static,
+    {
+        let (channel, healthy) = match settings {
+            Some(settings) => {
+                Channel::balance_channel_with_settings(capacity, settings, self.policy)
+            }
+            None => Channel::balance_channel_with_policy(capacity, self.policy),
+        };
+        let (tx, mut rx) = mpsc::channel::<Change<K, Endpoint>>(capacity);
+
+        tokio::spawn(async move {
+            let mut watches = HashMap::<K, JoinHandle<()>>::new();
+
+            while let Some(change) = rx.recv().await {
+                match change {
+                    Change::Insert(key, endpoint) => {
+                        // A replaced endpoint is removed until the new one is
+                        // reported as serving.
+                        if let Some(watch) = watches.remove(&key) {
+                            watch.abort();
+                            if healthy.send(Change::Remove(key.clone())).await.is_err() {
+                                break;
+                            }
+                        }
+
+                        let health = endpoint.connect_lazy();
+                        let watch = tokio::spawn(watch(
+                            key.clone(),
+                            endpoint,
+                            health,
+                            self.service.clone(),
+                            healthy.clone(),
+                        ));
+                        watches.insert(key, watch);
+                    }
+                    Change::Remove(key) => {
+                        if let Some(watch) = watches.remove(&key) {
+                            watch.abort();
+                        }
+                        if healthy.send(Change::Remove(key)).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            // Endpoints are still watched once no more changes are sent, until
+            // the channel is dropped.
+            healthy.closed().await;
+            watches.values().for_each(JoinHandle::abort);
+        });
+
+        (channel, tx)
+    }
+}
+
+/// Add `endpoint` to the balancer while `health` reports `service` as serving.
+async fn watch<K>(
+    key: K,
+    endpoint: Endpoint,
+    health: Channel,
+    service: String,
+    healthy: Sender<Change<K, Endpoint>>,
+) where
+    K: Clone,
+{
+    let mut client = HealthClient::new(health);
+    let mut watched = Watched {
+        key,
+        endpoint,
+        serving: false,
+        healthy,
+    };
+
+    loop {
+        let request = HealthCheckRequest {
+            service: service.clone(),
+        };
+
+        match client.watch(request).await {
+            Ok(response) => {
+                let mut statuses = response.into_inner();
+                while let Ok(Some(response)) = statuses.message().await {
+                    let serving = response.status() == ServingStatus::Serving;
+                    if watched.set_serving(serving).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(status) if status.code() == Code::Unimplemented => {
+                let _ = watched.set_serving(true).await;
+                return;
+            }
+            Err(_) => {}
+        }
+
+        if watched.set_serving(false).await.is_err() {
+            return;
+        }
+        tokio::time::sleep(RETRY_INTERVAL).await;
+    }
+}
+
+struct Watched<K> {
+    key: K,
+    endpoint: Endpoint,
+    serving: bool,
+    healthy: Sender<Change<K, Endpoint>>,
+}
+
+impl<K: Clone> Watched<K> {
+    /// Add or remove the endpoint from the balancer, failing if the channel
+    /// was dropped.
+    async fn set_serving(&mut self, serving: bool) -> Result<(), ()> {
+        if serving == self.serving {
+            return Ok(());
+        }
+        self.serving = serving;
+
+        let change = if serving {
+            Change::Insert(self.key.clone(), self.endpoint.clone())
+        } else {
+            Change::Remove(self.key.clone())
+        };
+        self.healthy.send(change).await.map_err(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::health_reporter;
+    use tonic::service::Routes;
+
+    #[tokio::test]
+    async fn follows_serving_status() {
+        let (reporter, server) = health_reporter();
+        reporter
+            .set_service_status("test.Service", crate::ServingStatus::NotServing)
+            .await;
+        let health = Channel::in_process(Routes::new(server));
+
+        let endpoint = Endpoint::from_static("http://test");
+        let (tx, mut rx) = mpsc::channel(4);
+        tokio::spawn(watch(1, endpoint, health, "test.Service".into(), tx));
+
+        reporter
+            .set_service_status("test.Service", crate::ServingStatus::Serving)
+            .await;
+        assert!(matches!(rx.recv().await, Some(Change::Insert(1, _))));
+
+        reporter
+            .set_service_status("test.Service", crate::ServingStatus::NotServing)
+            .await;
+        assert!(matches!(rx.recv().await, Some(Change::Remove(1))));
+    }
+
+    #[tokio::test]
+    async fn balance_list_follows_serving_status() {
+        use tonic::transport::{Server, channel::ConnectivityState, server::TcpIncoming};
+
+        let (reporter, server) = health_reporter();
+        reporter
+            .set_service_status("test.Service", crate::ServingStatus::Serving)
+            .await;
+        let incoming = TcpIncoming::bind(([127, 0, 0, 1], 0).into()).unwrap();
+        let endpoint =
+            Endpoint::from_shared(format!("http://{}", incoming.local_addr().unwrap())).unwrap();
+        tokio::spawn(
+            Server::builder()
+                .add_routes(Routes::new(server))
+                .serve_with_incoming(incoming),
+        );
+
+        let channel = HealthCheck::new("test.Service").balance_list([endpoint].into_iter());
+        let client = HealthClient::new(channel.clone());
+        let check = |timeout| {
+            let mut client = client.clone();
+            let request = HealthCheckRequest {
+                service: "test.Service".into(),
+            };
+            async move { tokio::time::timeout(timeout, client.check(request)).await }
+        };
+
+        // Calls wait for the endpoint to be reported as serving.
+        check(Duration::from_secs(5)).await.unwrap().unwrap();
+        assert_eq!(channel.state(), ConnectivityState::Ready);
+
+        reporter
+            .set_service_status("test.Service", crate::ServingStatus::NotServing)
+            .await;
+
+        // Once removed, calls wait for an endpoint to be added again.
+        let removed = async { while check(Duration::from_millis(50)).await.is_ok() {} };
+        tokio::time::timeout(Duration::from_secs(5), removed)
+            .await
+            .unwrap();
+        assert_eq!(channel.state(), ConnectivityState::Idle);
+    }
+
+    #[tokio::test]
+    async fn unimplemented_health_is_healthy() {
+        let health = Channel::in_process(Routes::default());
+
+        let endpoint = Endpoint::from_static("http://test");
+        let (tx, mut rx) = mpsc::channel(4);
+        tokio::spawn(watch(1, endpoint, health, "".into(), tx));
+
+        assert!(matches!(rx.recv().await, Some(Change::Insert(1, _))));
+        assert!(rx.recv().await.is_none());
+    }
+}
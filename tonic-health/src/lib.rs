@@ -42,6 +42,8 @@ pub mod pb {
     pub use crate::generated::{FILE_DESCRIPTOR_SET, grpc_health_v1::*};
 }
 
+#[cfg(feature = "client")]
+pub mod client;
 pub mod server;
 
 /// An enumeration of values representing gRPC service health.
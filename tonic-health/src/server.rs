@@ -8,6 +8,8 @@ use std::fmt;
 use std::sync::Arc;
 use tokio::sync::{RwLock, watch};
 use tokio_stream::Stream;
+#[cfg(feature = "transport")]
+use tonic::transport::server::ShutdownHandle;
 use tonic::{Request, Response, Status, server::NamedService};
 
 /// Creates a `HealthReporter` and a linked `HealthServer` pair. Together,
@@ -91,6 +93,21 @@ impl HealthReporter {
         };
     }
 
+    /// Sets the status of every service, including the overall server health,
+    /// to `NotServing` once `shutdown` starts draining the server.
+    ///
+    /// Clients checking the health of the server then stop sending it calls,
+    /// while the calls in flight complete. The returned future must be spawned
+    /// or polled alongside the server.
+    #[cfg(feature = "transport")]
+    pub async fn set_not_serving_on_drain(&self, shutdown: ShutdownHandle) {
+        shutdown.draining().await;
+
+        for (tx, _) in self.statuses.read().await.values() {
+            tx.send_replace(ServingStatus::NotServing);
+        }
+    }
+
     /// Clear the status of the given service.
     pub async fn clear_service_status(&mut self, service_name: &str) {
         let mut writer = self.statuses.write().await;
@@ -350,4 +367,29 @@ mod tests {
         let item = resp.next().await;
         assert!(item.is_none());
     }
+
+    #[cfg(feature = "transport")]
+    #[tokio::test]
+    async fn test_not_serving_on_drain() {
+        let (reporter, service) = make_test_service().await;
+        let shutdown = tonic::transport::Server::builder().shutdown_handle();
+
+        let watcher = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { reporter.set_not_serving_on_drain(shutdown).await }
+        });
+        shutdown.drain(std::time::Duration::ZERO).await;
+        watcher.await.unwrap();
+
+        for service_name in ["", "TestService"] {
+            let resp = service
+                .check(Request::new(HealthCheckRequest {
+                    service: service_name.to_string(),
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_serving_status(resp.status, ServingStatus::NotServing);
+        }
+    }
 }
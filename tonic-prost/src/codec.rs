@@ -68,7 +68,9 @@ where
     fn decoder(&mut self) -> Self::Decoder {
         ProstDecoder {
             _pd: PhantomData,
-            buffer_settings: BufferSettings::default(),
+            // prost decodes from any `Buf`, and `bytes::Bytes` fields then
+            // share the received frames instead of copying them.
+            buffer_settings: BufferSettings::default().zero_copy_decode(true),
         }
     }
 }
@@ -216,6 +218,41 @@ mod tests {
         assert_eq!(actual.message(), expected.message());
     }
 
+    #[tokio::test]
+    async fn decode_bytes_fields_without_copy() {
+        use bytes::Bytes;
+        use http_body::Frame;
+        use http_body_util::StreamBody;
+
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct Blob {
+            #[prost(bytes = "bytes", tag = "1")]
+            data: Bytes,
+        }
+
+        let blob = Blob {
+            data: Bytes::from(vec![7u8; LEN]),
+        };
+        let mut buf = BytesMut::new();
+        buf.put_u8(0);
+        buf.put_u32(blob.encoded_len() as u32);
+        blob.encode(&mut buf).unwrap();
+        let frame = buf.freeze();
+
+        let body = StreamBody::new(tokio_stream::iter([Ok::<_, Status>(Frame::data(
+            frame.clone(),
+        ))]));
+        let decoder = ProstCodec::<Blob, Blob>::default().decoder();
+        let mut stream = Streaming::new_request(decoder, body, None, None);
+
+        let decoded = stream.message().await.unwrap().unwrap();
+        assert_eq!(decoded, blob);
+
+        // The field points into the received frame.
+        let frame = frame.as_ptr_range();
+        assert!(frame.contains(&decoded.data.as_ptr()));
+    }
+
     #[tokio::test]
     async fn encode() {
         let encoder = MockEncoder::default();
@@ -306,6 +343,49 @@ mod tests {
         assert!(body.is_end_stream());
     }
 
+    #[tokio::test]
+    async fn encode_small_messages_uncompressed() {
+        use std::io;
+        use tonic::codec::{CompressionEncoding, Compressor};
+
+        struct Prefix;
+
+        impl Compressor for Prefix {
+            fn name(&self) -> &'static str {
+                "x-prefix"
+            }
+
+            fn compress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+                output.write_all(b"!")?;
+                output.write_all(input)
+            }
+
+            fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+                output.write_all(&input[1..])
+            }
+        }
+
+        let compression = CompressionEncoding::register(Prefix).min_message_size(4);
+        let messages = [vec![1u8; 3], vec![2u8; 4]].map(Ok::<_, Status>);
+
+        let body = EncodeBody::new_server_with_compression(
+            MockEncoder::default(),
+            tokio_stream::iter(messages),
+            Some(compression),
+            SingleMessageCompressionOverride::default(),
+            None,
+        );
+        let mut data = body.collect().await.unwrap().to_bytes();
+
+        assert_eq!(data.get_u8(), 0);
+        assert_eq!(data.get_u32(), 3);
+        data.advance(3);
+
+        assert_eq!(data.get_u8(), 1);
+        assert_eq!(data.get_u32(), 5);
+        assert_eq!(&data[..], b"!\x02\x02\x02\x02");
+    }
+
     #[derive(Debug, Clone, Default)]
     struct MockEncoder {}
 
@@ -60,6 +60,7 @@ pub mod greeter_client {
         where
             F: tonic::service::Interceptor,
             T::ResponseBody: Default,
+            T: Clone + std::marker::Send + 'static,
             T: tonic::codegen::Service<
                     http::Request<tonic::body::Body>,
                     Response = http::Response<
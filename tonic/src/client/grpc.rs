@@ -1,12 +1,15 @@
 use crate::codec::EncodeBody;
-use crate::codec::{CompressionEncoding, EnabledCompressionEncodings};
+use crate::codec::{CompressionEncoding, EnabledCompressionEncodings, SendCompression};
 use crate::metadata::GRPC_CONTENT_TYPE;
 use crate::{
     Code, Request, Response, Status,
     body::Body,
     client::GrpcService,
     codec::{Codec, Decoder, Streaming},
-    request::SanitizeHeaders,
+    request::{
+        AuthorityOverride, Deadline, MaxDecodingMessageSize, MaxEncodingMessageSize,
+        SanitizeHeaders, SendCompressed,
+    },
 };
 use http::{
     header::{CONTENT_TYPE, HeaderValue, TE},
@@ -39,7 +42,7 @@ struct GrpcConfig {
     /// Which compression encodings does the client accept?
     accept_compression_encodings: EnabledCompressionEncodings,
     /// The compression encoding that will be applied to requests.
-    send_compression_encodings: Option<CompressionEncoding>,
+    send_compression_encodings: Option<SendCompression>,
     /// Limits the maximum size of a decoded message.
     max_decoding_message_size: Option<usize>,
     /// Limits the maximum size of an encoded message.
@@ -72,6 +75,8 @@ impl<T> Grpc<T> {
     /// Compress requests with the provided encoding.
     ///
     /// Requires the server to accept the specified encoding, otherwise it might return an error.
+    /// Pass a [`SendCompression`] instead of a [`CompressionEncoding`] to set the compression
+    /// level or leave small messages uncompressed.
     ///
     /// # Example
     ///
@@ -95,8 +100,8 @@ impl<T> Grpc<T> {
     /// let client = TestClient::new(channel).send_compressed(CompressionEncoding::Gzip);
     /// # };
     /// ```
-    pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-        self.config.send_compression_encodings = Some(encoding);
+    pub fn send_compressed(mut self, compression: impl Into<SendCompression>) -> Self {
+        self.config.send_compression_encodings = Some(compression.into());
         self
     }
 
@@ -294,18 +299,32 @@ impl<T> Grpc<T> {
         M1: Send + Sync + 'static,
         M2: Send + Sync + 'static,
     {
+        let extensions = request.extensions();
+        let send_compression = extensions
+            .get::<SendCompressed>()
+            .map(|compression| compression.0)
+            .or(self.config.send_compression_encodings);
+        let max_encoding_message_size = extensions
+            .get::<MaxEncodingMessageSize>()
+            .map(|limit| limit.0)
+            .or(self.config.max_encoding_message_size);
+        let max_decoding_message_size = extensions
+            .get::<MaxDecodingMessageSize>()
+            .map(|limit| limit.0)
+            .or(self.config.max_decoding_message_size);
+
         let request = request
             .map(|s| {
-                EncodeBody::new_client(
+                EncodeBody::new_client_with_compression(
                     codec.encoder(),
                     s.map(Ok),
-                    self.config.send_compression_encodings,
-                    self.config.max_encoding_message_size,
+                    send_compression,
+                    max_encoding_message_size,
                 )
             })
             .map(Body::new);
 
-        let request = self.config.prepare_request(request, path);
+        let request = self.config.prepare_request(request, path, send_compression);
 
         let response = self
             .inner
@@ -315,7 +334,7 @@ impl<T> Grpc<T> {
 
         let decoder = codec.decoder();
 
-        self.create_response(decoder, response)
+        self.create_response(decoder, response, max_decoding_message_size)
     }
 
     // Keeping this code in a separate function from Self::streaming lets functions that return the
@@ -324,6 +343,7 @@ impl<T> Grpc<T> {
         &self,
         decoder: impl Decoder<Item = M2, Error = Status> + Send + 'static,
         response: http::Response<T::ResponseBody>,
+        max_decoding_message_size: Option<usize>,
     ) -> Result<Response<Streaming<M2>>, Status>
     where
         T: GrpcService<Body>,
@@ -357,7 +377,7 @@ impl<T> Grpc<T> {
                     body,
                     status_code,
                     encoding,
-                    self.config.max_decoding_message_size,
+                    max_decoding_message_size,
                 )
             } else {
                 Streaming::new_empty(decoder, body)
@@ -369,9 +389,22 @@ impl<T> Grpc<T> {
 }
 
 impl GrpcConfig {
-    fn prepare_request(&self, request: Request<Body>, path: PathAndQuery) -> http::Request<Body> {
+    fn prepare_request(
+        &self,
+        request: Request<Body>,
+        path: PathAndQuery,
+        send_compression: Option<SendCompression>,
+    ) -> http::Request<Body> {
         let mut parts = self.origin.clone().into_parts();
 
+        // A channel sets the authority itself, only override it if the origin
+        // has one.
+        if let Some(authority) = request.extensions().get::<AuthorityOverride>()
+            && parts.authority.is_some()
+        {
+            parts.authority = Some(authority.0.clone());
+        }
+
         match &parts.path_and_query {
             Some(pnq) if pnq != "/" => {
                 parts.path_and_query = Some(
@@ -394,6 +427,11 @@ impl GrpcConfig {
             SanitizeHeaders::Yes,
         );
 
+        // Propagate the deadline of the call, unless it has a shorter timeout.
+        if let Some(deadline) = request.extensions().get::<Deadline>().copied() {
+            deadline.apply(request.headers_mut());
+        }
+
         // Add the gRPC related HTTP headers
         request
             .headers_mut()
@@ -404,11 +442,10 @@ impl GrpcConfig {
             .headers_mut()
             .insert(CONTENT_TYPE, GRPC_CONTENT_TYPE);
 
-        #[cfg(any(feature = "gzip", feature = "deflate", feature = "zstd"))]
-        if let Some(encoding) = self.send_compression_encodings {
+        if let Some(compression) = send_compression {
             request.headers_mut().insert(
                 crate::codec::compression::ENCODING_HEADER,
-                encoding.into_header_value(),
+                compression.encoding().into_header_value(),
             );
         }
 
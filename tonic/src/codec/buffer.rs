@@ -1,10 +1,17 @@
 use bytes::buf::UninitSlice;
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::{collections::VecDeque, fmt};
 
 /// A specialized buffer to decode gRPC messages from.
-#[derive(Debug)]
+///
+/// The message is usually in one contiguous chunk. When the decoder
+/// enabled [`BufferSettings::zero_copy_decode`] it is instead made of the
+/// received data frames, and [`Buf::copy_to_bytes`] hands out slices of
+/// these frames without copying them.
+///
+/// [`BufferSettings::zero_copy_decode`]: super::BufferSettings::zero_copy_decode
 pub struct DecodeBuf<'a> {
-    buf: &'a mut BytesMut,
+    buf: &'a mut (dyn Buf + Send + Sync),
     len: usize,
 }
 
@@ -15,11 +22,17 @@ pub struct EncodeBuf<'a> {
 }
 
 impl<'a> DecodeBuf<'a> {
-    pub(crate) fn new(buf: &'a mut BytesMut, len: usize) -> Self {
+    pub(crate) fn new(buf: &'a mut (dyn Buf + Send + Sync), len: usize) -> Self {
         DecodeBuf { buf, len }
     }
 }
 
+impl fmt::Debug for DecodeBuf<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecodeBuf").field("len", &self.len).finish()
+    }
+}
+
 impl Buf for DecodeBuf<'_> {
     #[inline]
     fn remaining(&self) -> usize {
@@ -52,6 +65,70 @@ impl Buf for DecodeBuf<'_> {
     }
 }
 
+/// A buffer made of received data frames, which are not copied.
+#[derive(Debug, Default)]
+pub(crate) struct BufList {
+    bufs: VecDeque<Bytes>,
+    len: usize,
+}
+
+impl BufList {
+    pub(crate) fn push(&mut self, buf: Bytes) {
+        if !buf.is_empty() {
+            self.len += buf.len();
+            self.bufs.push_back(buf);
+        }
+    }
+}
+
+impl Buf for BufList {
+    #[inline]
+    fn remaining(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn chunk(&self) -> &[u8] {
+        self.bufs.front().map_or(&[], |buf| &buf[..])
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        assert!(cnt <= self.len);
+        self.len -= cnt;
+
+        while cnt > 0 {
+            let front = self.bufs.front_mut().expect("enough remaining bytes");
+            if cnt < front.len() {
+                front.advance(cnt);
+                return;
+            }
+            cnt -= front.len();
+            self.bufs.pop_front();
+        }
+    }
+
+    fn copy_to_bytes(&mut self, len: usize) -> Bytes {
+        assert!(len <= self.len);
+
+        match self.bufs.front_mut() {
+            // The bytes are in a single frame, share it.
+            Some(front) if len <= front.len() => {
+                let bytes = front.split_to(len);
+                if front.is_empty() {
+                    self.bufs.pop_front();
+                }
+                self.len -= len;
+                bytes
+            }
+            _ => {
+                let mut bytes = BytesMut::with_capacity(len);
+                bytes.put((&mut *self).take(len));
+                bytes.freeze()
+            }
+        }
+    }
+}
+
 impl<'a> EncodeBuf<'a> {
     pub(crate) fn new(buf: &'a mut BytesMut) -> Self {
         EncodeBuf { buf }
@@ -131,6 +208,26 @@ mod tests {
         assert!(!buf.has_remaining());
     }
 
+    #[test]
+    fn buf_list_shares_frames() {
+        let frame = Bytes::from_static(b"hello world");
+        let mut list = BufList::default();
+        list.push(frame.clone());
+        list.push(Bytes::from_static(b"!"));
+
+        let mut buf = DecodeBuf::new(&mut list, 12);
+        let hello = buf.copy_to_bytes(5);
+        assert_eq!(hello.as_ptr(), frame.as_ptr());
+
+        buf.advance(1);
+        assert_eq!(buf.chunk(), b"world");
+
+        // Spans two frames, so has to be copied.
+        assert_eq!(buf.copy_to_bytes(6), &b"world!"[..]);
+        assert!(!buf.has_remaining());
+        assert!(list.bufs.is_empty());
+    }
+
     #[test]
     fn encode_buf() {
         let mut bytes = BytesMut::with_capacity(100);
@@ -4,36 +4,58 @@ use bytes::{Buf, BufMut, BytesMut};
 use flate2::read::{GzDecoder, GzEncoder};
 #[cfg(feature = "deflate")]
 use flate2::read::{ZlibDecoder, ZlibEncoder};
-use std::{borrow::Cow, fmt};
+use std::{
+    borrow::Cow,
+    fmt, io,
+    sync::{Arc, RwLock},
+};
 #[cfg(feature = "zstd")]
 use zstd::stream::read::{Decoder, Encoder};
 
 pub(crate) const ENCODING_HEADER: &str = "grpc-encoding";
 pub(crate) const ACCEPT_ENCODING_HEADER: &str = "grpc-accept-encoding";
 
+/// The maximum number of encodings that can be enabled at the same time.
+const MAX_ENABLED_ENCODINGS: usize = 8;
+
 /// Struct used to configure which encodings are enabled on a server or channel.
 ///
 /// Represents an ordered list of compression encodings that are enabled.
 #[derive(Debug, Default, Clone, Copy)]
 pub struct EnabledCompressionEncodings {
-    inner: [Option<CompressionEncoding>; 3],
+    inner: [Option<SendCompression>; MAX_ENABLED_ENCODINGS],
 }
 
 impl EnabledCompressionEncodings {
     /// Enable a [`CompressionEncoding`].
     ///
-    /// Adds the new encoding to the end of the encoding list.
-    pub fn enable(&mut self, encoding: CompressionEncoding) {
+    /// Adds the new encoding to the end of the encoding list. Enabling an
+    /// encoding that is already enabled only replaces its [`SendCompression`]
+    /// settings.
+    ///
+    /// At most 8 encodings can be enabled, further ones are ignored with a
+    /// warning.
+    pub fn enable(&mut self, compression: impl Into<SendCompression>) {
+        let compression = compression.into();
+
         for e in self.inner.iter_mut() {
             match e {
-                Some(e) if *e == encoding => return,
+                Some(e) if e.encoding == compression.encoding => {
+                    *e = compression;
+                    return;
+                }
                 None => {
-                    *e = Some(encoding);
+                    *e = Some(compression);
                     return;
                 }
                 _ => continue,
             }
         }
+
+        tracing::warn!(
+            "ignoring compression encoding `{}`, at most {MAX_ENABLED_ENCODINGS} encodings can be enabled",
+            compression.encoding.as_str()
+        );
     }
 
     /// Remove the last [`CompressionEncoding`].
@@ -43,12 +65,13 @@ impl EnabledCompressionEncodings {
             .rev()
             .find(|entry| entry.is_some())?
             .take()
+            .map(|compression| compression.encoding)
     }
 
     pub(crate) fn into_accept_encoding_header_value(self) -> Option<http::HeaderValue> {
         let mut value = BytesMut::new();
-        for encoding in self.inner.into_iter().flatten() {
-            value.put_slice(encoding.as_str().as_bytes());
+        for compression in self.inner.into_iter().flatten() {
+            value.put_slice(compression.encoding.as_str().as_bytes());
             value.put_u8(b',');
         }
 
@@ -62,23 +85,224 @@ impl EnabledCompressionEncodings {
 
     /// Check if a [`CompressionEncoding`] is enabled.
     pub fn is_enabled(&self, encoding: CompressionEncoding) -> bool {
-        self.inner.contains(&Some(encoding))
+        self.get(encoding).is_some()
     }
 
     /// Check if any [`CompressionEncoding`]s are enabled.
     pub fn is_empty(&self) -> bool {
         self.inner.iter().all(|e| e.is_none())
     }
+
+    /// Iterate over the enabled encodings, in order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = SendCompression> + '_ {
+        self.inner.iter().flatten().copied()
+    }
+
+    /// Get the settings an enabled encoding was enabled with.
+    fn get(&self, encoding: CompressionEncoding) -> Option<SendCompression> {
+        self.iter()
+            .find(|compression| compression.encoding == encoding)
+    }
+}
+
+/// How hard a [`CompressionEncoding`] compresses messages.
+///
+/// Levels are mapped onto the range of levels each algorithm supports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompressionLevel {
+    /// The fastest level of the algorithm.
+    Fastest,
+    /// The default level of the algorithm.
+    #[default]
+    Default,
+    /// The level compressing the most, usually slowly.
+    Best,
+    /// A level specific to the algorithm.
+    ///
+    /// Values outside of the range the algorithm supports are clamped.
+    Precise(i32),
+}
+
+/// Configures how messages are compressed when sent.
+///
+/// A [`CompressionEncoding`] converts into a `SendCompression` that
+/// compresses every message at the default level of the algorithm. The
+/// level and a minimum size of compressed messages can be set as well:
+///
+/// ```
+/// # #[cfg(feature = "gzip")] {
+/// use tonic::codec::{CompressionEncoding, CompressionLevel};
+///
+/// let compression = CompressionEncoding::Gzip
+///     .level(CompressionLevel::Best)
+///     .min_message_size(1024);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SendCompression {
+    encoding: CompressionEncoding,
+    level: CompressionLevel,
+    min_message_size: usize,
+}
+
+impl SendCompression {
+    /// Compress messages with `encoding`.
+    pub fn new(encoding: CompressionEncoding) -> Self {
+        Self {
+            encoding,
+            level: CompressionLevel::Default,
+            min_message_size: 0,
+        }
+    }
+
+    /// Set the compression level.
+    pub fn level(self, level: CompressionLevel) -> Self {
+        SendCompression { level, ..self }
+    }
+
+    /// Send messages smaller than `min_message_size` bytes uncompressed.
+    ///
+    /// Compressing small messages often makes them larger and costs more
+    /// CPU time than it saves. Defaults to 0, which compresses every
+    /// message.
+    pub fn min_message_size(self, min_message_size: usize) -> Self {
+        SendCompression {
+            min_message_size,
+            ..self
+        }
+    }
+
+    /// The encoding messages are compressed with.
+    pub fn encoding(&self) -> CompressionEncoding {
+        self.encoding
+    }
+
+    /// Based on the `grpc-accept-encoding` header, pick an encoding to use.
+    pub(crate) fn from_accept_encoding_header(
+        map: &http::HeaderMap,
+        enabled_encodings: EnabledCompressionEncodings,
+    ) -> Option<Self> {
+        if enabled_encodings.is_empty() {
+            return None;
+        }
+
+        let header_value = map.get(ACCEPT_ENCODING_HEADER)?;
+        let header_value_str = header_value.to_str().ok()?;
+
+        split_by_comma(header_value_str).find_map(|value| {
+            CompressionEncoding::from_name(value)
+                .and_then(|encoding| enabled_encodings.get(encoding))
+        })
+    }
+
+    /// Returns `true` if a message of `len` bytes should be compressed.
+    pub(crate) fn compresses(&self, len: usize) -> bool {
+        len >= self.min_message_size
+    }
+
+    pub(crate) fn settings(&self, buffer_growth_interval: usize) -> CompressionSettings {
+        CompressionSettings {
+            encoding: self.encoding,
+            level: self.level,
+            buffer_growth_interval,
+        }
+    }
+}
+
+impl From<CompressionEncoding> for SendCompression {
+    fn from(encoding: CompressionEncoding) -> Self {
+        Self::new(encoding)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) struct CompressionSettings {
     pub(crate) encoding: CompressionEncoding,
+    pub(crate) level: CompressionLevel,
     /// buffer_growth_interval controls memory growth for internal buffers to balance resizing cost against memory waste.
     /// The default buffer growth interval is 8 kilobytes.
     pub(crate) buffer_growth_interval: usize,
 }
 
+/// A compression algorithm that can be used as a [`CompressionEncoding`].
+///
+/// Implement this trait to use compression algorithms tonic does not
+/// support out of the box, and register the implementation with
+/// [`CompressionEncoding::register`]. The returned encoding is enabled like
+/// any built-in encoding, and is negotiated through the `grpc-encoding` and
+/// `grpc-accept-encoding` headers using [`Compressor::name`].
+///
+/// ```
+/// use std::io::{self, Write};
+/// use tonic::codec::{CompressionEncoding, Compressor};
+///
+/// /// Stores messages as they are.
+/// struct Stored;
+///
+/// impl Compressor for Stored {
+///     fn name(&self) -> &'static str {
+///         "x-copy"
+///     }
+///
+///     fn compress(&self, input: &[u8], output: &mut dyn Write) -> io::Result<()> {
+///         output.write_all(input)
+///     }
+///
+///     fn decompress(&self, input: &[u8], output: &mut dyn Write) -> io::Result<()> {
+///         output.write_all(input)
+///     }
+/// }
+///
+/// let encoding = CompressionEncoding::register(Stored);
+/// assert_eq!(encoding.to_string(), "x-copy");
+/// ```
+pub trait Compressor: Send + Sync + 'static {
+    /// The name of the encoding, as used in the `grpc-encoding` header.
+    fn name(&self) -> &'static str;
+
+    /// Compress all of `input` into `output`.
+    fn compress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()>;
+
+    /// Compress all of `input` into `output` at `level`.
+    ///
+    /// The default implementation ignores `level` and calls
+    /// [`Compressor::compress`].
+    fn compress_with_level(
+        &self,
+        input: &[u8],
+        level: CompressionLevel,
+        output: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let _ = level;
+        self.compress(input, output)
+    }
+
+    /// Decompress all of `input` into `output`.
+    fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()>;
+}
+
+/// The compressors registered with [`CompressionEncoding::register`],
+/// indexed by [`CustomEncoding::index`].
+static COMPRESSORS: RwLock<Vec<Arc<dyn Compressor>>> = RwLock::new(Vec::new());
+
+/// A compression encoding registered with [`CompressionEncoding::register`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CustomEncoding {
+    index: usize,
+    name: &'static str,
+}
+
+impl CustomEncoding {
+    /// The name of the encoding.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn compressor(self) -> Arc<dyn Compressor> {
+        COMPRESSORS.read().unwrap()[self.index].clone()
+    }
+}
+
 /// The compression encodings Tonic supports.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[non_exhaustive]
@@ -92,6 +316,8 @@ pub enum CompressionEncoding {
     #[allow(missing_docs)]
     #[cfg(feature = "zstd")]
     Zstd,
+    /// An encoding registered with [`CompressionEncoding::register`].
+    Custom(CustomEncoding),
 }
 
 impl CompressionEncoding {
@@ -104,27 +330,60 @@ impl CompressionEncoding {
         CompressionEncoding::Zstd,
     ];
 
-    /// Based on the `grpc-accept-encoding` header, pick an encoding to use.
-    pub(crate) fn from_accept_encoding_header(
-        map: &http::HeaderMap,
-        enabled_encodings: EnabledCompressionEncodings,
-    ) -> Option<Self> {
-        if enabled_encodings.is_empty() {
-            return None;
-        }
+    /// Register a [`Compressor`] and return the encoding that uses it.
+    ///
+    /// Registering a compressor with the name of an already registered one
+    /// replaces the previous compressor, and returns the same encoding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name of the compressor is `identity`, the name of a
+    /// built-in encoding, or not a valid header token.
+    pub fn register(compressor: impl Compressor) -> Self {
+        let name = compressor.name();
+
+        assert!(
+            !name.is_empty() && name.bytes().all(is_token_byte),
+            "compression encoding name `{name}` is not a valid token"
+        );
+        assert!(
+            name != "identity" && !Self::ENCODINGS.iter().any(|e| e.as_str() == name),
+            "compression encoding `{name}` is reserved"
+        );
 
-        let header_value = map.get(ACCEPT_ENCODING_HEADER)?;
-        let header_value_str = header_value.to_str().ok()?;
+        let mut compressors = COMPRESSORS.write().unwrap();
+        let index = match compressors.iter().position(|c| c.name() == name) {
+            Some(index) => {
+                compressors[index] = Arc::new(compressor);
+                index
+            }
+            None => {
+                compressors.push(Arc::new(compressor));
+                compressors.len() - 1
+            }
+        };
 
-        split_by_comma(header_value_str).find_map(|value| match value {
-            #[cfg(feature = "gzip")]
-            "gzip" => Some(CompressionEncoding::Gzip),
-            #[cfg(feature = "deflate")]
-            "deflate" => Some(CompressionEncoding::Deflate),
-            #[cfg(feature = "zstd")]
-            "zstd" => Some(CompressionEncoding::Zstd),
-            _ => None,
-        })
+        CompressionEncoding::Custom(CustomEncoding { index, name })
+    }
+
+    /// Look up an encoding by its name.
+    fn from_name(name: &str) -> Option<Self> {
+        if let Some(encoding) = Self::ENCODINGS.iter().find(|e| e.as_str() == name) {
+            return Some(*encoding);
+        }
+
+        COMPRESSORS
+            .read()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .find(|(_, c)| c.name() == name)
+            .map(|(index, c)| {
+                CompressionEncoding::Custom(CustomEncoding {
+                    index,
+                    name: c.name(),
+                })
+            })
     }
 
     /// Get the value of `grpc-encoding` header. Returns an error if the encoding isn't supported.
@@ -136,21 +395,16 @@ impl CompressionEncoding {
             return Ok(None);
         };
 
-        match header_value.as_bytes() {
-            #[cfg(feature = "gzip")]
-            b"gzip" if enabled_encodings.is_enabled(CompressionEncoding::Gzip) => {
-                Ok(Some(CompressionEncoding::Gzip))
-            }
-            #[cfg(feature = "deflate")]
-            b"deflate" if enabled_encodings.is_enabled(CompressionEncoding::Deflate) => {
-                Ok(Some(CompressionEncoding::Deflate))
-            }
-            #[cfg(feature = "zstd")]
-            b"zstd" if enabled_encodings.is_enabled(CompressionEncoding::Zstd) => {
-                Ok(Some(CompressionEncoding::Zstd))
-            }
-            b"identity" => Ok(None),
-            other => {
+        let encoding = header_value
+            .to_str()
+            .ok()
+            .and_then(Self::from_name)
+            .filter(|encoding| enabled_encodings.is_enabled(*encoding));
+
+        match (encoding, header_value.as_bytes()) {
+            (Some(encoding), _) => Ok(Some(encoding)),
+            (None, b"identity") => Ok(None),
+            (None, other) => {
                 let other = match std::str::from_utf8(other) {
                     Ok(s) => Cow::Borrowed(s),
                     Err(_) => Cow::Owned(format!("{other:?}")),
@@ -181,13 +435,24 @@ impl CompressionEncoding {
             CompressionEncoding::Deflate => "deflate",
             #[cfg(feature = "zstd")]
             CompressionEncoding::Zstd => "zstd",
+            CompressionEncoding::Custom(custom) => custom.name,
         }
     }
 
-    #[cfg(any(feature = "gzip", feature = "deflate", feature = "zstd"))]
     pub(crate) fn into_header_value(self) -> http::HeaderValue {
         http::HeaderValue::from_static(self.as_str())
     }
+
+    /// Compress messages with this encoding at `level`.
+    pub fn level(self, level: CompressionLevel) -> SendCompression {
+        SendCompression::new(self).level(level)
+    }
+
+    /// Compress messages of at least `min_message_size` bytes with this
+    /// encoding, sending smaller ones uncompressed.
+    pub fn min_message_size(self, min_message_size: usize) -> SendCompression {
+        SendCompression::new(self).min_message_size(min_message_size)
+    }
 }
 
 impl fmt::Display for CompressionEncoding {
@@ -200,9 +465,34 @@ fn split_by_comma(s: &str) -> impl Iterator<Item = &str> {
     s.split(',').map(|s| s.trim())
 }
 
+/// Returns `true` if `b` may appear in an HTTP token (RFC 9110).
+fn is_token_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+#[cfg(any(feature = "gzip", feature = "deflate"))]
+fn flate2_level(level: CompressionLevel) -> flate2::Compression {
+    match level {
+        CompressionLevel::Fastest => flate2::Compression::fast(),
+        CompressionLevel::Default => flate2::Compression::new(6),
+        CompressionLevel::Best => flate2::Compression::best(),
+        CompressionLevel::Precise(level) => flate2::Compression::new(level.clamp(0, 9) as u32),
+    }
+}
+
+#[cfg(feature = "zstd")]
+pub(crate) fn zstd_level(level: CompressionLevel) -> i32 {
+    let range = zstd::compression_level_range();
+    match level {
+        CompressionLevel::Fastest => 1,
+        CompressionLevel::Default => zstd::DEFAULT_COMPRESSION_LEVEL,
+        CompressionLevel::Best => *range.end(),
+        CompressionLevel::Precise(level) => level.clamp(*range.start(), *range.end()),
+    }
+}
+
 /// Compress `len` bytes from `decompressed_buf` into `out_buf`.
 /// buffer_size_increment is a hint to control the growth of out_buf versus the cost of resizing it.
-#[allow(unused_variables, unreachable_code)]
 pub(crate) fn compress(
     settings: CompressionSettings,
     decompressed_buf: &mut BytesMut,
@@ -213,36 +503,33 @@ pub(crate) fn compress(
     let capacity = ((len / buffer_growth_interval) + 1) * buffer_growth_interval;
     out_buf.reserve(capacity);
 
-    #[cfg(any(feature = "gzip", feature = "deflate", feature = "zstd"))]
     let mut out_writer = out_buf.writer();
 
     match settings.encoding {
         #[cfg(feature = "gzip")]
         CompressionEncoding::Gzip => {
-            let mut gzip_encoder = GzEncoder::new(
-                &decompressed_buf[0..len],
-                // FIXME: support customizing the compression level
-                flate2::Compression::new(6),
-            );
+            let mut gzip_encoder =
+                GzEncoder::new(&decompressed_buf[0..len], flate2_level(settings.level));
             std::io::copy(&mut gzip_encoder, &mut out_writer)?;
         }
         #[cfg(feature = "deflate")]
         CompressionEncoding::Deflate => {
-            let mut deflate_encoder = ZlibEncoder::new(
-                &decompressed_buf[0..len],
-                // FIXME: support customizing the compression level
-                flate2::Compression::new(6),
-            );
+            let mut deflate_encoder =
+                ZlibEncoder::new(&decompressed_buf[0..len], flate2_level(settings.level));
             std::io::copy(&mut deflate_encoder, &mut out_writer)?;
         }
         #[cfg(feature = "zstd")]
         CompressionEncoding::Zstd => {
-            let mut zstd_encoder = Encoder::new(
+            let mut zstd_encoder =
+                Encoder::new(&decompressed_buf[0..len], zstd_level(settings.level))?;
+            std::io::copy(&mut zstd_encoder, &mut out_writer)?;
+        }
+        CompressionEncoding::Custom(custom) => {
+            custom.compressor().compress_with_level(
                 &decompressed_buf[0..len],
-                // FIXME: support customizing the compression level
-                zstd::DEFAULT_COMPRESSION_LEVEL,
+                settings.level,
+                &mut out_writer,
             )?;
-            std::io::copy(&mut zstd_encoder, &mut out_writer)?;
         }
     }
 
@@ -251,16 +538,14 @@ pub(crate) fn compress(
     Ok(())
 }
 
-/// Decompress `len` bytes from `compressed_buf` into `out_buf`.
-#[allow(unused_variables, unreachable_code)]
+/// Decompress `compressed_buf` into `out_buf`.
 pub(crate) fn decompress(
     settings: CompressionSettings,
-    compressed_buf: &mut BytesMut,
+    compressed_buf: &[u8],
     mut out_buf: bytes::buf::Limit<&mut BytesMut>,
-    len: usize,
 ) -> Result<(), std::io::Error> {
     let buffer_growth_interval = settings.buffer_growth_interval;
-    let estimate_decompressed_len = len * 2;
+    let estimate_decompressed_len = compressed_buf.len() * 2;
     let capacity = std::cmp::min(
         bytes::buf::Limit::limit(&out_buf),
         ((estimate_decompressed_len / buffer_growth_interval) + 1) * buffer_growth_interval,
@@ -268,29 +553,31 @@ pub(crate) fn decompress(
 
     out_buf.get_mut().reserve(capacity);
 
-    #[cfg(any(feature = "gzip", feature = "deflate", feature = "zstd"))]
     let mut out_writer = out_buf.writer();
 
     match settings.encoding {
         #[cfg(feature = "gzip")]
         CompressionEncoding::Gzip => {
-            let mut gzip_decoder = GzDecoder::new(&compressed_buf[0..len]);
+            let mut gzip_decoder = GzDecoder::new(compressed_buf);
             std::io::copy(&mut gzip_decoder, &mut out_writer)?;
         }
         #[cfg(feature = "deflate")]
         CompressionEncoding::Deflate => {
-            let mut deflate_decoder = ZlibDecoder::new(&compressed_buf[0..len]);
+            let mut deflate_decoder = ZlibDecoder::new(compressed_buf);
             std::io::copy(&mut deflate_decoder, &mut out_writer)?;
         }
         #[cfg(feature = "zstd")]
         CompressionEncoding::Zstd => {
-            let mut zstd_decoder = Decoder::new(&compressed_buf[0..len])?;
+            let mut zstd_decoder = Decoder::new(compressed_buf)?;
             std::io::copy(&mut zstd_decoder, &mut out_writer)?;
         }
+        CompressionEncoding::Custom(custom) => {
+            custom
+                .compressor()
+                .decompress(compressed_buf, &mut out_writer)?;
+        }
     }
 
-    compressed_buf.advance(len);
-
     Ok(())
 }
 
@@ -309,11 +596,111 @@ pub enum SingleMessageCompressionOverride {
 
 #[cfg(test)]
 mod tests {
-    #[cfg(any(feature = "gzip", feature = "deflate", feature = "zstd"))]
     use http::HeaderValue;
 
     use super::*;
 
+    fn enabled(slots: [Option<CompressionEncoding>; 3]) -> EnabledCompressionEncodings {
+        let mut encodings = EnabledCompressionEncodings::default();
+        for (slot, encoding) in encodings.inner.iter_mut().zip(slots) {
+            *slot = encoding.map(SendCompression::new);
+        }
+        encodings
+    }
+
+    /// Reverses the bytes of every message.
+    struct Reverse(&'static str);
+
+    impl Compressor for Reverse {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn compress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+            output.write_all(&input.iter().rev().copied().collect::<Vec<_>>())
+        }
+
+        fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+            self.compress(input, output)
+        }
+    }
+
+    #[test]
+    fn custom_encoding_round_trip() {
+        let settings = CompressionSettings {
+            encoding: CompressionEncoding::register(Reverse("x-reverse")),
+            level: CompressionLevel::Default,
+            buffer_growth_interval: 8 * 1024,
+        };
+
+        let mut decompressed = BytesMut::from(&b"hello"[..]);
+        let mut compressed = BytesMut::new();
+        compress(settings, &mut decompressed, &mut compressed, 5).unwrap();
+        assert_eq!(&compressed[..], b"olleh");
+
+        let mut out = BytesMut::new();
+        decompress(settings, &compressed, (&mut out).limit(5)).unwrap();
+        assert_eq!(&out[..], b"hello");
+    }
+
+    #[test]
+    fn extra_encodings_are_ignored() {
+        const NAMES: [&str; MAX_ENABLED_ENCODINGS + 1] = [
+            "x-many-0", "x-many-1", "x-many-2", "x-many-3", "x-many-4", "x-many-5", "x-many-6",
+            "x-many-7", "x-many-8",
+        ];
+        let encodings = NAMES.map(|name| CompressionEncoding::register(Reverse(name)));
+
+        let mut enabled = EnabledCompressionEncodings::default();
+        for encoding in encodings {
+            enabled.enable(encoding);
+        }
+
+        assert!(enabled.is_enabled(encodings[MAX_ENABLED_ENCODINGS - 1]));
+        assert!(!enabled.is_enabled(encodings[MAX_ENABLED_ENCODINGS]));
+    }
+
+    #[test]
+    fn custom_encoding_negotiation() {
+        let encoding = CompressionEncoding::register(Reverse("x-negotiated"));
+        let mut enabled = EnabledCompressionEncodings::default();
+
+        let mut headers = http::HeaderMap::new();
+        headers.insert(
+            ACCEPT_ENCODING_HEADER,
+            HeaderValue::from_static("x-unknown, x-negotiated"),
+        );
+        headers.insert(ENCODING_HEADER, HeaderValue::from_static("x-negotiated"));
+
+        enabled.enable(CompressionEncoding::register(Reverse("x-other")));
+        assert_eq!(
+            SendCompression::from_accept_encoding_header(&headers, enabled),
+            None
+        );
+        let status = CompressionEncoding::from_encoding_header(&headers, enabled).unwrap_err();
+        assert_eq!(status.code(), crate::Code::Unimplemented);
+
+        enabled.enable(encoding);
+        assert_eq!(
+            SendCompression::from_accept_encoding_header(&headers, enabled),
+            Some(SendCompression::new(encoding))
+        );
+        assert_eq!(
+            CompressionEncoding::from_encoding_header(&headers, enabled).unwrap(),
+            Some(encoding)
+        );
+        assert_eq!(
+            enabled.into_accept_encoding_header_value().unwrap(),
+            HeaderValue::from_static("x-other,x-negotiated,identity")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "reserved")]
+    fn identity_cannot_be_registered() {
+        CompressionEncoding::register(Reverse("identity"));
+    }
+
     #[test]
     fn convert_none_into_header_value() {
         let encodings = EnabledCompressionEncodings::default();
@@ -326,15 +713,11 @@ mod tests {
     fn convert_gzip_into_header_value() {
         const GZIP: HeaderValue = HeaderValue::from_static("gzip,identity");
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [Some(CompressionEncoding::Gzip), None, None],
-        };
+        let encodings = enabled([Some(CompressionEncoding::Gzip), None, None]);
 
         assert_eq!(encodings.into_accept_encoding_header_value().unwrap(), GZIP);
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [None, None, Some(CompressionEncoding::Gzip)],
-        };
+        let encodings = enabled([None, None, Some(CompressionEncoding::Gzip)]);
 
         assert_eq!(encodings.into_accept_encoding_header_value().unwrap(), GZIP);
     }
@@ -344,15 +727,11 @@ mod tests {
     fn convert_zstd_into_header_value() {
         const ZSTD: HeaderValue = HeaderValue::from_static("zstd,identity");
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [Some(CompressionEncoding::Zstd), None, None],
-        };
+        let encodings = enabled([Some(CompressionEncoding::Zstd), None, None]);
 
         assert_eq!(encodings.into_accept_encoding_header_value().unwrap(), ZSTD);
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [None, None, Some(CompressionEncoding::Zstd)],
-        };
+        let encodings = enabled([None, None, Some(CompressionEncoding::Zstd)]);
 
         assert_eq!(encodings.into_accept_encoding_header_value().unwrap(), ZSTD);
     }
@@ -360,26 +739,22 @@ mod tests {
     #[test]
     #[cfg(all(feature = "gzip", feature = "deflate", feature = "zstd"))]
     fn convert_compression_encodings_into_header_value() {
-        let encodings = EnabledCompressionEncodings {
-            inner: [
-                Some(CompressionEncoding::Gzip),
-                Some(CompressionEncoding::Deflate),
-                Some(CompressionEncoding::Zstd),
-            ],
-        };
+        let encodings = enabled([
+            Some(CompressionEncoding::Gzip),
+            Some(CompressionEncoding::Deflate),
+            Some(CompressionEncoding::Zstd),
+        ]);
 
         assert_eq!(
             encodings.into_accept_encoding_header_value().unwrap(),
             HeaderValue::from_static("gzip,deflate,zstd,identity"),
         );
 
-        let encodings = EnabledCompressionEncodings {
-            inner: [
-                Some(CompressionEncoding::Zstd),
-                Some(CompressionEncoding::Deflate),
-                Some(CompressionEncoding::Gzip),
-            ],
-        };
+        let encodings = enabled([
+            Some(CompressionEncoding::Zstd),
+            Some(CompressionEncoding::Deflate),
+            Some(CompressionEncoding::Gzip),
+        ]);
 
         assert_eq!(
             encodings.into_accept_encoding_header_value().unwrap(),
@@ -1,12 +1,14 @@
-use super::compression::{CompressionEncoding, CompressionSettings, decompress};
+use super::buffer::BufList;
+use super::compression::{CompressionEncoding, CompressionLevel, CompressionSettings, decompress};
 use super::{BufferSettings, DEFAULT_MAX_RECV_MESSAGE_SIZE, DecodeBuf, Decoder, HEADER_SIZE};
 use crate::{Code, Status, body::Body, metadata::MetadataMap};
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use http::{HeaderMap, StatusCode};
 use http_body::Body as HttpBody;
 use http_body_util::BodyExt;
 use std::{
-    fmt, future,
+    fmt,
+    future::{self, Future},
     pin::Pin,
     task::ready,
     task::{Context, Poll},
@@ -22,13 +24,22 @@ use tracing::{debug, trace};
 pub struct Streaming<T> {
     decoder: SyncWrapper<Box<dyn Decoder<Item = T, Error = Status> + Send + 'static>>,
     inner: StreamingInner,
+    intercept: Option<Intercept<T>>,
+}
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
+
+/// Runs the message hooks of async interceptors on each decoded message.
+struct Intercept<T> {
+    hook: Box<dyn Fn(T) -> BoxFuture<Result<T, Status>> + Send + Sync>,
+    pending: Option<SyncWrapper<BoxFuture<Result<T, Status>>>>,
 }
 
 struct StreamingInner {
     body: SyncWrapper<Body>,
     state: State,
     direction: Direction,
-    buf: BytesMut,
+    buf: RecvBuf,
     trailers: Option<HeaderMap>,
     decompress_buf: BytesMut,
     encoding: Option<CompressionEncoding>,
@@ -47,6 +58,59 @@ enum State {
     Error(Option<Status>),
 }
 
+/// Where received data frames are kept until a whole message arrived.
+enum RecvBuf {
+    /// Frames are copied into one buffer.
+    Contiguous(BytesMut),
+    /// Frames are kept as they are, see [`BufferSettings::zero_copy_decode`].
+    Frames(BufList),
+}
+
+impl RecvBuf {
+    fn push(&mut self, data: Bytes) {
+        match self {
+            RecvBuf::Contiguous(buf) => buf.put(data),
+            RecvBuf::Frames(frames) => frames.push(data),
+        }
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        if let RecvBuf::Contiguous(buf) = self {
+            buf.reserve(additional);
+        }
+    }
+}
+
+impl Buf for RecvBuf {
+    fn remaining(&self) -> usize {
+        match self {
+            RecvBuf::Contiguous(buf) => buf.remaining(),
+            RecvBuf::Frames(frames) => frames.remaining(),
+        }
+    }
+
+    fn chunk(&self) -> &[u8] {
+        match self {
+            RecvBuf::Contiguous(buf) => buf.chunk(),
+            RecvBuf::Frames(frames) => frames.chunk(),
+        }
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        match self {
+            RecvBuf::Contiguous(buf) => buf.advance(cnt),
+            RecvBuf::Frames(frames) => frames.advance(cnt),
+        }
+    }
+
+    fn copy_to_bytes(&mut self, len: usize) -> Bytes {
+        match self {
+            RecvBuf::Contiguous(buf) => buf.copy_to_bytes(len),
+            RecvBuf::Frames(frames) => frames.copy_to_bytes(len),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 enum Direction {
     Request,
@@ -122,7 +186,13 @@ impl<T> Streaming<T> {
         B::Error: Into<crate::BoxError>,
         D: Decoder<Item = T, Error = Status> + Send + 'static,
     {
-        let buffer_size = decoder.buffer_settings().buffer_size;
+        let buffer_settings = decoder.buffer_settings();
+        let buf = if buffer_settings.zero_copy_decode {
+            RecvBuf::Frames(BufList::default())
+        } else {
+            RecvBuf::Contiguous(BytesMut::with_capacity(buffer_settings.buffer_size))
+        };
+
         Self {
             decoder: SyncWrapper::new(Box::new(decoder)),
             inner: StreamingInner {
@@ -134,14 +204,26 @@ impl<T> Streaming<T> {
                 )),
                 state: State::ReadHeader,
                 direction,
-                buf: BytesMut::with_capacity(buffer_size),
+                buf,
                 trailers: None,
                 decompress_buf: BytesMut::new(),
                 encoding,
                 max_message_size,
             },
+            intercept: None,
         }
     }
+
+    /// Pass each decoded message through `hook` before yielding it.
+    pub(crate) fn intercept<F>(&mut self, hook: F)
+    where
+        F: Fn(T) -> BoxFuture<Result<T, Status>> + Send + Sync + 'static,
+    {
+        self.intercept = Some(Intercept {
+            hook: Box::new(hook),
+            pending: None,
+        });
+    }
 }
 
 impl StreamingInner {
@@ -207,7 +289,7 @@ impl StreamingInner {
         if let State::ReadBody { len, compression } = self.state {
             // if we haven't read enough of the message then return and keep
             // reading
-            if self.buf.remaining() < len || self.buf.len() < len {
+            if self.buf.remaining() < len {
                 return Ok(None);
             }
 
@@ -217,15 +299,16 @@ impl StreamingInner {
                     .max_message_size
                     .unwrap_or(DEFAULT_MAX_RECV_MESSAGE_SIZE);
                 let limited_out_buf = (&mut self.decompress_buf).limit(limit);
+                let compressed = self.buf.copy_to_bytes(len);
 
                 if let Err(err) = decompress(
                     CompressionSettings {
                         encoding,
+                        level: CompressionLevel::Default,
                         buffer_growth_interval: buffer_settings.buffer_size,
                     },
-                    &mut self.buf,
+                    &compressed,
                     limited_out_buf,
-                    len,
                 ) {
                     if matches!(err.kind(), std::io::ErrorKind::WriteZero) {
                         return Err(Status::resource_exhausted(format!(
@@ -278,7 +361,7 @@ impl StreamingInner {
         };
 
         Poll::Ready(if frame.is_data() {
-            self.buf.put(frame.into_data().unwrap());
+            self.buf.push(frame.into_data().unwrap());
             Ok(Some(()))
         } else if frame.is_trailers() {
             if let Some(trailers) = &mut self.trailers {
@@ -399,6 +482,35 @@ impl<T> Stream for Streaming<T> {
     type Item = Result<T, Status>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.intercept.is_none() {
+            return self.poll_message(cx);
+        }
+
+        loop {
+            let intercept = self.intercept.as_mut().unwrap();
+            if let Some(pending) = &mut intercept.pending {
+                let result = ready!(pending.get_mut().as_mut().poll(cx));
+                intercept.pending = None;
+                if result.is_err() {
+                    // The call fails with the status of the interceptor.
+                    self.inner.state = State::Error(None);
+                }
+                return Poll::Ready(Some(result));
+            }
+
+            match ready!(self.poll_message(cx)) {
+                Some(Ok(message)) => {
+                    let intercept = self.intercept.as_mut().unwrap();
+                    intercept.pending = Some(SyncWrapper::new((intercept.hook)(message)));
+                }
+                item => return Poll::Ready(item),
+            }
+        }
+    }
+}
+
+impl<T> Streaming<T> {
+    fn poll_message(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, Status>>> {
         loop {
             // When the stream encounters an error yield that error once and then on subsequent
             // calls to poll_next return Poll::Ready(None) indicating that the stream has been
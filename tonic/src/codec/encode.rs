@@ -1,6 +1,7 @@
 use super::compression::{
-    CompressionEncoding, CompressionSettings, SingleMessageCompressionOverride, compress,
+    CompressionEncoding, SendCompression, SingleMessageCompressionOverride, compress,
 };
+use super::pool::PooledBuf;
 use super::{BufferSettings, DEFAULT_MAX_SEND_MESSAGE_SIZE, EncodeBuf, Encoder, HEADER_SIZE};
 use crate::Status;
 use bytes::{BufMut, Bytes, BytesMut};
@@ -24,10 +25,10 @@ struct EncodedBytes<T, U> {
     #[pin]
     source: Fuse<U>,
     encoder: T,
-    compression_encoding: Option<CompressionEncoding>,
+    compression: Option<SendCompression>,
     max_message_size: Option<usize>,
-    buf: BytesMut,
-    uncompression_buf: BytesMut,
+    buf: PooledBuf,
+    uncompression_buf: PooledBuf,
     error: Option<Status>,
 }
 
@@ -35,30 +36,29 @@ impl<T: Encoder, U: Stream> EncodedBytes<T, U> {
     fn new(
         encoder: T,
         source: U,
-        compression_encoding: Option<CompressionEncoding>,
+        compression: Option<SendCompression>,
         compression_override: SingleMessageCompressionOverride,
         max_message_size: Option<usize>,
     ) -> Self {
         let buffer_settings = encoder.buffer_settings();
-        let buf = BytesMut::with_capacity(buffer_settings.buffer_size);
+        let buf = PooledBuf::new(buffer_settings.pool, buffer_settings.buffer_size);
 
-        let compression_encoding =
-            if compression_override == SingleMessageCompressionOverride::Disable {
-                None
-            } else {
-                compression_encoding
-            };
+        let compression = if compression_override == SingleMessageCompressionOverride::Disable {
+            None
+        } else {
+            compression
+        };
 
-        let uncompression_buf = if compression_encoding.is_some() {
-            BytesMut::with_capacity(buffer_settings.buffer_size)
+        let uncompression_buf = if compression.is_some() {
+            PooledBuf::new(buffer_settings.pool, buffer_settings.buffer_size)
         } else {
-            BytesMut::new()
+            PooledBuf::new(None, 0)
         };
 
         Self {
             source: source.fuse(),
             encoder,
-            compression_encoding,
+            compression,
             max_message_size,
             buf,
             uncompression_buf,
@@ -78,12 +78,13 @@ where
         let EncodedBytesProj {
             mut source,
             encoder,
-            compression_encoding,
+            compression,
             max_message_size,
             buf,
             uncompression_buf,
             error,
         } = self.project();
+        let buf: &mut BytesMut = buf;
         let buffer_settings = encoder.buffer_settings();
 
         if let Some(status) = error.take() {
@@ -106,7 +107,7 @@ where
                         encoder,
                         buf,
                         uncompression_buf,
-                        *compression_encoding,
+                        *compression,
                         *max_message_size,
                         buffer_settings,
                         item,
@@ -134,7 +135,7 @@ fn encode_item<T>(
     encoder: &mut T,
     buf: &mut BytesMut,
     uncompression_buf: &mut BytesMut,
-    compression_encoding: Option<CompressionEncoding>,
+    compression: Option<SendCompression>,
     max_message_size: Option<usize>,
     buffer_settings: BufferSettings,
     item: T::Item,
@@ -149,7 +150,7 @@ where
         buf.advance_mut(HEADER_SIZE);
     }
 
-    if let Some(encoding) = compression_encoding {
+    let compressed = if let Some(compression) = compression {
         uncompression_buf.clear();
 
         encoder
@@ -158,28 +159,33 @@ where
 
         let uncompressed_len = uncompression_buf.len();
 
-        compress(
-            CompressionSettings {
-                encoding,
-                buffer_growth_interval: buffer_settings.buffer_size,
-            },
-            uncompression_buf,
-            buf,
-            uncompressed_len,
-        )
-        .map_err(|err| Status::internal(format!("Error compressing: {err}")))?;
+        if compression.compresses(uncompressed_len) {
+            compress(
+                compression.settings(buffer_settings.buffer_size),
+                uncompression_buf,
+                buf,
+                uncompressed_len,
+            )
+            .map_err(|err| Status::internal(format!("Error compressing: {err}")))?;
+            true
+        } else {
+            // Too small to be worth compressing, send it as is.
+            buf.extend_from_slice(uncompression_buf);
+            false
+        }
     } else {
         encoder
             .encode(item, &mut EncodeBuf::new(buf))
             .map_err(|err| Status::internal(format!("Error encoding: {err}")))?;
-    }
+        false
+    };
 
     // now that we know length, we can write the header
-    finish_encoding(compression_encoding, max_message_size, &mut buf[offset..])
+    finish_encoding(compressed, max_message_size, &mut buf[offset..])
 }
 
 fn finish_encoding(
-    compression_encoding: Option<CompressionEncoding>,
+    compressed: bool,
     max_message_size: Option<usize>,
     buf: &mut [u8],
 ) -> Result<(), Status> {
@@ -198,7 +204,7 @@ fn finish_encoding(
     }
     {
         let mut buf = &mut buf[..HEADER_SIZE];
-        buf.put_u8(compression_encoding.is_some() as u8);
+        buf.put_u8(compressed as u8);
         buf.put_u32(len as u32);
     }
 
@@ -235,12 +241,28 @@ impl<T: Encoder, U: Stream> EncodeBody<T, U> {
         source: U,
         compression_encoding: Option<CompressionEncoding>,
         max_message_size: Option<usize>,
+    ) -> Self {
+        Self::new_client_with_compression(
+            encoder,
+            source,
+            compression_encoding.map(SendCompression::new),
+            max_message_size,
+        )
+    }
+
+    /// Like [`EncodeBody::new_client`], compressing the messages with the
+    /// level and minimum message size of `compression`.
+    pub fn new_client_with_compression(
+        encoder: T,
+        source: U,
+        compression: Option<SendCompression>,
+        max_message_size: Option<usize>,
     ) -> Self {
         Self {
             inner: EncodedBytes::new(
                 encoder,
                 source,
-                compression_encoding,
+                compression,
                 SingleMessageCompressionOverride::default(),
                 max_message_size,
             ),
@@ -260,12 +282,30 @@ impl<T: Encoder, U: Stream> EncodeBody<T, U> {
         compression_encoding: Option<CompressionEncoding>,
         compression_override: SingleMessageCompressionOverride,
         max_message_size: Option<usize>,
+    ) -> Self {
+        Self::new_server_with_compression(
+            encoder,
+            source,
+            compression_encoding.map(SendCompression::new),
+            compression_override,
+            max_message_size,
+        )
+    }
+
+    /// Like [`EncodeBody::new_server`], compressing the messages with the
+    /// level and minimum message size of `compression`.
+    pub fn new_server_with_compression(
+        encoder: T,
+        source: U,
+        compression: Option<SendCompression>,
+        compression_override: SingleMessageCompressionOverride,
+        max_message_size: Option<usize>,
     ) -> Self {
         Self {
             inner: EncodedBytes::new(
                 encoder,
                 source,
-                compression_encoding,
+                compression,
                 compression_override,
                 max_message_size,
             ),
@@ -6,13 +6,22 @@ mod buffer;
 pub(crate) mod compression;
 mod decode;
 mod encode;
+mod pool;
+#[cfg(feature = "zstd")]
+mod zstd_dictionary;
 use crate::Status;
 use std::io;
 
 pub use self::buffer::{DecodeBuf, EncodeBuf};
-pub use self::compression::{CompressionEncoding, EnabledCompressionEncodings};
+pub use self::compression::{
+    CompressionEncoding, CompressionLevel, Compressor, CustomEncoding, EnabledCompressionEncodings,
+    SendCompression,
+};
 pub use self::decode::Streaming;
 pub use self::encode::EncodeBody;
+pub use self::pool::BufferPool;
+#[cfg(feature = "zstd")]
+pub use self::zstd_dictionary::ZstdDictionary;
 
 // Doc hidden since this is used in a test in another crate, we can expose this publically later
 // if we need it.
@@ -63,10 +72,16 @@ const DEFAULT_YIELD_THRESHOLD: usize = 32 * 1024;
 /// not affect the responsiveness of your streaming rpc (for reasonable
 /// sizes of yield threshold).
 /// Yield threshold defaults to 32 KiB.
+///
+/// Encode buffers are allocated for every call, unless a [`BufferPool`]
+/// is set, or was set as the default with [`BufferPool::set_default`], to
+/// recycle them across calls.
 #[derive(Clone, Copy, Debug)]
 pub struct BufferSettings {
     buffer_size: usize,
     yield_threshold: usize,
+    pool: Option<&'static BufferPool>,
+    zero_copy_decode: bool,
 }
 
 impl BufferSettings {
@@ -75,6 +90,36 @@ impl BufferSettings {
         Self {
             buffer_size,
             yield_threshold,
+            pool: BufferPool::default_pool(),
+            zero_copy_decode: false,
+        }
+    }
+
+    /// Take encode and compression buffers from `pool` instead of
+    /// allocating them for every call.
+    pub fn pool(self, pool: &'static BufferPool) -> Self {
+        Self {
+            pool: Some(pool),
+            ..self
+        }
+    }
+
+    /// Decode messages straight from the received data frames.
+    ///
+    /// By default the data frames of a message are copied into one buffer
+    /// before it is decoded. With this enabled the [`DecodeBuf`] is made of
+    /// the frames themselves: [`Buf::chunk`] may return only part of the
+    /// message, and [`Buf::copy_to_bytes`] returns reference-counted slices
+    /// of the frames instead of copies. This saves copying large `bytes`
+    /// fields, but only works with decoders that handle messages split
+    /// across several chunks.
+    ///
+    /// [`Buf::chunk`]: bytes::Buf::chunk
+    /// [`Buf::copy_to_bytes`]: bytes::Buf::copy_to_bytes
+    pub fn zero_copy_decode(self, enabled: bool) -> Self {
+        Self {
+            zero_copy_decode: enabled,
+            ..self
         }
     }
 }
@@ -84,6 +129,8 @@ impl Default for BufferSettings {
         Self {
             buffer_size: DEFAULT_CODEC_BUFFER_SIZE,
             yield_threshold: DEFAULT_YIELD_THRESHOLD,
+            pool: BufferPool::default_pool(),
+            zero_copy_decode: false,
         }
     }
 }
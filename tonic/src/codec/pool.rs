@@ -0,0 +1,264 @@
+use bytes::BytesMut;
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+static DEFAULT_POOL: OnceLock<&'static BufferPool> = OnceLock::new();
+
+/// A pool of buffers shared by the encoders of many calls.
+///
+/// Every call encoding messages allocates a buffer to encode them into, and
+/// another one when they are compressed. With a pool set through
+/// [`BufferSettings::pool`], these buffers are taken from the pool and given
+/// back once the call is done, which saves allocations when many short calls
+/// or streams are handled.
+///
+/// Pools are usually `static`, so that they can be shared by all codecs:
+///
+/// ```
+/// use tonic::codec::{BufferPool, BufferSettings};
+///
+/// static POOL: BufferPool = BufferPool::new(1024, 64 * 1024);
+///
+/// let settings = BufferSettings::default().pool(&POOL);
+/// ```
+///
+/// Codecs created by generated clients and servers use the default buffer
+/// settings, which take their buffers from the pool set with
+/// [`BufferPool::set_default`].
+///
+/// [`BufferSettings::pool`]: super::BufferSettings::pool
+pub struct BufferPool {
+    buffers: Mutex<Vec<BytesMut>>,
+    max_buffers: usize,
+    max_capacity: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl BufferPool {
+    /// Create a pool keeping up to `max_buffers` buffers.
+    ///
+    /// Buffers that grew larger than `max_capacity` bytes are not kept, so
+    /// that a few large messages do not pin a lot of memory.
+    pub const fn new(max_buffers: usize, max_capacity: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::new()),
+            max_buffers,
+            max_capacity,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Use this pool for all the [`BufferSettings`] created afterwards, unless
+    /// they are given another pool.
+    ///
+    /// This enables pooling for the codecs of generated clients and servers,
+    /// which are created with the default settings. Returns `false`, keeping
+    /// the previous pool, if a default pool was already set.
+    ///
+    /// ```
+    /// use tonic::codec::{BufferPool, BufferSettings};
+    ///
+    /// static POOL: BufferPool = BufferPool::new(1024, 64 * 1024);
+    ///
+    /// assert!(POOL.set_default());
+    /// ```
+    ///
+    /// [`BufferSettings`]: super::BufferSettings
+    pub fn set_default(&'static self) -> bool {
+        self.set_default_in(&DEFAULT_POOL)
+    }
+
+    fn set_default_in(&'static self, default: &OnceLock<&'static BufferPool>) -> bool {
+        default.set(self).is_ok()
+    }
+
+    /// The pool set with [`BufferPool::set_default`], if any.
+    pub(crate) fn default_pool() -> Option<&'static BufferPool> {
+        DEFAULT_POOL.get().copied()
+    }
+
+    /// The number of buffers that were taken from the pool.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of buffers that had to be allocated because the pool was
+    /// empty, or its buffers were still shared with messages being sent.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// The share of buffers taken from the pool, between 0 and 1.
+    ///
+    /// Returns 0 if no buffer was requested yet.
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits();
+        let total = hits + self.misses();
+
+        if total == 0 {
+            0.0
+        } else {
+            hits as f64 / total as f64
+        }
+    }
+
+    fn take(&self, capacity: usize) -> BytesMut {
+        let buf = self.buffers.lock().unwrap().pop();
+
+        // The messages split off a buffer may still be in flight, in which
+        // case it can't be reused without allocating.
+        if let Some(mut buf) = buf
+            && buf.try_reclaim(capacity)
+        {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return buf;
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        BytesMut::with_capacity(capacity)
+    }
+
+    fn give_back(&self, mut buf: BytesMut) {
+        if buf.capacity() == 0 || buf.capacity() > self.max_capacity {
+            return;
+        }
+
+        buf.clear();
+
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.len() < self.max_buffers {
+            buffers.push(buf);
+        }
+    }
+}
+
+impl fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("max_buffers", &self.max_buffers)
+            .field("max_capacity", &self.max_capacity)
+            .field("hits", &self.hits())
+            .field("misses", &self.misses())
+            .finish()
+    }
+}
+
+/// A buffer that goes back to its pool when dropped.
+#[derive(Debug)]
+pub(crate) struct PooledBuf {
+    buf: BytesMut,
+    pool: Option<&'static BufferPool>,
+}
+
+impl PooledBuf {
+    /// Take a buffer of at least `capacity` bytes from `pool`, or allocate
+    /// one if there is no pool.
+    pub(crate) fn new(pool: Option<&'static BufferPool>, capacity: usize) -> Self {
+        let buf = match pool {
+            Some(pool) => pool.take(capacity),
+            None => BytesMut::with_capacity(capacity),
+        };
+
+        Self { buf, pool }
+    }
+}
+
+impl Deref for PooledBuf {
+    type Target = BytesMut;
+
+    fn deref(&self) -> &BytesMut {
+        &self.buf
+    }
+}
+
+impl DerefMut for PooledBuf {
+    fn deref_mut(&mut self) -> &mut BytesMut {
+        &mut self.buf
+    }
+}
+
+impl Drop for PooledBuf {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool {
+            pool.give_back(std::mem::take(&mut self.buf));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffers_are_reused() {
+        static POOL: BufferPool = BufferPool::new(1, 1024);
+
+        let mut buf = PooledBuf::new(Some(&POOL), 64);
+        buf.extend_from_slice(b"hello");
+        drop(buf);
+
+        let buf = PooledBuf::new(Some(&POOL), 64);
+        assert!(buf.is_empty());
+        assert!(buf.capacity() >= 64);
+
+        // The pool is empty while the buffer is in use.
+        let _other = PooledBuf::new(Some(&POOL), 64);
+        assert_eq!(POOL.hits(), 1);
+        assert_eq!(POOL.misses(), 2);
+        assert!((POOL.hit_rate() - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn shared_buffers_are_not_hits() {
+        static POOL: BufferPool = BufferPool::new(1, 1024);
+
+        let send = || {
+            let mut buf = PooledBuf::new(Some(&POOL), 64);
+            buf.extend_from_slice(&[0; 32]);
+            buf.split().freeze()
+        };
+
+        // The message still holds the allocation of the pooled buffer.
+        let message = send();
+        drop(PooledBuf::new(Some(&POOL), 64));
+        assert_eq!(POOL.hits(), 0);
+        drop(message);
+
+        // Once the message is dropped, the allocation can be reused.
+        drop(send());
+        let hits = POOL.hits();
+        let buf = PooledBuf::new(Some(&POOL), 64);
+        assert!(buf.capacity() >= 64);
+        assert_eq!(POOL.hits(), hits + 1);
+    }
+
+    #[test]
+    fn default_pool() {
+        // A default of its own, since the process-wide one is shared by tests.
+        static DEFAULT: OnceLock<&'static BufferPool> = OnceLock::new();
+        static POOL: BufferPool = BufferPool::new(1, 1024);
+        static OTHER: BufferPool = BufferPool::new(1, 1024);
+
+        assert!(POOL.set_default_in(&DEFAULT));
+        assert!(!OTHER.set_default_in(&DEFAULT));
+        assert!(DEFAULT.get().is_some_and(|pool| std::ptr::eq(*pool, &POOL)));
+    }
+
+    #[test]
+    fn large_buffers_are_dropped() {
+        static POOL: BufferPool = BufferPool::new(4, 1024);
+
+        drop(PooledBuf::new(Some(&POOL), 4096));
+        drop(PooledBuf::new(Some(&POOL), 64));
+
+        assert_eq!(POOL.misses(), 2);
+    }
+}
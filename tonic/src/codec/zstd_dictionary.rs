@@ -0,0 +1,155 @@
+use super::compression::{CompressionLevel, Compressor, zstd_level};
+use std::{
+    fmt, io,
+    sync::{Arc, Mutex},
+};
+use zstd::{
+    dict::{DecoderDictionary, EncoderDictionary},
+    stream::read::{Decoder, Encoder},
+};
+
+/// A zstd [`Compressor`] using a pre-shared dictionary.
+///
+/// Small messages that share a lot of structure, like most protobuf
+/// messages of the same type, compress much better with a dictionary
+/// trained on samples of them. Both peers need the exact same dictionary,
+/// so each dictionary is registered as its own encoding with a name of its
+/// own, and is only used when the peer lists that name in its
+/// `grpc-accept-encoding` header.
+///
+/// ```
+/// use tonic::codec::{CompressionEncoding, ZstdDictionary};
+///
+/// # fn samples() -> Vec<Vec<u8>> {
+/// #     (0..1000u32)
+/// #         .map(|i| format!("{{\"id\":{i},\"name\":\"user-{}\",\"active\":true}}", i * 7).into_bytes())
+/// #         .collect()
+/// # }
+/// // Usually done once, with the dictionary shipped to clients and servers.
+/// let dictionary = ZstdDictionary::train(&samples(), 4 * 1024).unwrap();
+///
+/// // The name identifies this version of the dictionary.
+/// let encoding = CompressionEncoding::register(ZstdDictionary::new("zstd-users-v1", dictionary));
+/// ```
+pub struct ZstdDictionary {
+    name: &'static str,
+    dictionary: Vec<u8>,
+    decoder: DecoderDictionary<'static>,
+    /// Encoder dictionaries prepared for the levels used so far.
+    encoders: Mutex<Vec<(i32, Arc<EncoderDictionary<'static>>)>>,
+}
+
+impl ZstdDictionary {
+    /// Create a compressor for the encoding `name` using `dictionary`.
+    pub fn new(name: &'static str, dictionary: impl Into<Vec<u8>>) -> Self {
+        let dictionary = dictionary.into();
+
+        Self {
+            name,
+            decoder: DecoderDictionary::copy(&dictionary),
+            dictionary,
+            encoders: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Train a dictionary of at most `max_size` bytes from sample messages.
+    ///
+    /// The samples are encoded but uncompressed messages, for example the
+    /// output of `prost::Message::encode_to_vec`. Training needs a fair
+    /// number of samples, a few hundred at least, and fails when given too
+    /// few.
+    pub fn train<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> io::Result<Vec<u8>> {
+        zstd::dict::from_samples(samples, max_size)
+    }
+
+    /// The dictionary used by this compressor.
+    pub fn dictionary(&self) -> &[u8] {
+        &self.dictionary
+    }
+
+    fn encoder(&self, level: i32) -> Arc<EncoderDictionary<'static>> {
+        let mut encoders = self.encoders.lock().unwrap();
+
+        if let Some((_, encoder)) = encoders.iter().find(|(l, _)| *l == level) {
+            return encoder.clone();
+        }
+
+        let encoder = Arc::new(EncoderDictionary::copy(&self.dictionary, level));
+        encoders.push((level, encoder.clone()));
+        encoder
+    }
+}
+
+impl Compressor for ZstdDictionary {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn compress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+        self.compress_with_level(input, CompressionLevel::Default, output)
+    }
+
+    fn compress_with_level(
+        &self,
+        input: &[u8],
+        level: CompressionLevel,
+        output: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        let dictionary = self.encoder(zstd_level(level));
+        let mut encoder = Encoder::with_prepared_dictionary(input, &dictionary)?;
+        io::copy(&mut encoder, output)?;
+        Ok(())
+    }
+
+    fn decompress(&self, input: &[u8], output: &mut dyn io::Write) -> io::Result<()> {
+        let mut decoder = Decoder::with_prepared_dictionary(input, &self.decoder)?;
+        io::copy(&mut decoder, output)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ZstdDictionary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ZstdDictionary")
+            .field("name", &self.name)
+            .field("dictionary_len", &self.dictionary.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples() -> Vec<Vec<u8>> {
+        (0..1000u32)
+            .map(|i| {
+                format!(
+                    "{{\"id\":{i},\"name\":\"user-{}\",\"email\":\"user-{i}@example.com\",\"active\":true}}",
+                    i * 7
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dictionary_round_trip() {
+        let dictionary = ZstdDictionary::train(&samples(), 4 * 1024).unwrap();
+        let compressor = ZstdDictionary::new("zstd-test", dictionary);
+
+        let message =
+            b"{\"id\":4242,\"name\":\"user-29694\",\"email\":\"user-4242@example.com\",\"active\":true}";
+
+        let mut with_dictionary = Vec::new();
+        compressor.compress(message, &mut with_dictionary).unwrap();
+        let without_dictionary = zstd::encode_all(&message[..], 0).unwrap();
+        assert!(with_dictionary.len() < without_dictionary.len());
+
+        let mut decompressed = Vec::new();
+        compressor
+            .decompress(&with_dictionary, &mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, message);
+    }
+}
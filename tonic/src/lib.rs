@@ -21,6 +21,8 @@
 //!   and `channel` features. Enabled by default.
 //! - `server`: Enables just the full featured server portion of the `transport` feature.
 //! - `channel`: Enables just the full featured channel portion of the `transport` feature.
+//! - `service-config`: Enables configuring channels with a JSON gRPC service config.
+//!   Depends on [`serde_json`]. Not enabled by default.
 //! - `router`: Enables the [`axum`] based service router. Enabled by default.
 //! - `codegen`: Enables all the required exports and optional dependencies required
 //!   for [`tonic-build`]. Enabled by default.
@@ -40,7 +42,8 @@
 //!   Not enabled by default.
 //! - `deflate`: Enables compressing requests, responses, and streams. Depends on [`flate2`].
 //!   Not enabled by default.
-//! - `zstd`: Enables compressing requests, responses, and streams. Depends on [`zstd`].
+//! - `zstd`: Enables compressing requests, responses, and streams, optionally with a
+//!   pre-shared dictionary. Depends on [`zstd`].
 //!   Not enabled by default.
 //!
 //! # Structure
@@ -90,6 +93,7 @@
 //! [`webpki-roots`]: https://docs.rs/webpki-roots
 //! [`flate2`]: https://docs.rs/flate2
 //! [`zstd`]: https://docs.rs/zstd
+//! [`serde_json`]: https://docs.rs/serde_json
 
 #![recursion_limit = "256"]
 #![doc(
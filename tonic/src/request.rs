@@ -1,14 +1,15 @@
-use crate::metadata::{MetadataMap, MetadataValue};
-#[cfg(feature = "server")]
-use crate::transport::server::TcpConnectInfo;
+use crate::codec::SendCompression;
+use crate::metadata::{GRPC_TIMEOUT_HEADER, MetadataMap, MetadataValue};
 #[cfg(all(feature = "server", feature = "_tls-any"))]
 use crate::transport::server::TlsConnectInfo;
-use http::Extensions;
+#[cfg(feature = "server")]
+use crate::transport::server::{CancellationToken, TcpConnectInfo};
+use http::{Extensions, HeaderMap, HeaderValue, uri::Authority};
 #[cfg(feature = "server")]
 use std::net::SocketAddr;
 #[cfg(all(feature = "server", feature = "_tls-any"))]
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 #[cfg(all(feature = "server", feature = "_tls-any"))]
 use tokio_rustls::rustls::pki_types::CertificateDer;
 use tokio_stream::Stream;
@@ -263,6 +264,44 @@ impl<T> Request<T> {
             .and_then(|i| i.peer_certs())
     }
 
+    /// Returns a future that resolves once the call is cancelled.
+    ///
+    /// The future does not borrow the request, so it can be moved into work
+    /// spawned by the handler. It never resolves for requests that did not
+    /// come from the `transport` server. See [`CancellationToken`] for when a
+    /// call is cancelled.
+    ///
+    /// ```rust,no_run
+    /// # async fn expensive_work() {}
+    /// # async fn handler(request: tonic::Request<()>) {
+    /// let cancelled = request.cancelled();
+    /// tokio::spawn(async move {
+    ///     tokio::select! {
+    ///         _ = cancelled => { /* clean up */ }
+    ///         _ = expensive_work() => {}
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    #[cfg(feature = "server")]
+    pub fn cancelled(&self) -> impl Future<Output = ()> + Send + 'static {
+        let token = self.cancellation_token();
+        async move {
+            match token {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    /// Get the [`CancellationToken`] of the call.
+    ///
+    /// This only returns `Some` on the server side of the `transport` server.
+    #[cfg(feature = "server")]
+    pub fn cancellation_token(&self) -> Option<CancellationToken> {
+        self.extensions().get::<CancellationToken>().cloned()
+    }
+
     /// Set the max duration the request is allowed to take.
     ///
     /// Requires the server to support the `grpc-timeout` metadata, which Tonic does.
@@ -292,8 +331,124 @@ impl<T> Request<T> {
     /// [the spec]: https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md
     pub fn set_timeout(&mut self, deadline: Duration) {
         let value: MetadataValue<_> = duration_to_grpc_timeout(deadline).parse().unwrap();
-        self.metadata_mut()
-            .insert(crate::metadata::GRPC_TIMEOUT_HEADER, value);
+        self.metadata_mut().insert(GRPC_TIMEOUT_HEADER, value);
+    }
+
+    /// Get the point in time by which the call has to complete.
+    ///
+    /// On the server this is derived from the `grpc-timeout` of the incoming
+    /// request and the timeout of the `transport` server, whichever is
+    /// shorter. On the client this returns the deadline set with
+    /// [`Request::set_deadline`].
+    pub fn deadline(&self) -> Option<Instant> {
+        self.extensions()
+            .get::<Deadline>()
+            .map(|deadline| deadline.0)
+    }
+
+    /// Set the point in time by which the call has to complete.
+    ///
+    /// When the request is sent by a [`Grpc`] client, its `grpc-timeout` is
+    /// set to the time remaining until `deadline`, unless the request already
+    /// has a shorter timeout. This propagates the deadline of an incoming
+    /// request to the calls a handler makes:
+    ///
+    /// ```rust
+    /// use tonic::Request;
+    ///
+    /// fn downstream_request<T>(incoming: &Request<T>) -> Request<()> {
+    ///     let mut request = Request::new(());
+    ///     if let Some(deadline) = incoming.deadline() {
+    ///         request.set_deadline(deadline);
+    ///     }
+    ///     request
+    /// }
+    /// ```
+    ///
+    /// [`Grpc`]: crate::client::Grpc
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.extensions_mut().insert(Deadline(deadline));
+    }
+
+    /// Wait for the channel to be connected instead of failing immediately.
+    ///
+    /// A channel configured with a [`ConnectionBackoff`] fails calls with
+    /// [`Code::Unavailable`] while it waits to reconnect. A wait-for-ready call
+    /// instead waits until the channel is connected, or until its timeout
+    /// expires.
+    ///
+    /// ```rust
+    /// use tonic::Request;
+    ///
+    /// let mut request = Request::new(());
+    /// request.set_wait_for_ready(true);
+    /// assert!(request.wait_for_ready());
+    /// ```
+    ///
+    /// [`ConnectionBackoff`]: crate::transport::channel::ConnectionBackoff
+    /// [`Code::Unavailable`]: crate::Code::Unavailable
+    pub fn set_wait_for_ready(&mut self, wait_for_ready: bool) {
+        self.extensions_mut().insert(WaitForReady(wait_for_ready));
+    }
+
+    /// Whether the call waits for the channel to be connected, see
+    /// [`Request::set_wait_for_ready`].
+    pub fn wait_for_ready(&self) -> bool {
+        self.extensions()
+            .get::<WaitForReady>()
+            .is_some_and(|wait_for_ready| wait_for_ready.0)
+    }
+
+    /// Compress the messages of this call, instead of using the compression
+    /// of the [`Grpc`] client sending it.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "gzip")] {
+    /// use tonic::{Request, codec::CompressionEncoding};
+    ///
+    /// let mut request = Request::new(());
+    /// request.set_send_compressed(CompressionEncoding::Gzip);
+    /// # }
+    /// ```
+    ///
+    /// [`Grpc`]: crate::client::Grpc
+    pub fn set_send_compressed(&mut self, compression: impl Into<SendCompression>) {
+        self.extensions_mut()
+            .insert(SendCompressed(compression.into()));
+    }
+
+    /// Limit the size of the messages received by this call, instead of
+    /// using the limit of the [`Grpc`] client sending it.
+    ///
+    /// [`Grpc`]: crate::client::Grpc
+    pub fn set_max_decoding_message_size(&mut self, limit: usize) {
+        self.extensions_mut().insert(MaxDecodingMessageSize(limit));
+    }
+
+    /// Limit the size of the messages sent by this call, instead of using the
+    /// limit of the [`Grpc`] client sending it.
+    ///
+    /// [`Grpc`]: crate::client::Grpc
+    pub fn set_max_encoding_message_size(&mut self, limit: usize) {
+        self.extensions_mut().insert(MaxEncodingMessageSize(limit));
+    }
+
+    /// Send this call with `authority` as its `:authority`, instead of the
+    /// origin of the channel.
+    ///
+    /// This is used by servers hosting several services by name. It only
+    /// changes the `:authority` pseudo-header: the connection, including the
+    /// name the TLS certificate of the server is verified against, is the
+    /// one of the channel.
+    ///
+    /// ```rust
+    /// use tonic::Request;
+    ///
+    /// let mut request = Request::new(());
+    /// request.set_authority("api.example.com".parse().unwrap());
+    /// ```
+    pub fn set_authority(&mut self, authority: Authority) {
+        self.extensions_mut().insert(AuthorityOverride(authority));
     }
 
     /// Returns a reference to the associated extensions.
@@ -394,7 +549,7 @@ mod sealed {
     pub trait Sealed {}
 }
 
-fn duration_to_grpc_timeout(duration: Duration) -> String {
+pub(crate) fn duration_to_grpc_timeout(duration: Duration) -> String {
     fn try_format<T: Into<u128>>(
         duration: Duration,
         unit: char,
@@ -428,6 +583,100 @@ fn duration_to_grpc_timeout(duration: Duration) -> String {
         .expect("duration is unrealistically large")
 }
 
+const SECONDS_IN_HOUR: u64 = 60 * 60;
+const SECONDS_IN_MINUTE: u64 = 60;
+
+/// Tries to parse the `grpc-timeout` header if it is present. If we fail to parse, returns
+/// the value we attempted to parse.
+///
+/// Follows the [gRPC over HTTP2 spec](https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md).
+pub(crate) fn try_parse_grpc_timeout(
+    headers: &HeaderMap<HeaderValue>,
+) -> Result<Option<Duration>, &HeaderValue> {
+    let Some(val) = headers.get(GRPC_TIMEOUT_HEADER) else {
+        return Ok(None);
+    };
+
+    let (timeout_value, timeout_unit) = val
+        .to_str()
+        .map_err(|_| val)
+        .and_then(|s| if s.is_empty() { Err(val) } else { Ok(s) })?
+        // `HeaderValue::to_str` only returns `Ok` if the header contains ASCII so this
+        // `split_at` will never panic from trying to split in the middle of a character.
+        // See https://docs.rs/http/1/http/header/struct.HeaderValue.html#method.to_str
+        //
+        // `len - 1` also wont panic since we just checked `s.is_empty`.
+        .split_at(val.len() - 1);
+
+    // gRPC spec specifies `TimeoutValue` will be at most 8 digits
+    // Caping this at 8 digits also prevents integer overflow from ever occurring
+    if timeout_value.len() > 8 {
+        return Err(val);
+    }
+
+    let timeout_value: u64 = timeout_value.parse().map_err(|_| val)?;
+
+    let duration = match timeout_unit {
+        // Hours
+        "H" => Duration::from_secs(timeout_value * SECONDS_IN_HOUR),
+        // Minutes
+        "M" => Duration::from_secs(timeout_value * SECONDS_IN_MINUTE),
+        // Seconds
+        "S" => Duration::from_secs(timeout_value),
+        // Milliseconds
+        "m" => Duration::from_millis(timeout_value),
+        // Microseconds
+        "u" => Duration::from_micros(timeout_value),
+        // Nanoseconds
+        "n" => Duration::from_nanos(timeout_value),
+        _ => return Err(val),
+    };
+
+    Ok(Some(duration))
+}
+
+/// The deadline of a call, stored in the request extensions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Deadline(pub(crate) Instant);
+
+impl Deadline {
+    /// Set the `grpc-timeout` in `headers` to the time remaining until this
+    /// deadline, unless it is already shorter.
+    pub(crate) fn apply(self, headers: &mut HeaderMap) {
+        let remaining = self.0.saturating_duration_since(Instant::now());
+        let current = try_parse_grpc_timeout(headers).ok().flatten();
+
+        if current.is_none_or(|current| remaining < current) {
+            let value = duration_to_grpc_timeout(remaining);
+            headers.insert(
+                GRPC_TIMEOUT_HEADER,
+                HeaderValue::try_from(value).expect("valid grpc-timeout"),
+            );
+        }
+    }
+}
+
+/// Whether a call is wait-for-ready, stored in the request extensions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct WaitForReady(pub(crate) bool);
+
+/// The compression of the messages of a call, stored in the request
+/// extensions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SendCompressed(pub(crate) SendCompression);
+
+/// The size limit of received messages, stored in the request extensions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MaxDecodingMessageSize(pub(crate) usize);
+
+/// The size limit of sent messages, stored in the request extensions.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MaxEncodingMessageSize(pub(crate) usize);
+
+/// The `:authority` of a call, stored in the request extensions.
+#[derive(Debug, Clone)]
+pub(crate) struct AuthorityOverride(pub(crate) Authority);
+
 /// When converting a `tonic::Request` into a `http::Request` should reserved
 /// headers be removed?
 pub(crate) enum SanitizeHeaders {
@@ -481,6 +730,37 @@ mod tests {
         assert_eq!(user_agent, "Custom/1.2.3");
     }
 
+    #[test]
+    fn deadline_shortens_timeout() {
+        let mut r = Request::new(1);
+        r.set_timeout(Duration::from_secs(60));
+        r.set_deadline(Instant::now() + Duration::from_secs(1));
+
+        let mut http_request = r.into_http(
+            Uri::default(),
+            http::Method::POST,
+            http::Version::HTTP_2,
+            SanitizeHeaders::Yes,
+        );
+        let deadline = *http_request.extensions().get::<Deadline>().unwrap();
+        deadline.apply(http_request.headers_mut());
+
+        let timeout = try_parse_grpc_timeout(http_request.headers())
+            .unwrap()
+            .unwrap();
+        assert!(timeout <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn deadline_keeps_shorter_timeout() {
+        let mut headers = HeaderMap::new();
+        headers.insert(GRPC_TIMEOUT_HEADER, HeaderValue::from_static("10m"));
+
+        Deadline(Instant::now() + Duration::from_secs(1)).apply(&mut headers);
+
+        assert_eq!(headers[GRPC_TIMEOUT_HEADER], "10m");
+    }
+
     #[test]
     fn duration_to_grpc_timeout_less_than_second() {
         let timeout = Duration::from_millis(500);
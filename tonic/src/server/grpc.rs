@@ -1,6 +1,7 @@
 use crate::codec::EncodeBody;
 use crate::codec::compression::{
-    CompressionEncoding, EnabledCompressionEncodings, SingleMessageCompressionOverride,
+    CompressionEncoding, EnabledCompressionEncodings, SendCompression,
+    SingleMessageCompressionOverride,
 };
 use crate::metadata::GRPC_CONTENT_TYPE;
 use crate::{
@@ -8,20 +9,12 @@ use crate::{
     body::Body,
     codec::{Codec, Streaming},
     server::{ClientStreamingService, ServerStreamingService, StreamingService, UnaryService},
+    service::interceptor::{InterceptedCall, InterceptedResponses},
 };
 use http_body::Body as HttpBody;
-use std::{fmt, pin::pin};
+use std::{fmt, pin::pin, sync::Arc};
 use tokio_stream::{Stream, StreamExt};
 
-macro_rules! t {
-    ($result:expr) => {
-        match $result {
-            Ok(value) => value,
-            Err(status) => return status.into_http(),
-        }
-    };
-}
-
 /// A gRPC Server handler.
 ///
 /// This will wrap some inner [`Codec`] and provide utilities to handle
@@ -93,6 +86,8 @@ where
     /// Enable sending compressed responses.
     ///
     /// Requires the client to also support receiving compressed responses.
+    /// Pass a [`SendCompression`] instead of a [`CompressionEncoding`] to set
+    /// the compression level or leave small messages uncompressed.
     ///
     /// # Example
     ///
@@ -116,8 +111,8 @@ where
     ///
     /// let service = ExampleServer::new(Svc).send_compressed(CompressionEncoding::Gzip);
     /// ```
-    pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-        self.send_compression_encodings.enable(encoding);
+    pub fn send_compressed(mut self, compression: impl Into<SendCompression>) -> Self {
+        self.send_compression_encodings.enable(compression);
         self
     }
 
@@ -187,13 +182,11 @@ where
         accept_encodings: EnabledCompressionEncodings,
         send_encodings: EnabledCompressionEncodings,
     ) -> Self {
-        for &encoding in CompressionEncoding::ENCODINGS {
-            if accept_encodings.is_enabled(encoding) {
-                self = self.accept_compressed(encoding);
-            }
-            if send_encodings.is_enabled(encoding) {
-                self = self.send_compressed(encoding);
-            }
+        for compression in accept_encodings.iter() {
+            self = self.accept_compressed(compression.encoding());
+        }
+        for compression in send_encodings.iter() {
+            self = self.send_compressed(compression);
         }
 
         self
@@ -219,27 +212,31 @@ where
     pub async fn unary<S, B>(
         &mut self,
         mut service: S,
-        req: http::Request<B>,
+        mut req: http::Request<B>,
     ) -> http::Response<Body>
     where
         S: UnaryService<T::Decode, Response = T::Encode>,
         B: HttpBody + Send + 'static,
         B::Error: Into<crate::BoxError> + Send,
     {
-        let accept_encoding = CompressionEncoding::from_accept_encoding_header(
+        let accept_encoding = SendCompression::from_accept_encoding_header(
             req.headers(),
             self.send_compression_encodings,
         );
+        let call = InterceptedCall::from_request(&mut req);
 
-        let request = match self.map_request_unary(req).await {
+        let request = match self.map_request_unary(req, call.as_deref()).await {
             Ok(r) => r,
             Err(status) => {
-                return self.map_response::<tokio_stream::Once<Result<T::Encode, Status>>>(
-                    Err(status),
-                    accept_encoding,
-                    SingleMessageCompressionOverride::default(),
-                    self.max_encoding_message_size,
-                );
+                return self
+                    .map_response::<tokio_stream::Once<Result<T::Encode, Status>>>(
+                        Err(status),
+                        call,
+                        accept_encoding,
+                        SingleMessageCompressionOverride::default(),
+                        self.max_encoding_message_size,
+                    )
+                    .await;
             }
         };
 
@@ -252,17 +249,19 @@ where
 
         self.map_response(
             response,
+            call,
             accept_encoding,
             compression_override,
             self.max_encoding_message_size,
         )
+        .await
     }
 
     /// Handle a server side streaming request.
     pub async fn server_streaming<S, B>(
         &mut self,
         mut service: S,
-        req: http::Request<B>,
+        mut req: http::Request<B>,
     ) -> http::Response<Body>
     where
         S: ServerStreamingService<T::Decode, Response = T::Encode>,
@@ -270,20 +269,24 @@ where
         B: HttpBody + Send + 'static,
         B::Error: Into<crate::BoxError> + Send,
     {
-        let accept_encoding = CompressionEncoding::from_accept_encoding_header(
+        let accept_encoding = SendCompression::from_accept_encoding_header(
             req.headers(),
             self.send_compression_encodings,
         );
+        let call = InterceptedCall::from_request(&mut req);
 
-        let request = match self.map_request_unary(req).await {
+        let request = match self.map_request_unary(req, call.as_deref()).await {
             Ok(r) => r,
             Err(status) => {
-                return self.map_response::<S::ResponseStream>(
-                    Err(status),
-                    accept_encoding,
-                    SingleMessageCompressionOverride::default(),
-                    self.max_encoding_message_size,
-                );
+                return self
+                    .map_response::<S::ResponseStream>(
+                        Err(status),
+                        call,
+                        accept_encoding,
+                        SingleMessageCompressionOverride::default(),
+                        self.max_encoding_message_size,
+                    )
+                    .await;
             }
         };
 
@@ -291,31 +294,47 @@ where
 
         self.map_response(
             response,
+            call,
             accept_encoding,
             // disabling compression of individual stream items must be done on
             // the items themselves
             SingleMessageCompressionOverride::default(),
             self.max_encoding_message_size,
         )
+        .await
     }
 
     /// Handle a client side streaming gRPC request.
     pub async fn client_streaming<S, B>(
         &mut self,
         mut service: S,
-        req: http::Request<B>,
+        mut req: http::Request<B>,
     ) -> http::Response<Body>
     where
         S: ClientStreamingService<T::Decode, Response = T::Encode>,
         B: HttpBody + Send + 'static,
         B::Error: Into<crate::BoxError> + Send + 'static,
     {
-        let accept_encoding = CompressionEncoding::from_accept_encoding_header(
+        let accept_encoding = SendCompression::from_accept_encoding_header(
             req.headers(),
             self.send_compression_encodings,
         );
+        let call = InterceptedCall::from_request(&mut req);
 
-        let request = t!(self.map_request_streaming(req));
+        let request = match self.map_request_streaming(req, call.clone()).await {
+            Ok(r) => r,
+            Err(status) => {
+                return self
+                    .map_response::<tokio_stream::Once<Result<T::Encode, Status>>>(
+                        Err(status),
+                        call,
+                        accept_encoding,
+                        SingleMessageCompressionOverride::default(),
+                        self.max_encoding_message_size,
+                    )
+                    .await;
+            }
+        };
 
         let response = service
             .call(request)
@@ -326,17 +345,19 @@ where
 
         self.map_response(
             response,
+            call,
             accept_encoding,
             compression_override,
             self.max_encoding_message_size,
         )
+        .await
     }
 
     /// Handle a bi-directional streaming gRPC request.
     pub async fn streaming<S, B>(
         &mut self,
         mut service: S,
-        req: http::Request<B>,
+        mut req: http::Request<B>,
     ) -> http::Response<Body>
     where
         S: StreamingService<T::Decode, Response = T::Encode> + Send,
@@ -344,26 +365,43 @@ where
         B: HttpBody + Send + 'static,
         B::Error: Into<crate::BoxError> + Send,
     {
-        let accept_encoding = CompressionEncoding::from_accept_encoding_header(
+        let accept_encoding = SendCompression::from_accept_encoding_header(
             req.headers(),
             self.send_compression_encodings,
         );
+        let call = InterceptedCall::from_request(&mut req);
 
-        let request = t!(self.map_request_streaming(req));
+        let request = match self.map_request_streaming(req, call.clone()).await {
+            Ok(r) => r,
+            Err(status) => {
+                return self
+                    .map_response::<S::ResponseStream>(
+                        Err(status),
+                        call,
+                        accept_encoding,
+                        SingleMessageCompressionOverride::default(),
+                        self.max_encoding_message_size,
+                    )
+                    .await;
+            }
+        };
 
         let response = service.call(request).await;
 
         self.map_response(
             response,
+            call,
             accept_encoding,
             SingleMessageCompressionOverride::default(),
             self.max_encoding_message_size,
         )
+        .await
     }
 
     async fn map_request_unary<B>(
         &mut self,
-        request: http::Request<B>,
+        mut request: http::Request<B>,
+        call: Option<&InterceptedCall>,
     ) -> Result<Request<T::Decode>, Status>
     where
         B: HttpBody + Send + 'static,
@@ -371,6 +409,10 @@ where
     {
         let request_compression_encoding = self.request_encoding_if_supported(&request)?;
 
+        if let Some(call) = call {
+            request = call.request(request).await?;
+        }
+
         let (parts, body) = request.into_parts();
 
         let mut stream = pin!(Streaming::new_request(
@@ -380,11 +422,15 @@ where
             self.max_decoding_message_size,
         ));
 
-        let message = stream
+        let mut message = stream
             .try_next()
             .await?
             .ok_or_else(|| Status::internal("Missing request message."))?;
 
+        if let Some(call) = call {
+            call.request_message(&mut message).await?;
+        }
+
         let mut req = Request::from_http_parts(parts, message);
 
         if let Some(trailers) = stream.trailers().await? {
@@ -394,9 +440,10 @@ where
         Ok(req)
     }
 
-    fn map_request_streaming<B>(
+    async fn map_request_streaming<B>(
         &mut self,
-        request: http::Request<B>,
+        mut request: http::Request<B>,
+        call: Option<Arc<InterceptedCall>>,
     ) -> Result<Request<Streaming<T::Decode>>, Status>
     where
         B: HttpBody + Send + 'static,
@@ -404,29 +451,53 @@ where
     {
         let encoding = self.request_encoding_if_supported(&request)?;
 
+        if let Some(call) = &call {
+            request = call.request(request).await?;
+        }
+
         let request = request.map(|body| {
-            Streaming::new_request(
+            let mut stream = Streaming::new_request(
                 self.codec.decoder(),
                 body,
                 encoding,
                 self.max_decoding_message_size,
-            )
+            );
+            if let Some(call) = call {
+                stream.intercept(call.request_messages());
+            }
+            stream
         });
 
         Ok(Request::from_http(request))
     }
 
-    fn map_response<B>(
+    async fn map_response<B>(
         &mut self,
         response: Result<crate::Response<B>, Status>,
-        accept_encoding: Option<CompressionEncoding>,
+        call: Option<Arc<InterceptedCall>>,
+        accept_encoding: Option<SendCompression>,
         compression_override: SingleMessageCompressionOverride,
         max_message_size: Option<usize>,
     ) -> http::Response<Body>
     where
         B: Stream<Item = Result<T::Encode, Status>> + Send + 'static,
     {
-        let response = t!(response);
+        let mut response = match response {
+            Ok(response) => response,
+            Err(mut status) => {
+                if let Some(call) = &call {
+                    call.status(&mut status).await;
+                }
+                return status.into_http();
+            }
+        };
+
+        if let Some(call) = &call
+            && let Err(mut status) = call.response(response.metadata_mut()).await
+        {
+            call.status(&mut status).await;
+            return status.into_http();
+        }
 
         let (mut parts, body) = response.into_http().into_parts();
 
@@ -435,24 +506,32 @@ where
             .headers
             .insert(http::header::CONTENT_TYPE, GRPC_CONTENT_TYPE);
 
-        #[cfg(any(feature = "gzip", feature = "deflate", feature = "zstd"))]
-        if let Some(encoding) = accept_encoding {
+        if let Some(compression) = accept_encoding {
             // Set the content encoding
             parts.headers.insert(
                 crate::codec::compression::ENCODING_HEADER,
-                encoding.into_header_value(),
+                compression.encoding().into_header_value(),
             );
         }
 
-        let body = EncodeBody::new_server(
-            self.codec.encoder(),
-            body,
-            accept_encoding,
-            compression_override,
-            max_message_size,
-        );
+        let body = match call {
+            Some(call) => Body::new(EncodeBody::new_server_with_compression(
+                self.codec.encoder(),
+                InterceptedResponses::new(body, call),
+                accept_encoding,
+                compression_override,
+                max_message_size,
+            )),
+            None => Body::new(EncodeBody::new_server_with_compression(
+                self.codec.encoder(),
+                body,
+                accept_encoding,
+                compression_override,
+                max_message_size,
+            )),
+        };
 
-        http::Response::from_parts(parts, Body::new(body))
+        http::Response::from_parts(parts, body)
     }
 
     fn request_encoding_if_supported<B>(
@@ -1,6 +1,12 @@
 //! gRPC interceptors which are a kind of middleware.
 //!
-//! See [`Interceptor`] for more details.
+//! See [`Interceptor`] for more details, and [`AsyncInterceptor`] for
+//! interceptors that need to await or see the messages of a call.
+
+mod async_interceptor;
+
+pub use self::async_interceptor::{Async, AsyncInterceptor};
+pub(crate) use self::async_interceptor::{InterceptedCall, InterceptedResponses};
 
 use crate::{Status, request::SanitizeHeaders};
 use pin_project::pin_project;
@@ -8,7 +14,7 @@ use std::{
     fmt,
     future::Future,
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, ready},
 };
 use tower_layer::Layer;
 use tower_service::Service;
@@ -35,6 +41,9 @@ use tower_service::Service;
 /// tower-http's [`Trace`](https://docs.rs/tower-http/latest/tower_http/trace/index.html)
 /// middleware supports gRPC out of the box.
 ///
+/// Interceptors that need to await, or to see the messages and the status of a call, can
+/// implement [`AsyncInterceptor`] instead.
+///
 /// [tower]: https://crates.io/crates/tower
 /// [example]: https://github.com/hyperium/tonic/tree/master/examples/src/interceptor
 /// [tower-example]: https://github.com/hyperium/tonic/tree/master/examples/src/tower
@@ -114,8 +123,9 @@ where
 
 impl<S, I, ReqBody, ResBody> Service<http::Request<ReqBody>> for InterceptedService<S, I>
 where
-    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
     I: Interceptor,
+    ReqBody: Send + 'static,
 {
     type Response = http::Response<ResponseBody<ResBody>>;
     type Error = S::Error;
@@ -146,7 +156,19 @@ where
                 let (metadata, extensions, _) = req.into_parts();
                 let req = crate::Request::from_parts(metadata, extensions, msg);
                 let req = req.into_http(uri, method, version, SanitizeHeaders::No);
-                ResponseFuture::future(self.inner.call(req))
+
+                if !async_interceptor::has_pending_request(&req) {
+                    return ResponseFuture::future(self.inner.call(req));
+                }
+
+                // Async interceptors reject calls before they reach the inner service,
+                // which is called once their `on_request` hooks are done.
+                let clone = self.inner.clone();
+                let mut inner = std::mem::replace(&mut self.inner, clone);
+                ResponseFuture::intercepting(Box::pin(async move {
+                    let req = async_interceptor::intercept_request(req).await?;
+                    Ok(inner.call(req))
+                }))
             }
             Err(status) => ResponseFuture::status(status),
         }
@@ -181,23 +203,50 @@ impl<F> ResponseFuture<F> {
             kind: Kind::Status(Some(status)),
         }
     }
+
+    fn intercepting(future: BoxFuture<'static, Result<F, Status>>) -> Self {
+        Self {
+            kind: Kind::Intercepting(future),
+        }
+    }
 }
 
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
 #[pin_project(project = KindProj)]
-#[derive(Debug)]
 enum Kind<F> {
+    Intercepting(BoxFuture<'static, Result<F, Status>>),
     Future(#[pin] F),
     Status(Option<Status>),
 }
 
+impl<F: fmt::Debug> fmt::Debug for Kind<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Intercepting(_) => f.write_str("Intercepting"),
+            Kind::Future(future) => f.debug_tuple("Future").field(future).finish(),
+            Kind::Status(status) => f.debug_tuple("Status").field(status).finish(),
+        }
+    }
+}
+
 impl<F, E, B> Future for ResponseFuture<F>
 where
     F: Future<Output = Result<http::Response<B>, E>>,
 {
     type Output = Result<http::Response<ResponseBody<B>>, E>;
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.project().kind.project() {
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut kind = self.as_mut().project().kind;
+        if let KindProj::Intercepting(future) = kind.as_mut().project() {
+            match ready!(future.as_mut().poll(cx)) {
+                Ok(future) => kind.set(Kind::Future(future)),
+                Err(status) => kind.set(Kind::Status(Some(status))),
+            }
+        }
+
+        match kind.project() {
+            KindProj::Intercepting(_) => unreachable!("interception is done"),
             KindProj::Future(future) => future.poll(cx).map_ok(|res| res.map(ResponseBody::wrap)),
             KindProj::Status(status) => {
                 let (parts, ()) = status.take().unwrap().into_http::<()>().into_parts();
@@ -0,0 +1,777 @@
+use super::Interceptor;
+use crate::{Code, GrpcMethod, Request, Status, metadata::MetadataMap, request::SanitizeHeaders};
+use pin_project::pin_project;
+use std::{
+    any::Any,
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll, ready},
+};
+use tokio_stream::Stream;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// An asynchronous gRPC server interceptor.
+///
+/// Unlike [`Interceptor`], an `AsyncInterceptor` can await while handling a
+/// call, for example to look up a token in a remote store, and it sees the
+/// whole call: the request metadata, every message received and sent, the
+/// response headers and the final [`Status`]. Every hook has a default
+/// implementation that lets the call through unchanged, so only the hooks
+/// needed have to be implemented.
+///
+/// Messages are passed as [`Any`] and can be downcast to the request and
+/// response types of the method, which is described by the [`GrpcMethod`]
+/// given to every hook.
+///
+/// Apart from [`on_request`](AsyncInterceptor::on_request), async interceptors
+/// only run on the server. They are installed by wrapping
+/// them in [`Async`], which is an [`Interceptor`] and can be passed to the
+/// `with_interceptor` constructor of generated servers, to
+/// [`InterceptedService`] or to [`InterceptorLayer`]:
+///
+/// ```
+/// use std::any::Any;
+/// use tonic::{GrpcMethod, Request, Status, service::interceptor::{Async, AsyncInterceptor}};
+///
+/// struct CheckToken;
+///
+/// impl AsyncInterceptor for CheckToken {
+///     async fn on_request(
+///         &self,
+///         _method: &GrpcMethod<'_>,
+///         request: Request<()>,
+///     ) -> Result<Request<()>, Status> {
+///         match request.metadata().get("authorization") {
+///             Some(token) if is_valid(token.as_bytes()).await => Ok(request),
+///             _ => Err(Status::unauthenticated("invalid token")),
+///         }
+///     }
+///
+///     async fn on_request_message(
+///         &self,
+///         method: &GrpcMethod<'_>,
+///         message: &mut (dyn Any + Send),
+///     ) -> Result<(), Status> {
+///         match message.downcast_ref::<String>() {
+///             Some(name) if name.len() > 64 => Err(Status::invalid_argument(format!(
+///                 "{} called with a name too long",
+///                 method.method()
+///             ))),
+///             _ => Ok(()),
+///         }
+///     }
+/// }
+///
+/// async fn is_valid(token: &[u8]) -> bool {
+///     token == b"Bearer secret"
+/// }
+///
+/// let interceptor = Async::new(CheckToken);
+/// // let service = GreeterServer::with_interceptor(MyGreeter, interceptor);
+/// ```
+///
+/// [`InterceptedService`]: super::InterceptedService
+/// [`InterceptorLayer`]: super::InterceptorLayer
+pub trait AsyncInterceptor: Send + Sync + 'static {
+    /// Intercept a request before its messages are received, optionally
+    /// cancelling it.
+    fn on_request(
+        &self,
+        method: &GrpcMethod<'_>,
+        request: Request<()>,
+    ) -> impl Future<Output = Result<Request<()>, Status>> + Send {
+        let _ = method;
+        async move { Ok(request) }
+    }
+
+    /// Intercept each message received from the client.
+    ///
+    /// Returning an error fails the call with that status.
+    fn on_request_message(
+        &self,
+        method: &GrpcMethod<'_>,
+        message: &mut (dyn Any + Send),
+    ) -> impl Future<Output = Result<(), Status>> + Send {
+        let _ = (method, message);
+        async { Ok(()) }
+    }
+
+    /// Intercept the response headers before they are sent.
+    ///
+    /// Returning an error fails the call with that status instead.
+    fn on_response(
+        &self,
+        method: &GrpcMethod<'_>,
+        metadata: &mut MetadataMap,
+    ) -> impl Future<Output = Result<(), Status>> + Send {
+        let _ = (method, metadata);
+        async { Ok(()) }
+    }
+
+    /// Intercept each message sent to the client.
+    ///
+    /// Returning an error ends the response stream with that status.
+    fn on_response_message(
+        &self,
+        method: &GrpcMethod<'_>,
+        message: &mut (dyn Any + Send),
+    ) -> impl Future<Output = Result<(), Status>> + Send {
+        let _ = (method, message);
+        async { Ok(()) }
+    }
+
+    /// Intercept the final status of the call, which has code
+    /// [`Code::Ok`] if the call succeeded.
+    ///
+    /// A successful call fails if the status is changed to an error.
+    fn on_status(
+        &self,
+        method: &GrpcMethod<'_>,
+        status: &mut Status,
+    ) -> impl Future<Output = ()> + Send {
+        let _ = (method, status);
+        async {}
+    }
+}
+
+/// Object safe version of [`AsyncInterceptor`].
+trait DynInterceptor: Send + Sync {
+    fn on_request<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        request: Request<()>,
+    ) -> BoxFuture<'a, Result<Request<()>, Status>>;
+
+    fn on_request_message<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        message: &'a mut (dyn Any + Send),
+    ) -> BoxFuture<'a, Result<(), Status>>;
+
+    fn on_response<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        metadata: &'a mut MetadataMap,
+    ) -> BoxFuture<'a, Result<(), Status>>;
+
+    fn on_response_message<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        message: &'a mut (dyn Any + Send),
+    ) -> BoxFuture<'a, Result<(), Status>>;
+
+    fn on_status<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        status: &'a mut Status,
+    ) -> BoxFuture<'a, ()>;
+}
+
+impl<I: AsyncInterceptor> DynInterceptor for I {
+    fn on_request<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        request: Request<()>,
+    ) -> BoxFuture<'a, Result<Request<()>, Status>> {
+        Box::pin(AsyncInterceptor::on_request(self, method, request))
+    }
+
+    fn on_request_message<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        message: &'a mut (dyn Any + Send),
+    ) -> BoxFuture<'a, Result<(), Status>> {
+        Box::pin(AsyncInterceptor::on_request_message(self, method, message))
+    }
+
+    fn on_response<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        metadata: &'a mut MetadataMap,
+    ) -> BoxFuture<'a, Result<(), Status>> {
+        Box::pin(AsyncInterceptor::on_response(self, method, metadata))
+    }
+
+    fn on_response_message<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        message: &'a mut (dyn Any + Send),
+    ) -> BoxFuture<'a, Result<(), Status>> {
+        Box::pin(AsyncInterceptor::on_response_message(self, method, message))
+    }
+
+    fn on_status<'a>(
+        &'a self,
+        method: &'a GrpcMethod<'a>,
+        status: &'a mut Status,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(AsyncInterceptor::on_status(self, method, status))
+    }
+}
+
+/// Installs an [`AsyncInterceptor`] where an [`Interceptor`] is expected.
+///
+/// [`AsyncInterceptor::on_request`] runs in the [`InterceptedService`] before
+/// the request reaches the wrapped service, so rejected calls never reach it.
+/// The other hooks are recorded in the request extensions and run by the
+/// generated server handling the call. Several async interceptors can be
+/// stacked, the outermost one runs first.
+///
+/// Only `on_request` runs if the request is not handled by a
+/// [`Grpc`](crate::server::Grpc) server, as with hand-written services or on
+/// clients. An error is logged when that happens.
+///
+/// [`InterceptedService`]: super::InterceptedService
+pub struct Async<I> {
+    interceptor: Arc<I>,
+}
+
+impl<I: AsyncInterceptor> Async<I> {
+    /// Wrap an async interceptor.
+    pub fn new(interceptor: I) -> Self {
+        Self {
+            interceptor: Arc::new(interceptor),
+        }
+    }
+}
+
+impl<I> Clone for Async<I> {
+    fn clone(&self) -> Self {
+        Self {
+            interceptor: self.interceptor.clone(),
+        }
+    }
+}
+
+impl<I> fmt::Debug for Async<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Async")
+            .field(&format_args!("{}", std::any::type_name::<I>()))
+            .finish()
+    }
+}
+
+impl<I: AsyncInterceptor> Interceptor for Async<I> {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let interceptor = self.interceptor.clone() as Arc<dyn DynInterceptor>;
+
+        match request.extensions_mut().get_mut::<Installed>() {
+            Some(installed) => installed.interceptors.push(interceptor),
+            None => {
+                request.extensions_mut().insert(Installed {
+                    interceptors: vec![interceptor],
+                    requested: 0,
+                    taken: Arc::new(Taken(AtomicBool::new(false))),
+                });
+            }
+        }
+
+        Ok(request)
+    }
+}
+
+/// The async interceptors installed on a request.
+#[derive(Clone)]
+struct Installed {
+    interceptors: Vec<Arc<dyn DynInterceptor>>,
+    /// The number of interceptors whose `on_request` hook already ran.
+    requested: usize,
+    taken: Arc<Taken>,
+}
+
+/// Whether `request` has async interceptors whose `on_request` hook did not
+/// run yet.
+pub(crate) fn has_pending_request<B>(request: &http::Request<B>) -> bool {
+    request
+        .extensions()
+        .get::<Installed>()
+        .is_some_and(|installed| installed.requested < installed.interceptors.len())
+}
+
+/// Run the `on_request` hooks of the async interceptors installed on
+/// `request` that did not run yet.
+///
+/// If a hook rejects the call, the status hooks of every installed
+/// interceptor run before the status is returned.
+pub(crate) async fn intercept_request<B>(
+    request: http::Request<B>,
+) -> Result<http::Request<B>, Status> {
+    let Some(installed) = request.extensions().get::<Installed>().cloned() else {
+        return Ok(request);
+    };
+
+    let call = InterceptedCall {
+        interceptors: installed.interceptors,
+        requested: installed.requested,
+        path: request.uri().path().to_owned(),
+    };
+
+    match call.request(request).await {
+        Ok(mut request) => {
+            if let Some(installed) = request.extensions_mut().get_mut::<Installed>() {
+                installed.requested = installed.interceptors.len();
+            }
+            Ok(request)
+        }
+        Err(mut status) => {
+            installed.taken.0.store(true, Ordering::Relaxed);
+            call.status(&mut status).await;
+            Err(status)
+        }
+    }
+}
+
+/// Whether a server took the interceptors of a request to run them, logging
+/// an error if the request is dropped before.
+struct Taken(AtomicBool);
+
+impl Drop for Taken {
+    fn drop(&mut self) {
+        if !*self.0.get_mut() {
+            tracing::error!(
+                "async interceptor hooks were not run because the request was not handled by a tonic server"
+            );
+        }
+    }
+}
+
+/// The async interceptors of a call, along with the method they intercept.
+pub(crate) struct InterceptedCall {
+    interceptors: Vec<Arc<dyn DynInterceptor>>,
+    requested: usize,
+    path: String,
+}
+
+impl InterceptedCall {
+    /// Take the interceptors installed on `request`, if any.
+    pub(crate) fn from_request<B>(request: &mut http::Request<B>) -> Option<Arc<Self>> {
+        let Installed {
+            interceptors,
+            requested,
+            taken,
+        } = request.extensions_mut().remove::<Installed>()?;
+        taken.0.store(true, Ordering::Relaxed);
+
+        Some(Arc::new(Self {
+            interceptors,
+            requested,
+            path: request.uri().path().to_owned(),
+        }))
+    }
+
+    fn method(&self) -> GrpcMethod<'_> {
+        match self.path.trim_start_matches('/').split_once('/') {
+            Some((service, method)) => GrpcMethod::new(service, method),
+            None => GrpcMethod::new("", &self.path),
+        }
+    }
+
+    pub(crate) async fn request<B>(
+        &self,
+        request: http::Request<B>,
+    ) -> Result<http::Request<B>, Status> {
+        let method = self.method();
+        let uri = request.uri().clone();
+        let http_method = request.method().clone();
+        let version = request.version();
+        let (metadata, extensions, body) = Request::from_http(request).into_parts();
+
+        let mut request = Request::from_parts(metadata, extensions, ());
+        for interceptor in &self.interceptors[self.requested..] {
+            request = interceptor.on_request(&method, request).await?;
+        }
+
+        let (metadata, extensions, ()) = request.into_parts();
+        Ok(Request::from_parts(metadata, extensions, body).into_http(
+            uri,
+            http_method,
+            version,
+            SanitizeHeaders::No,
+        ))
+    }
+
+    pub(crate) async fn request_message(
+        &self,
+        message: &mut (dyn Any + Send),
+    ) -> Result<(), Status> {
+        let method = self.method();
+        for interceptor in &self.interceptors {
+            interceptor.on_request_message(&method, message).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn response(&self, metadata: &mut MetadataMap) -> Result<(), Status> {
+        let method = self.method();
+        for interceptor in &self.interceptors {
+            interceptor.on_response(&method, metadata).await?;
+        }
+        Ok(())
+    }
+
+    async fn response_message(&self, message: &mut (dyn Any + Send)) -> Result<(), Status> {
+        let method = self.method();
+        for interceptor in &self.interceptors {
+            interceptor.on_response_message(&method, message).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn status(&self, status: &mut Status) {
+        let method = self.method();
+        for interceptor in &self.interceptors {
+            interceptor.on_status(&method, status).await;
+        }
+    }
+
+    /// Run the message hooks on each message of a request stream.
+    pub(crate) fn request_messages<T>(
+        self: Arc<Self>,
+    ) -> impl Fn(T) -> BoxFuture<'static, Result<T, Status>> + Send + Sync + 'static
+    where
+        T: Send + 'static,
+    {
+        move |mut message| {
+            let call = self.clone();
+            Box::pin(async move {
+                call.request_message(&mut message).await?;
+                Ok(message)
+            })
+        }
+    }
+}
+
+impl fmt::Debug for InterceptedCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterceptedCall")
+            .field("interceptors", &self.interceptors.len())
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// A response stream running the message and status hooks of a call.
+#[pin_project]
+pub(crate) struct InterceptedResponses<S, T> {
+    #[pin]
+    inner: S,
+    call: Arc<InterceptedCall>,
+    pending: Option<BoxFuture<'static, Option<Result<T, Status>>>>,
+    done: bool,
+}
+
+impl<S, T> InterceptedResponses<S, T> {
+    pub(crate) fn new(inner: S, call: Arc<InterceptedCall>) -> Self {
+        Self {
+            inner,
+            call,
+            pending: None,
+            done: false,
+        }
+    }
+}
+
+impl<S, T> Stream for InterceptedResponses<S, T>
+where
+    S: Stream<Item = Result<T, Status>>,
+    T: Send + 'static,
+{
+    type Item = Result<T, Status>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(pending) = this.pending {
+                let item = ready!(pending.as_mut().poll(cx));
+                *this.pending = None;
+                if !matches!(item, Some(Ok(_))) {
+                    *this.done = true;
+                }
+                return Poll::Ready(item);
+            }
+
+            if *this.done {
+                return Poll::Ready(None);
+            }
+
+            let call = this.call.clone();
+            let pending: BoxFuture<'static, _> = match ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(Ok(mut message)) => Box::pin(async move {
+                    match call.response_message(&mut message).await {
+                        Ok(()) => Some(Ok(message)),
+                        Err(mut status) => {
+                            call.status(&mut status).await;
+                            Some(Err(status))
+                        }
+                    }
+                }),
+                Some(Err(mut status)) => Box::pin(async move {
+                    call.status(&mut status).await;
+                    Some(Err(status))
+                }),
+                None => Box::pin(async move {
+                    let mut status = Status::ok("");
+                    call.status(&mut status).await;
+                    (status.code() != Code::Ok).then_some(Err(status))
+                }),
+            };
+            *this.pending = Some(pending);
+        }
+    }
+}
+
+impl<S, T> fmt::Debug for InterceptedResponses<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterceptedResponses")
+            .field("call", &self.call)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Response,
+        codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder},
+        server::Grpc,
+        service::interceptor::InterceptedService,
+    };
+    use bytes::{Buf, BufMut, Bytes};
+    use http_body_util::{BodyExt, Full};
+    use std::sync::Mutex;
+    use tower::ServiceExt;
+    use tower_service::Service;
+
+    #[derive(Default)]
+    struct StringCodec;
+
+    impl Codec for StringCodec {
+        type Encode = String;
+        type Decode = String;
+        type Encoder = StringCodec;
+        type Decoder = StringCodec;
+
+        fn encoder(&mut self) -> Self::Encoder {
+            StringCodec
+        }
+
+        fn decoder(&mut self) -> Self::Decoder {
+            StringCodec
+        }
+    }
+
+    impl Encoder for StringCodec {
+        type Item = String;
+        type Error = Status;
+
+        fn encode(&mut self, item: String, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+            dst.put_slice(item.as_bytes());
+            Ok(())
+        }
+    }
+
+    impl Decoder for StringCodec {
+        type Item = String;
+        type Error = Status;
+
+        fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<String>, Status> {
+            let bytes = src.copy_to_bytes(src.remaining());
+            String::from_utf8(bytes.to_vec())
+                .map(Some)
+                .map_err(|_| Status::internal("invalid utf-8"))
+        }
+    }
+
+    #[derive(Default)]
+    struct Shout {
+        statuses: Mutex<Vec<(String, Code)>>,
+    }
+
+    impl AsyncInterceptor for Arc<Shout> {
+        async fn on_request(
+            &self,
+            _method: &GrpcMethod<'_>,
+            request: Request<()>,
+        ) -> Result<Request<()>, Status> {
+            tokio::task::yield_now().await;
+            match request.metadata().get("authorization") {
+                Some(_) => Ok(request),
+                None => Err(Status::unauthenticated("missing token")),
+            }
+        }
+
+        async fn on_request_message(
+            &self,
+            _method: &GrpcMethod<'_>,
+            message: &mut (dyn Any + Send),
+        ) -> Result<(), Status> {
+            let message = message.downcast_mut::<String>().unwrap();
+            *message = message.to_uppercase();
+            Ok(())
+        }
+
+        async fn on_response(
+            &self,
+            _method: &GrpcMethod<'_>,
+            metadata: &mut MetadataMap,
+        ) -> Result<(), Status> {
+            metadata.insert("x-intercepted", "true".parse().unwrap());
+            Ok(())
+        }
+
+        async fn on_response_message(
+            &self,
+            _method: &GrpcMethod<'_>,
+            message: &mut (dyn Any + Send),
+        ) -> Result<(), Status> {
+            message.downcast_mut::<String>().unwrap().push('!');
+            Ok(())
+        }
+
+        async fn on_status(&self, method: &GrpcMethod<'_>, status: &mut Status) {
+            self.statuses
+                .lock()
+                .unwrap()
+                .push((method.method().to_owned(), status.code()));
+        }
+    }
+
+    fn request(message: &str, token: bool) -> http::Request<Full<Bytes>> {
+        streaming_request(&[message], token)
+    }
+
+    fn streaming_request(messages: &[&str], token: bool) -> http::Request<Full<Bytes>> {
+        let mut body = vec![];
+        for message in messages {
+            body.put_u8(0);
+            body.put_u32(message.len() as u32);
+            body.put_slice(message.as_bytes());
+        }
+
+        let mut request = http::Request::builder().uri("/test.Echo/Say");
+        if token {
+            request = request.header("authorization", "Bearer secret");
+        }
+        request.body(Full::new(Bytes::from(body))).unwrap()
+    }
+
+    async fn echo(
+        interceptor: Arc<Shout>,
+        request: http::Request<Full<Bytes>>,
+    ) -> http::Response<crate::body::Body> {
+        let svc = tower::service_fn(|request: http::Request<Full<Bytes>>| async move {
+            let echo = tower::service_fn(|request: Request<String>| async move {
+                Ok::<_, Status>(Response::new(request.into_inner()))
+            });
+            Ok::<_, Status>(Grpc::new(StringCodec).unary(echo, request).await)
+        });
+
+        let response = InterceptedService::new(svc, Async::new(interceptor))
+            .oneshot(request)
+            .await
+            .unwrap();
+        let (parts, body) = response.into_parts();
+        http::Response::from_parts(parts, crate::body::Body::new(body))
+    }
+
+    #[tokio::test]
+    async fn intercepts_messages_and_status() {
+        let interceptor = Arc::new(Shout::default());
+
+        let response = echo(interceptor.clone(), request("hello", true)).await;
+        assert_eq!(response.headers()["x-intercepted"], "true");
+
+        let body = response.into_body().collect().await.unwrap();
+        let trailers = body.trailers().cloned().unwrap();
+        assert_eq!(trailers["grpc-status"], "0");
+        assert_eq!(&body.to_bytes()[5..], b"HELLO!");
+
+        assert_eq!(
+            *interceptor.statuses.lock().unwrap(),
+            [("Say".to_owned(), Code::Ok)]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_requests_reach_status_hook() {
+        let interceptor = Arc::new(Shout::default());
+
+        let response = echo(interceptor.clone(), request("hello", false)).await;
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        assert!(response.headers().get("x-intercepted").is_none());
+
+        assert_eq!(
+            *interceptor.statuses.lock().unwrap(),
+            [("Say".to_owned(), Code::Unauthenticated)]
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_without_grpc_server() {
+        let interceptor = Arc::new(Shout::default());
+        let called = Arc::new(AtomicBool::new(false));
+
+        let svc = tower::service_fn({
+            let called = called.clone();
+            move |_: http::Request<Full<Bytes>>| {
+                called.store(true, Ordering::Relaxed);
+                async { Ok::<_, Status>(http::Response::new(())) }
+            }
+        });
+        let mut svc = InterceptedService::new(svc, Async::new(interceptor.clone()));
+
+        let response = svc
+            .ready()
+            .await
+            .unwrap()
+            .call(request("hello", false))
+            .await
+            .unwrap();
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::Unauthenticated);
+        assert!(!called.load(Ordering::Relaxed));
+        assert_eq!(
+            *interceptor.statuses.lock().unwrap(),
+            [("Say".to_owned(), Code::Unauthenticated)]
+        );
+
+        let response = svc.oneshot(request("hello", true)).await.unwrap();
+        assert!(Status::from_header_map(response.headers()).is_none());
+        assert!(called.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn intercepts_streamed_messages() {
+        let interceptor = Arc::new(Shout::default());
+
+        let svc = tower::service_fn(|request: http::Request<Full<Bytes>>| async move {
+            let join = tower::service_fn(|request: Request<crate::Streaming<String>>| async move {
+                let mut stream = request.into_inner();
+                let mut joined = String::new();
+                while let Some(message) = stream.message().await? {
+                    joined.push_str(&message);
+                }
+                Ok::<_, Status>(Response::new(joined))
+            });
+            Ok::<_, Status>(Grpc::new(StringCodec).client_streaming(join, request).await)
+        });
+
+        let response = InterceptedService::new(svc, Async::new(interceptor.clone()))
+            .oneshot(streaming_request(&["a", "b"], true))
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap();
+        assert_eq!(&body.to_bytes()[5..], b"AB!");
+    }
+}
@@ -6,7 +6,7 @@ pub(crate) mod layered;
 pub(crate) mod router;
 
 #[doc(inline)]
-pub use self::interceptor::{Interceptor, InterceptorLayer};
+pub use self::interceptor::{AsyncInterceptor, Interceptor, InterceptorLayer};
 pub use self::layered::{LayerExt, Layered};
 #[doc(inline)]
 #[cfg(feature = "router")]
@@ -7,12 +7,24 @@ use std::{
     pin::Pin,
     task::{Context, Poll},
 };
-use tower::{Service, ServiceExt};
+use tower::{Layer, Service, ServiceExt};
 
 /// A [`Service`] router.
+///
+/// Requests are routed by the gRPC service name in their path. With
+/// [`Routes::add_host`], they can also be routed by their authority first, so
+/// that different services are served to different hosts.
 #[derive(Debug, Clone)]
 pub struct Routes {
     router: axum::Router,
+    hosts: Vec<VirtualHost>,
+}
+
+/// The routes served to the hosts matching a pattern.
+#[derive(Debug, Clone)]
+struct VirtualHost {
+    pattern: String,
+    routes: Routes,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -39,6 +51,33 @@ impl RoutesBuilder {
         self
     }
 
+    /// Add a new service wrapped in `layer`.
+    ///
+    /// See [`Routes::add_service_with_layer`] for more details.
+    pub fn add_service_with_layer<S, L>(&mut self, svc: S, layer: L) -> &mut Self
+    where
+        S: NamedService,
+        L: Layer<S>,
+        L::Service: Service<Request<Body>> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Response: axum::response::IntoResponse + Send,
+        <L::Service as Service<Request<Body>>>::Error: Into<crate::BoxError> + Send,
+        <L::Service as Service<Request<Body>>>::Future: Send + 'static,
+    {
+        let routes = self.routes.take().unwrap_or_default();
+        self.routes
+            .replace(routes.add_service_with_layer(svc, layer));
+        self
+    }
+
+    /// Serve `routes` to the hosts matching `pattern`.
+    ///
+    /// See [`Routes::add_host`] for more details.
+    pub fn add_host(&mut self, pattern: impl Into<String>, routes: Routes) -> &mut Self {
+        let current = self.routes.take().unwrap_or_default();
+        self.routes.replace(current.add_host(pattern, routes));
+        self
+    }
+
     /// Returns the routes with added services or empty [`Routes`] if no service was added
     pub fn routes(self) -> Routes {
         self.routes.unwrap_or_default()
@@ -49,6 +88,7 @@ impl Default for Routes {
     fn default() -> Self {
         Self {
             router: axum::Router::new().fallback(unimplemented),
+            hosts: Vec::new(),
         }
     }
 }
@@ -93,21 +133,133 @@ impl Routes {
         self
     }
 
+    /// Add a new service wrapped in `layer`.
+    ///
+    /// Unlike layers set on the whole server, `layer` only applies to calls to
+    /// this service, which is still routed by its [`NamedService::NAME`]. This
+    /// allows, for example, requiring authentication for an internal service
+    /// only.
+    ///
+    /// Errors returned by the layered service are turned into responses with
+    /// the matching [`Status`], or [`Code::Unknown`](crate::Code::Unknown) if
+    /// the error is not a status.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tonic::{body::Body, server::NamedService, service::Routes};
+    /// # use std::convert::Infallible;
+    /// # #[derive(Clone)]
+    /// # struct Svc;
+    /// # impl NamedService for Svc { const NAME: &'static str = "internal.Admin"; }
+    /// # impl tower::Service<http::Request<Body>> for Svc {
+    /// #     type Response = http::Response<Body>;
+    /// #     type Error = Infallible;
+    /// #     type Future = std::future::Ready<Result<Self::Response, Infallible>>;
+    /// #     fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Infallible>> { std::task::Poll::Ready(Ok(())) }
+    /// #     fn call(&mut self, _: http::Request<Body>) -> Self::Future { unimplemented!() }
+    /// # }
+    /// use std::time::Duration;
+    /// use tower::timeout::TimeoutLayer;
+    ///
+    /// let layer = TimeoutLayer::new(Duration::from_secs(5));
+    /// let routes = Routes::default().add_service_with_layer(Svc, layer);
+    /// ```
+    pub fn add_service_with_layer<S, L>(mut self, svc: S, layer: L) -> Self
+    where
+        S: NamedService,
+        L: Layer<S>,
+        L::Service: Service<Request<Body>> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request<Body>>>::Response: axum::response::IntoResponse + Send,
+        <L::Service as Service<Request<Body>>>::Error: Into<crate::BoxError> + Send,
+        <L::Service as Service<Request<Body>>>::Future: Send + 'static,
+    {
+        let svc = layer
+            .layer(svc)
+            .map_request(|req: Request<axum::body::Body>| req.map(Body::new));
+
+        self.router = self.router.route_service(
+            &format!("/{}/{{*rest}}", S::NAME),
+            axum::error_handling::HandleError::<_, _, ()>::new(
+                svc,
+                error_response::<<L::Service as Service<Request<Body>>>::Error>,
+            ),
+        );
+        self
+    }
+
+    /// Serve `routes` to the hosts matching `pattern`.
+    ///
+    /// Requests are matched on the host of their `:authority`, or of their
+    /// `Host` header, ignoring the port and case. A pattern is either an exact
+    /// host name like `a.example.com`, a suffix wildcard like `*.example.com`,
+    /// a prefix wildcard like `example.*` or `*`, which matches all hosts.
+    /// When several patterns match, an exact match is preferred over a suffix
+    /// wildcard, which is preferred over a prefix wildcard, and the longest
+    /// pattern wins among wildcards of the same kind.
+    ///
+    /// Requests for a host matching no pattern are routed to the services
+    /// added to these routes directly, which act as the default host.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tonic::{body::Body, server::NamedService, service::Routes};
+    /// # use std::convert::Infallible;
+    /// # #[derive(Clone)]
+    /// # struct Svc;
+    /// # impl NamedService for Svc { const NAME: &'static str = "store.Catalog"; }
+    /// # impl tower::Service<http::Request<Body>> for Svc {
+    /// #     type Response = http::Response<Body>;
+    /// #     type Error = Infallible;
+    /// #     type Future = std::future::Ready<Result<Self::Response, Infallible>>;
+    /// #     fn poll_ready(&mut self, _: &mut std::task::Context<'_>) -> std::task::Poll<Result<(), Infallible>> { std::task::Poll::Ready(Ok(())) }
+    /// #     fn call(&mut self, _: http::Request<Body>) -> Self::Future { unimplemented!() }
+    /// # }
+    /// # let (tenant_a, tenant_b, shared) = (Svc, Svc, Svc);
+    /// let routes = Routes::new(shared)
+    ///     .add_host("a.example.com", Routes::new(tenant_a))
+    ///     .add_host("*.b.example.com", Routes::new(tenant_b));
+    /// ```
+    pub fn add_host(mut self, pattern: impl Into<String>, routes: Routes) -> Self {
+        let pattern = pattern.into().to_ascii_lowercase();
+
+        match self.hosts.iter_mut().find(|host| host.pattern == pattern) {
+            Some(host) => host.routes = routes,
+            None => self.hosts.push(VirtualHost { pattern, routes }),
+        }
+        self
+    }
+
     /// This makes axum perform update some internals of the router that improves perf.
     ///
     /// See <https://docs.rs/axum/latest/axum/routing/struct.Router.html#a-note-about-performance>
     pub fn prepare(self) -> Self {
         Self {
             router: self.router.with_state(()),
+            hosts: self
+                .hosts
+                .into_iter()
+                .map(|host| VirtualHost {
+                    pattern: host.pattern,
+                    routes: host.routes.prepare(),
+                })
+                .collect(),
         }
     }
 
     /// Convert this `Routes` into an [`axum::Router`].
     pub fn into_axum_router(self) -> axum::Router {
-        self.router
+        if self.hosts.is_empty() {
+            self.router
+        } else {
+            axum::Router::new().fallback_service(self)
+        }
     }
 
     /// Get a mutable reference to the [`axum::Router`].
+    ///
+    /// This is the router of the default host, see [`Routes::add_host`].
     pub fn axum_router_mut(&mut self) -> &mut axum::Router {
         &mut self.router
     }
@@ -131,15 +283,79 @@ impl From<axum::Router> for RoutesBuilder {
 
 impl From<axum::Router> for Routes {
     fn from(router: axum::Router) -> Self {
-        Self { router }
+        Self {
+            router,
+            hosts: Vec::new(),
+        }
+    }
+}
+
+/// The host a request is sent to, from its URI or `Host` header.
+fn request_host<B>(req: &Request<B>) -> Option<&str> {
+    if let Some(authority) = req.uri().authority() {
+        return Some(authority.host());
+    }
+
+    let host = req.headers().get(http::header::HOST)?.to_str().ok()?;
+    // Strip the port, keeping the brackets of IPv6 addresses like the URI does.
+    match host.rfind(':') {
+        Some(i) if !host[i..].contains(']') => Some(&host[..i]),
+        _ => Some(host),
     }
 }
 
+/// How well a host pattern matched a host, better matches are smaller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum HostMatch {
+    Exact,
+    Suffix(std::cmp::Reverse<usize>),
+    Prefix(std::cmp::Reverse<usize>),
+    Any,
+}
+
+fn match_host(host: &str, pattern: &str) -> Option<HostMatch> {
+    if pattern == "*" {
+        return Some(HostMatch::Any);
+    }
+
+    if host.eq_ignore_ascii_case(pattern) {
+        return Some(HostMatch::Exact);
+    }
+
+    let host = host.to_ascii_lowercase();
+
+    if let Some(suffix) = pattern.strip_prefix('*')
+        && host.len() > suffix.len()
+        && host.ends_with(suffix)
+    {
+        return Some(HostMatch::Suffix(std::cmp::Reverse(suffix.len())));
+    }
+
+    if let Some(prefix) = pattern.strip_suffix('*')
+        && host.len() > prefix.len()
+        && host.starts_with(prefix)
+    {
+        return Some(HostMatch::Prefix(std::cmp::Reverse(prefix.len())));
+    }
+
+    None
+}
+
 async fn unimplemented() -> Response<Body> {
     let (parts, ()) = Status::unimplemented("").into_http::<()>().into_parts();
     Response::from_parts(parts, Body::empty())
 }
 
+async fn error_response<E>(err: E) -> Response<Body>
+where
+    E: Into<crate::BoxError>,
+{
+    let (parts, ()) = Status::from_error(err.into())
+        .into_http::<()>()
+        .into_parts();
+    Response::from_parts(parts, Body::empty())
+}
+
 impl<B> Service<Request<B>> for Routes
 where
     B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
@@ -155,6 +371,20 @@ where
     }
 
     fn call(&mut self, req: Request<B>) -> Self::Future {
+        if !self.hosts.is_empty() {
+            let host = request_host(&req).and_then(|host| {
+                self.hosts
+                    .iter_mut()
+                    .filter_map(|vh| Some((match_host(host, &vh.pattern)?, vh)))
+                    .min_by_key(|(score, _)| *score)
+            });
+
+            if let Some((_, vh)) = host {
+                // axum routers are always ready.
+                return vh.routes.call(req);
+            }
+        }
+
         RoutesFuture(self.router.call(req))
     }
 }
@@ -176,3 +406,136 @@ impl Future for RoutesFuture {
             .map_ok(|res| res.map(Body::new))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Code;
+
+    #[derive(Clone)]
+    struct Public;
+
+    impl NamedService for Public {
+        const NAME: &'static str = "test.Public";
+    }
+
+    #[derive(Clone)]
+    struct Internal;
+
+    impl NamedService for Internal {
+        const NAME: &'static str = "test.Internal";
+    }
+
+    macro_rules! ok_service {
+        ($svc:ty) => {
+            impl Service<Request<Body>> for $svc {
+                type Response = Response<Body>;
+                type Error = Infallible;
+                type Future = std::future::Ready<Result<Response<Body>, Infallible>>;
+
+                fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+                    Poll::Ready(Ok(()))
+                }
+
+                fn call(&mut self, _: Request<Body>) -> Self::Future {
+                    std::future::ready(Ok(Response::new(Body::empty())))
+                }
+            }
+        };
+    }
+
+    ok_service!(Public);
+    ok_service!(Internal);
+
+    fn deny<S>(
+        _: S,
+    ) -> impl Service<
+        Request<Body>,
+        Response = Response<Body>,
+        Error = Status,
+        Future = std::future::Ready<Result<Response<Body>, Status>>,
+    > + Clone {
+        tower::service_fn(|_: Request<Body>| {
+            std::future::ready(Err(Status::permission_denied("internal only")))
+        })
+    }
+
+    async fn call(routes: &Routes, path: &str) -> Option<Code> {
+        let request = Request::builder().uri(path).body(Body::empty()).unwrap();
+        let response = routes.clone().oneshot(request).await.unwrap();
+        Status::from_header_map(response.headers()).map(|status| status.code())
+    }
+
+    #[tokio::test]
+    async fn layer_applies_to_one_service() {
+        let routes = Routes::new(Public)
+            .add_service_with_layer(Internal, tower::layer::layer_fn(deny))
+            .prepare();
+
+        assert_eq!(call(&routes, "/test.Public/Get").await, None);
+        assert_eq!(
+            call(&routes, "/test.Internal/Get").await,
+            Some(Code::PermissionDenied)
+        );
+        assert_eq!(
+            call(&routes, "/test.Other/Get").await,
+            Some(Code::Unimplemented)
+        );
+    }
+
+    #[tokio::test]
+    async fn routes_by_host() {
+        let public = Routes::new(Public);
+        let internal = Routes::new(Internal);
+        let routes = Routes::default()
+            .add_host("internal.example.com", internal.clone())
+            .add_host("*.Example.com", public.clone())
+            .add_host("*.internal.example.com", internal)
+            .add_host("localhost", public)
+            .prepare();
+
+        let call = |host: &'static str, path: &'static str, header: bool| {
+            let mut request = Request::builder();
+            request = if header {
+                request.uri(path).header(http::header::HOST, host)
+            } else {
+                request.uri(format!("http://{host}{path}"))
+            };
+            let request = request.body(Body::empty()).unwrap();
+            let routes = routes.clone();
+            async move {
+                let response = routes.oneshot(request).await.unwrap();
+                Status::from_header_map(response.headers()).map(|status| status.code())
+            }
+        };
+
+        let unimplemented = Some(Code::Unimplemented);
+        assert_eq!(
+            call("internal.example.com", "/test.Internal/Get", false).await,
+            None
+        );
+        assert_eq!(
+            call("INTERNAL.example.com", "/test.Public/Get", false).await,
+            unimplemented
+        );
+        assert_eq!(
+            call("a.internal.example.com", "/test.Internal/Get", false).await,
+            None
+        );
+        assert_eq!(call("a.example.com", "/test.Public/Get", false).await, None);
+        assert_eq!(
+            call("a.example.com", "/test.Internal/Get", false).await,
+            unimplemented
+        );
+        assert_eq!(
+            call("localhost:50051", "/test.Public/Get", true).await,
+            None
+        );
+        // No service is added to the default host.
+        assert_eq!(
+            call("example.org", "/test.Public/Get", false).await,
+            unimplemented
+        );
+        assert_eq!(call("", "/test.Public/Get", true).await, unimplemented);
+    }
+}
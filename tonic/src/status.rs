@@ -622,7 +622,7 @@ impl Status {
     pub const GRPC_STATUS_DETAILS: HeaderName = HeaderName::from_static("grpc-status-details-bin");
 }
 
-fn find_status_in_source_chain(err: &(dyn Error + 'static)) -> Option<Status> {
+pub(crate) fn find_status_in_source_chain(err: &(dyn Error + 'static)) -> Option<Status> {
     let mut source = Some(err);
 
     while let Some(err) = source {
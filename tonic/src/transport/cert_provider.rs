@@ -0,0 +1,302 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+use crate::transport::tls::{Certificate, Identity};
+
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Provides the certificates of TLS handshakes, which may change over time.
+///
+/// A provider is set with `ServerTlsConfig::certificate_provider` or
+/// `ClientTlsConfig::certificate_provider`. Every new handshake uses the
+/// certificates returned by the provider at that time, so rotated
+/// certificates are picked up without restarting servers or rebuilding
+/// channels. Established connections keep the certificates they were
+/// negotiated with.
+///
+/// See [`FileWatcherProvider`] for a provider reading files.
+pub trait CertificateProvider: fmt::Debug + Send + Sync + 'static {
+    /// Returns the current certificates.
+    ///
+    /// This is called on every handshake, and the TLS configuration is
+    /// rebuilt whenever the returned `Arc` is not the one returned last, so
+    /// providers should keep returning the same `Arc` until their
+    /// certificates change.
+    fn certificates(&self) -> Arc<Certificates>;
+}
+
+/// The certificates returned by a [`CertificateProvider`].
+#[derive(Debug, Clone, Default)]
+pub struct Certificates {
+    pub(crate) identity: Option<Identity>,
+    pub(crate) ca_certificates: Vec<Certificate>,
+}
+
+impl Certificates {
+    /// Creates empty `Certificates`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the [`Identity`] presented to the peer.
+    pub fn identity(self, identity: Identity) -> Self {
+        Certificates {
+            identity: Some(identity),
+            ..self
+        }
+    }
+
+    /// Adds a CA certificate against which to verify the certificate of the
+    /// peer.
+    pub fn ca_certificate(self, ca_certificate: Certificate) -> Self {
+        let mut ca_certificates = self.ca_certificates;
+        ca_certificates.push(ca_certificate);
+        Certificates {
+            ca_certificates,
+            ..self
+        }
+    }
+}
+
+/// Configures the files read by a [`FileWatcherProvider`].
+#[derive(Debug, Clone)]
+pub struct FileWatcherConfig {
+    identity: Option<(PathBuf, PathBuf)>,
+    ca_certificate: Option<PathBuf>,
+    refresh_interval: Duration,
+}
+
+impl FileWatcherConfig {
+    /// Creates a new `FileWatcherConfig`, reading no files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the [`Identity`] from a PEM encoded certificate chain and private
+    /// key.
+    pub fn identity(self, cert_file: impl Into<PathBuf>, key_file: impl Into<PathBuf>) -> Self {
+        FileWatcherConfig {
+            identity: Some((cert_file.into(), key_file.into())),
+            ..self
+        }
+    }
+
+    /// Reads the CA certificates from a PEM encoded bundle.
+    pub fn ca_certificate(self, ca_file: impl Into<PathBuf>) -> Self {
+        FileWatcherConfig {
+            ca_certificate: Some(ca_file.into()),
+            ..self
+        }
+    }
+
+    /// Sets how long the certificates are used before the files are read
+    /// again.
+    ///
+    /// # Default
+    /// By default, the files are read again every 10 minutes.
+    pub fn refresh_interval(self, refresh_interval: Duration) -> Self {
+        FileWatcherConfig {
+            refresh_interval,
+            ..self
+        }
+    }
+
+    fn read(&self) -> io::Result<Files> {
+        let identity = match &self.identity {
+            Some((cert_file, key_file)) => Some((read(cert_file)?, read(key_file)?)),
+            None => None,
+        };
+        let ca_certificate = self.ca_certificate.as_deref().map(read).transpose()?;
+        Ok(Files {
+            identity,
+            ca_certificate,
+        })
+    }
+}
+
+impl Default for FileWatcherConfig {
+    fn default() -> Self {
+        Self {
+            identity: None,
+            ca_certificate: None,
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+        }
+    }
+}
+
+/// A [`CertificateProvider`] reading PEM encoded files.
+///
+/// The files are read again every refresh interval by a background task, so
+/// handshakes never wait for them. If a file can't be read, the previous
+/// certificates are kept until the next refresh.
+///
+/// ```no_run
+/// # use tonic::transport::{FileWatcherConfig, FileWatcherProvider, ServerTlsConfig};
+/// # use std::{sync::Arc, time::Duration};
+/// # async fn run() -> std::io::Result<()> {
+/// let provider = FileWatcherProvider::new(
+///     FileWatcherConfig::new()
+///         .identity("server.pem", "server.key")
+///         .ca_certificate("client_ca.pem")
+///         .refresh_interval(Duration::from_secs(60)),
+/// )?;
+///
+/// let tls = ServerTlsConfig::new().certificate_provider(Arc::new(provider));
+/// # Ok(())
+/// # }
+/// ```
+pub struct FileWatcherProvider {
+    config: FileWatcherConfig,
+    certificates: Arc<RwLock<Arc<Certificates>>>,
+    refresh: JoinHandle<()>,
+}
+
+/// The contents of the files read by a [`FileWatcherProvider`].
+#[derive(PartialEq, Eq)]
+struct Files {
+    identity: Option<(Vec<u8>, Vec<u8>)>,
+    ca_certificate: Option<Vec<u8>>,
+}
+
+impl FileWatcherProvider {
+    /// Creates a provider reading the files of `config`.
+    ///
+    /// Returns an error if a file can't be read.
+    ///
+    /// This must be called from within a tokio runtime.
+    pub fn new(config: FileWatcherConfig) -> io::Result<Self> {
+        let files = config.read()?;
+        let certificates = Arc::new(RwLock::new(Arc::new(files.certificates())));
+        let refresh = tokio::spawn(refresh(config.clone(), files, certificates.clone()));
+
+        Ok(Self {
+            config,
+            certificates,
+            refresh,
+        })
+    }
+}
+
+impl CertificateProvider for FileWatcherProvider {
+    fn certificates(&self) -> Arc<Certificates> {
+        self.certificates.read().unwrap().clone()
+    }
+}
+
+impl Drop for FileWatcherProvider {
+    fn drop(&mut self) {
+        self.refresh.abort();
+    }
+}
+
+/// Read the files of `config` every refresh interval, replacing the
+/// certificates when they changed.
+async fn refresh(
+    config: FileWatcherConfig,
+    mut files: Files,
+    certificates: Arc<RwLock<Arc<Certificates>>>,
+) {
+    loop {
+        tokio::time::sleep(config.refresh_interval).await;
+
+        let config = config.clone();
+        let read = tokio::task::spawn_blocking(move || config.read())
+            .await
+            .unwrap_or_else(|e| Err(io::Error::other(e)));
+        match read {
+            Ok(new_files) if new_files != files => {
+                tracing::debug!("certificate files changed");
+                *certificates.write().unwrap() = Arc::new(new_files.certificates());
+                files = new_files;
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("error reading certificate files: {e}"),
+        }
+    }
+}
+
+impl Files {
+    fn certificates(&self) -> Certificates {
+        let certificates = Certificates::new();
+        let certificates = match &self.identity {
+            Some((cert, key)) => certificates.identity(Identity::from_pem(cert, key)),
+            None => certificates,
+        };
+        match &self.ca_certificate {
+            Some(ca) => certificates.ca_certificate(Certificate::from_pem(ca)),
+            None => certificates,
+        }
+    }
+}
+
+impl fmt::Debug for FileWatcherProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileWatcherProvider")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+fn read(path: &Path) -> io::Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn file_watcher_rereads_changed_files() {
+        let dir = std::env::temp_dir().join(format!("tonic-cert-provider-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ca_file = dir.join("ca.pem");
+        std::fs::write(&ca_file, "first").unwrap();
+
+        let provider = FileWatcherProvider::new(
+            FileWatcherConfig::new()
+                .ca_certificate(&ca_file)
+                .refresh_interval(Duration::from_millis(10)),
+        )
+        .unwrap();
+
+        let first = provider.certificates();
+        assert_eq!(first.ca_certificates[0].get_ref(), b"first");
+        assert!(first.identity.is_none());
+        // Unchanged files keep the same certificates.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(Arc::ptr_eq(&first, &provider.certificates()));
+
+        std::fs::write(&ca_file, "second").unwrap();
+        let second = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let certificates = provider.certificates();
+                if !Arc::ptr_eq(&first, &certificates) {
+                    break certificates;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(second.ca_certificates[0].get_ref(), b"second");
+
+        // Files that can't be read keep the previous certificates.
+        std::fs::remove_file(&ca_file).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(Arc::ptr_eq(&second, &provider.certificates()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_watcher_requires_files() {
+        let config = FileWatcherConfig::new().ca_certificate("/nonexistent/ca.pem");
+        let err = FileWatcherProvider::new(config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,212 @@
+use std::time::Duration;
+use tower::util::rng::{HasherRng, Rng};
+
+/// Longer delays would overflow the deadlines computed from them.
+const MAX_DELAY: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Configures how long a channel waits before reconnecting after a failed
+/// connection attempt.
+///
+/// This follows the [gRPC connection backoff] protocol: the first reconnection
+/// is attempted `initial_backoff` after the previous attempt started, and every
+/// following failure multiplies that delay by `multiplier`, up to
+/// `max_backoff`. Each delay is randomly spread by `jitter` so that clients
+/// which lost the same backend do not reconnect all at once. An attempt is
+/// given at least `min_connect_timeout` to complete.
+///
+/// While the channel waits to reconnect, calls fail immediately with
+/// [`Code::Unavailable`], unless they are [wait-for-ready], in which case they
+/// wait until the channel is connected or their deadline expires.
+///
+/// ```
+/// # use tonic::transport::{Endpoint, channel::ConnectionBackoff};
+/// # use std::time::Duration;
+/// let backoff = ConnectionBackoff::new()
+///     .initial_backoff(Duration::from_millis(100))
+///     .max_backoff(Duration::from_secs(10));
+///
+/// let endpoint = Endpoint::from_static("http://[::1]:50051").connection_backoff(backoff);
+/// ```
+///
+/// [gRPC connection backoff]: https://github.com/grpc/grpc/blob/master/doc/connection-backoff.md
+/// [`Code::Unavailable`]: crate::Code::Unavailable
+/// [wait-for-ready]: crate::Request::set_wait_for_ready
+#[derive(Debug, Clone)]
+pub struct ConnectionBackoff {
+    initial_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    max_backoff: Duration,
+    min_connect_timeout: Duration,
+}
+
+impl ConnectionBackoff {
+    /// Create a new `ConnectionBackoff` with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the delay before the first reconnection.
+    ///
+    /// Defaults to 1 second.
+    pub fn initial_backoff(self, initial_backoff: Duration) -> Self {
+        ConnectionBackoff {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    /// Set the factor the delay grows by after each failed attempt.
+    ///
+    /// Defaults to 1.6.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `multiplier` is NaN or infinite.
+    pub fn multiplier(self, multiplier: f64) -> Self {
+        assert!(multiplier.is_finite(), "multiplier must be finite");
+        ConnectionBackoff { multiplier, ..self }
+    }
+
+    /// Set how much each delay is randomly spread, as a fraction of the delay.
+    ///
+    /// Defaults to 0.2, meaning that a delay of 1 second is randomly chosen
+    /// between 0.8 and 1.2 seconds.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `jitter` is NaN or infinite.
+    pub fn jitter(self, jitter: f64) -> Self {
+        assert!(jitter.is_finite(), "jitter must be finite");
+        ConnectionBackoff {
+            jitter: jitter.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Set the maximum delay between two attempts.
+    ///
+    /// Defaults to 120 seconds.
+    pub fn max_backoff(self, max_backoff: Duration) -> Self {
+        ConnectionBackoff {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Set the minimum time a connection attempt is given to complete.
+    ///
+    /// Defaults to 20 seconds.
+    pub fn min_connect_timeout(self, min_connect_timeout: Duration) -> Self {
+        ConnectionBackoff {
+            min_connect_timeout,
+            ..self
+        }
+    }
+}
+
+impl Default for ConnectionBackoff {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            multiplier: 1.6,
+            jitter: 0.2,
+            max_backoff: Duration::from_secs(120),
+            min_connect_timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+/// The backoff state of a connection.
+#[derive(Debug)]
+pub(crate) struct Backoff {
+    config: ConnectionBackoff,
+    current: Duration,
+    rng: HasherRng,
+}
+
+impl Backoff {
+    pub(crate) fn new(config: ConnectionBackoff) -> Self {
+        Self {
+            current: config.initial_backoff,
+            config,
+            rng: HasherRng::default(),
+        }
+    }
+
+    /// The time to wait before the next attempt, measured from the start of
+    /// the current one.
+    pub(crate) fn delay(&mut self) -> Duration {
+        let jitter = self.config.jitter * (self.rng.next_f64() * 2.0 - 1.0);
+        saturating_mul(self.current, 1.0 + jitter).min(MAX_DELAY)
+    }
+
+    /// How long an attempt which must complete by `delay` is given.
+    pub(crate) fn connect_timeout(&self, delay: Duration) -> Duration {
+        delay.max(self.config.min_connect_timeout)
+    }
+
+    /// Grow the delay after a failed attempt.
+    pub(crate) fn failed(&mut self) {
+        self.current = saturating_mul(self.current, self.config.multiplier.max(1.0))
+            .min(self.config.max_backoff);
+    }
+
+    /// Start over after a successful connection.
+    pub(crate) fn reset(&mut self) {
+        self.current = self.config.initial_backoff;
+    }
+}
+
+fn saturating_mul(duration: Duration, factor: f64) -> Duration {
+    Duration::try_from_secs_f64(duration.as_secs_f64() * factor).unwrap_or(Duration::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_grow_with_jitter_up_to_max() {
+        let mut backoff = Backoff::new(
+            ConnectionBackoff::new()
+                .initial_backoff(Duration::from_secs(1))
+                .multiplier(2.0)
+                .max_backoff(Duration::from_secs(5)),
+        );
+
+        for expected in [1, 2, 4, 5, 5] {
+            let expected = Duration::from_secs(expected);
+            let delay = backoff.delay();
+            assert!(delay >= expected.mul_f64(0.8) && delay <= expected.mul_f64(1.2));
+            backoff.failed();
+        }
+
+        backoff.reset();
+        assert!(backoff.delay() <= Duration::from_millis(1200));
+        assert_eq!(
+            backoff.connect_timeout(Duration::from_secs(1)),
+            Duration::from_secs(20)
+        );
+    }
+
+    #[test]
+    fn long_delays_saturate() {
+        let mut backoff = Backoff::new(
+            ConnectionBackoff::new()
+                .initial_backoff(Duration::MAX)
+                .max_backoff(Duration::MAX),
+        );
+
+        for _ in 0..3 {
+            assert_eq!(backoff.delay(), MAX_DELAY);
+            backoff.failed();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "jitter must be finite")]
+    fn rejects_nan_jitter() {
+        let _ = ConnectionBackoff::new().jitter(f64::NAN);
+    }
+}
@@ -0,0 +1,342 @@
+use super::BoxFuture;
+use crate::metadata::AsciiMetadataKey;
+use http::Request;
+use std::{
+    fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tower::{
+    discover::{Change, Discover},
+    load::Load,
+    ready_cache::ReadyCache,
+    util::rng::{HasherRng, Rng},
+};
+use tower_service::Service;
+use tracing::{debug, trace};
+
+/// How a balanced [`Channel`](super::Channel) picks the endpoint of each call.
+///
+/// Only endpoints that are connected and ready to send a call are picked
+/// from, calls wait for one to be ready otherwise.
+#[derive(Debug, Clone, Default)]
+pub enum LoadBalancingPolicy {
+    /// Pick the least loaded of two random endpoints, the load being the
+    /// number of calls waiting for their response headers.
+    #[default]
+    PowerOfTwoChoices,
+    /// Pick every endpoint in turn, in the order they were added.
+    RoundRobin,
+    /// Send every call to the first endpoint that was added, until it is not
+    /// ready anymore, then to the next one.
+    PickFirst,
+    /// Pick the endpoint with the fewest calls waiting for their response
+    /// headers.
+    LeastRequest,
+    /// Pick an endpoint from the value of a metadata entry of the call, so
+    /// that calls with the same value go to the same endpoint while it is
+    /// ready.
+    ///
+    /// Endpoints are picked with rendezvous hashing: when an endpoint is added
+    /// or removed, only the values mapped to it move to another endpoint.
+    /// Calls without the metadata entry are spread round-robin.
+    ConsistentHash(AsciiMetadataKey),
+}
+
+/// Balances calls across the services of `discover` according to a
+/// [`LoadBalancingPolicy`].
+///
+/// Some policies need the request to pick the endpoint, so it is picked in
+/// `call`, among the services that are ready.
+pub(crate) struct Balancer<D, B>
+where
+    D: Discover,
+    D::Key: Hash,
+{
+    discover: D,
+    services: ReadyCache<D::Key, D::Service, Request<B>>,
+    /// The keys of the services, in the order they were added.
+    keys: Vec<D::Key>,
+    next: usize,
+    policy: LoadBalancingPolicy,
+    rng: HasherRng,
+}
+
+impl<D, B> Balancer<D, B>
+where
+    D: Discover,
+    D::Key: Hash,
+    D::Service: Service<Request<B>>,
+    <D::Service as Service<Request<B>>>::Error: Into<crate::BoxError>,
+{
+    pub(crate) fn new(discover: D, policy: LoadBalancingPolicy) -> Self {
+        Self {
+            discover,
+            services: ReadyCache::default(),
+            keys: Vec::new(),
+            next: 0,
+            policy,
+            rng: HasherRng::default(),
+        }
+    }
+}
+
+impl<D, B> Balancer<D, B>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Clone,
+    D::Error: Into<crate::BoxError>,
+    D::Service: Service<Request<B>> + Load<Metric = usize>,
+    <D::Service as Service<Request<B>>>::Error: Into<crate::BoxError>,
+{
+    fn update_from_discover(&mut self, cx: &mut Context<'_>) -> Result<(), crate::BoxError> {
+        while let Poll::Ready(Some(change)) = Pin::new(&mut self.discover).poll_discover(cx) {
+            match change.map_err(Into::into)? {
+                Change::Insert(key, service) => {
+                    trace!("insert");
+                    if !self.keys.contains(&key) {
+                        self.keys.push(key.clone());
+                    }
+                    self.services.push(key, service);
+                }
+                Change::Remove(key) => {
+                    trace!("remove");
+                    self.keys.retain(|k| *k != key);
+                    self.services.evict(&key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn promote_pending_to_ready(&mut self, cx: &mut Context<'_>) {
+        while let Poll::Ready(Err(error)) = self.services.poll_pending(cx) {
+            debug!(%error, "dropping failed endpoint");
+        }
+
+        // Services may have stopped being ready since they were promoted.
+        // Going backwards, the service swapped in place of one that is not
+        // ready anymore was already checked.
+        for index in (0..self.services.ready_len()).rev() {
+            if let Err(error) = self.services.check_ready_index(cx, index) {
+                debug!(%error, "endpoint failed");
+            }
+        }
+    }
+
+    fn ready_index(&mut self, request: &Request<B>) -> usize {
+        match &self.policy {
+            LoadBalancingPolicy::PowerOfTwoChoices => match self.services.ready_len() {
+                1 => 0,
+                len => {
+                    let a = self.rng.next_range(0..len as u64) as usize;
+                    let b = self.rng.next_range(0..len as u64 - 1) as usize;
+                    let b = if b >= a { b + 1 } else { b };
+                    [a, b]
+                        .into_iter()
+                        .min_by_key(|index| {
+                            let (_, service) = self.services.get_ready_index(*index).unwrap();
+                            service.load()
+                        })
+                        .unwrap()
+                }
+            },
+            LoadBalancingPolicy::RoundRobin => self.round_robin_index(),
+            LoadBalancingPolicy::PickFirst => self
+                .keys
+                .iter()
+                .find_map(|key| self.services.get_ready(key))
+                .map(|(index, _, _)| index)
+                .expect("a service is ready"),
+            LoadBalancingPolicy::LeastRequest => (0..self.services.ready_len())
+                .min_by_key(|index| {
+                    let (_, service) = self.services.get_ready_index(*index).unwrap();
+                    service.load()
+                })
+                .expect("a service is ready"),
+            LoadBalancingPolicy::ConsistentHash(key) => match request.headers().get(key.as_str()) {
+                Some(value) => (0..self.services.ready_len())
+                    .max_by_key(|index| {
+                        let (key, _) = self.services.get_ready_index(*index).unwrap();
+                        let mut hasher = DefaultHasher::new();
+                        key.hash(&mut hasher);
+                        value.as_bytes().hash(&mut hasher);
+                        hasher.finish()
+                    })
+                    .expect("a service is ready"),
+                None => self.round_robin_index(),
+            },
+        }
+    }
+
+    fn round_robin_index(&mut self) -> usize {
+        let len = self.keys.len();
+        (0..len)
+            .find_map(|i| {
+                let position = (self.next + i) % len;
+                let (index, _, _) = self.services.get_ready(&self.keys[position])?;
+                self.next = position + 1;
+                Some(index)
+            })
+            .expect("a service is ready")
+    }
+}
+
+impl<D, B> Service<Request<B>> for Balancer<D, B>
+where
+    D: Discover + Unpin,
+    D::Key: Hash + Clone,
+    D::Error: Into<crate::BoxError>,
+    D::Service: Service<Request<B>> + Load<Metric = usize>,
+    <D::Service as Service<Request<B>>>::Error: Into<crate::BoxError>,
+    <D::Service as Service<Request<B>>>::Future: Send + 'static,
+{
+    type Response = <D::Service as Service<Request<B>>>::Response;
+    type Error = crate::BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.update_from_discover(cx)?;
+        self.promote_pending_to_ready(cx);
+
+        if self.services.ready_len() == 0 {
+            return Poll::Pending;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let index = self.ready_index(&request);
+        let future = self.services.call_ready_index(index, request);
+        Box::pin(async move { future.await.map_err(Into::into) })
+    }
+}
+
+impl<D, B> fmt::Debug for Balancer<D, B>
+where
+    D: Discover,
+    D::Key: Hash,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Balancer")
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        convert::Infallible,
+        future::{Ready, ready},
+    };
+    use tokio_stream::Stream;
+    use tower::ServiceExt;
+
+    /// An endpoint with its name and load.
+    #[derive(Clone)]
+    struct Endpoint(&'static str, usize);
+
+    impl Service<Request<()>> for Endpoint {
+        type Response = &'static str;
+        type Error = Infallible;
+        type Future = Ready<Result<&'static str, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _: Request<()>) -> Self::Future {
+            ready(Ok(self.0))
+        }
+    }
+
+    impl Load for Endpoint {
+        type Metric = usize;
+
+        fn load(&self) -> usize {
+            self.1
+        }
+    }
+
+    type Changes =
+        Pin<Box<dyn Stream<Item = Result<Change<&'static str, Endpoint>, Infallible>> + Send>>;
+
+    fn balancer(policy: LoadBalancingPolicy) -> Balancer<Changes, ()> {
+        balancer_with_loads(policy, [0, 0, 0])
+    }
+
+    fn balancer_with_loads(
+        policy: LoadBalancingPolicy,
+        loads: [usize; 3],
+    ) -> Balancer<Changes, ()> {
+        let changes = ["a", "b", "c"]
+            .into_iter()
+            .zip(loads)
+            .map(|(name, load)| Ok(Change::Insert(name, Endpoint(name, load))))
+            .collect::<Vec<_>>();
+        Balancer::new(Box::pin(tokio_stream::iter(changes)), policy)
+    }
+
+    async fn call(balancer: &mut Balancer<Changes, ()>, user: Option<&str>) -> &'static str {
+        let mut request = Request::new(());
+        if let Some(user) = user {
+            request
+                .headers_mut()
+                .insert("x-user", user.parse().unwrap());
+        }
+        balancer.ready().await.unwrap().call(request).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn round_robin() {
+        let mut balancer = balancer(LoadBalancingPolicy::RoundRobin);
+        let mut picked = Vec::new();
+        for _ in 0..6 {
+            picked.push(call(&mut balancer, None).await);
+        }
+        assert_eq!(picked, ["a", "b", "c", "a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn pick_first() {
+        let mut balancer = balancer(LoadBalancingPolicy::PickFirst);
+        for _ in 0..3 {
+            assert_eq!(call(&mut balancer, None).await, "a");
+        }
+    }
+
+    #[tokio::test]
+    async fn least_request() {
+        let mut balancer = balancer_with_loads(LoadBalancingPolicy::LeastRequest, [3, 1, 2]);
+        for _ in 0..3 {
+            assert_eq!(call(&mut balancer, None).await, "b");
+        }
+    }
+
+    #[tokio::test]
+    async fn power_of_two_choices() {
+        let mut balancer = balancer_with_loads(LoadBalancingPolicy::PowerOfTwoChoices, [3, 1, 2]);
+        // The most loaded endpoint loses against any other one.
+        for _ in 0..20 {
+            assert_ne!(call(&mut balancer, None).await, "a");
+        }
+    }
+
+    #[tokio::test]
+    async fn consistent_hash() {
+        let key = AsciiMetadataKey::from_static("x-user");
+        let mut balancer = balancer(LoadBalancingPolicy::ConsistentHash(key));
+
+        for user in ["alice", "bob", "carol", "dave"] {
+            let first = call(&mut balancer, Some(user)).await;
+            for _ in 0..3 {
+                assert_eq!(call(&mut balancer, Some(user)).await, first);
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::{Arc, Mutex, Weak};
+use tokio::sync::{Notify, watch};
+
+/// The state of the connection of a [`Channel`](super::Channel).
+///
+/// A channel begins in the `Idle` state. When a call is made, it moves to
+/// `Connecting`, then to `Ready` once connected. If connecting fails, the
+/// channel is in `TransientFailure` until it tries again. When the connection
+/// closes, the channel goes back to `Idle`, whether or not calls are made.
+///
+/// A balanced channel is `Ready` if any of its endpoints is, otherwise
+/// `Connecting` if any endpoint is connecting, otherwise `Idle` if any endpoint
+/// is idle or if it has no endpoints, and `TransientFailure` if all its
+/// endpoints failed to connect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectivityState {
+    /// Not connected, a connection is made on the next call.
+    Idle,
+    /// A connection attempt is in progress.
+    Connecting,
+    /// Connected and able to send calls.
+    Ready,
+    /// The last connection attempt failed, waiting to try again.
+    TransientFailure,
+    /// The channel was shut down and does not connect anymore.
+    Shutdown,
+}
+
+/// The connectivity state of a connection, shared with its channel.
+#[derive(Debug, Clone)]
+pub(crate) struct Connectivity {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: watch::Sender<ConnectivityState>,
+    last_error: Mutex<Option<String>>,
+    /// The balanced channel this connection is part of.
+    parent: Mutex<Weak<Inner>>,
+    /// The connections a balanced channel is made of.
+    members: Mutex<Vec<Connectivity>>,
+    /// Notified when one of the members fails to connect.
+    member_failed: Notify,
+}
+
+impl Connectivity {
+    pub(crate) fn new() -> Self {
+        Self::with_state(ConnectivityState::Idle)
+    }
+
+    pub(crate) fn with_state(state: ConnectivityState) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                state: watch::Sender::new(state),
+                last_error: Mutex::new(None),
+                parent: Mutex::new(Weak::new()),
+                members: Mutex::new(Vec::new()),
+                member_failed: Notify::new(),
+            }),
+        }
+    }
+
+    pub(crate) fn state(&self) -> ConnectivityState {
+        *self.inner.state.borrow()
+    }
+
+    pub(crate) fn set(&self, state: ConnectivityState) {
+        self.update(|current| {
+            let changed = *current != state;
+            *current = state;
+            changed
+        });
+    }
+
+    /// Enter [`ConnectivityState::Idle`] because the connection closed, unless
+    /// the state already moved on from [`ConnectivityState::Ready`].
+    pub(crate) fn closed(&self) {
+        self.update(|current| {
+            let ready = *current == ConnectivityState::Ready;
+            if ready {
+                *current = ConnectivityState::Idle;
+            }
+            ready
+        });
+    }
+
+    fn update(&self, modify: impl FnOnce(&mut ConnectivityState) -> bool) {
+        if self.inner.state.send_if_modified(modify) {
+            let parent = self.inner.parent.lock().unwrap().upgrade();
+            if let Some(inner) = parent {
+                Connectivity { inner }.aggregate();
+            }
+        }
+    }
+
+    /// Enter [`ConnectivityState::TransientFailure`] because of `error`.
+    pub(crate) fn failed(&self, error: &crate::BoxError) {
+        *self.inner.last_error.lock().unwrap() = Some(error.to_string());
+        self.set(ConnectivityState::TransientFailure);
+
+        if let Some(parent) = self.inner.parent.lock().unwrap().upgrade() {
+            parent.member_failed.notify_one();
+        }
+    }
+
+    /// Wait for one of the members of a balanced channel to fail to connect.
+    pub(crate) async fn member_failed(&self) {
+        self.inner.member_failed.notified().await
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<ConnectivityState> {
+        self.inner.state.subscribe()
+    }
+
+    /// The status failing calls while in [`ConnectivityState::TransientFailure`].
+    pub(crate) fn unavailable(&self) -> crate::Status {
+        match &*self.inner.last_error.lock().unwrap() {
+            Some(error) => crate::Status::unavailable(format!("connection failed: {error}")),
+            None => crate::Status::unavailable("connection failed"),
+        }
+    }
+
+    /// Make the state of `self` follow the state of `member`, along with its
+    /// other members.
+    pub(crate) fn add_member(&self, member: Connectivity) {
+        *member.inner.parent.lock().unwrap() = Arc::downgrade(&self.inner);
+        self.inner.members.lock().unwrap().push(member);
+        self.aggregate();
+    }
+
+    pub(crate) fn remove_member(&self, member: &Connectivity) {
+        self.inner
+            .members
+            .lock()
+            .unwrap()
+            .retain(|m| !Arc::ptr_eq(&m.inner, &member.inner));
+        self.aggregate();
+    }
+
+    fn aggregate(&self) {
+        let states = self
+            .inner
+            .members
+            .lock()
+            .unwrap()
+            .iter()
+            .map(Connectivity::state)
+            .filter(|state| *state != ConnectivityState::Shutdown)
+            .collect::<Vec<_>>();
+
+        let state = [
+            ConnectivityState::Ready,
+            ConnectivityState::Connecting,
+            ConnectivityState::Idle,
+        ]
+        .into_iter()
+        .find(|state| states.contains(state))
+        .unwrap_or(if states.is_empty() {
+            ConnectivityState::Idle
+        } else {
+            ConnectivityState::TransientFailure
+        });
+
+        self.set(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balanced_state_follows_members() {
+        let balanced = Connectivity::new();
+        let a = Connectivity::new();
+        let b = Connectivity::new();
+        balanced.add_member(a.clone());
+        balanced.add_member(b.clone());
+
+        a.set(ConnectivityState::TransientFailure);
+        assert_eq!(balanced.state(), ConnectivityState::Idle);
+
+        b.set(ConnectivityState::TransientFailure);
+        assert_eq!(balanced.state(), ConnectivityState::TransientFailure);
+
+        b.set(ConnectivityState::Connecting);
+        assert_eq!(balanced.state(), ConnectivityState::Connecting);
+
+        a.set(ConnectivityState::Ready);
+        assert_eq!(balanced.state(), ConnectivityState::Ready);
+
+        balanced.remove_member(&a);
+        assert_eq!(balanced.state(), ConnectivityState::Connecting);
+
+        b.set(ConnectivityState::Shutdown);
+        assert_eq!(balanced.state(), ConnectivityState::Idle);
+    }
+}
@@ -0,0 +1,257 @@
+use super::{
+    Change, Channel, Endpoint, Executor, LoadBalancingPolicy, connectivity::Connectivity,
+    endpoint::EndpointType,
+};
+use crate::transport::Error;
+use http::{Uri, uri::Authority};
+use std::{collections::HashSet, fmt, net::SocketAddr, time::Duration};
+use tokio::{
+    net::lookup_host,
+    sync::mpsc::Sender,
+    time::{Instant, sleep_until, timeout},
+};
+
+const DEFAULT_PORT: u16 = 443;
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// Connection failures do not cause resolutions more often than this.
+const MIN_RESOLUTION_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Balances calls across all the addresses a DNS name resolves to.
+///
+/// The name is resolved when the channel is created, then every
+/// `refresh_interval` and whenever a connection to one of its addresses
+/// fails. Addresses that appear are connected to, and the connections to
+/// addresses that disappear are closed, so the channel follows services whose
+/// backends change, such as headless Kubernetes services.
+///
+/// Each address is connected to with the settings of the [`Endpoint`] given
+/// to [`DnsBalance::endpoint`], using the scheme of its URI. The `:authority`
+/// of the calls is the resolved name, unless the endpoint has an
+/// [`origin`](Endpoint::origin). With TLS, the name the certificates are
+/// verified against must be set with
+/// [`ClientTlsConfig::domain_name`](super::ClientTlsConfig::domain_name).
+///
+/// ```no_run
+/// # use tonic::transport::channel::DnsBalance;
+/// # use std::time::Duration;
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), tonic::transport::Error> {
+/// let channel = DnsBalance::new("dns:///greeter.default.svc.cluster.local:50051")?
+///     .refresh_interval(Duration::from_secs(10))
+///     .channel();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DnsBalance {
+    host: String,
+    authority: Authority,
+    port: u16,
+    endpoint: Endpoint,
+    refresh_interval: Duration,
+    policy: LoadBalancingPolicy,
+}
+
+impl DnsBalance {
+    /// Balance across the addresses of `target`.
+    ///
+    /// The target is a name and an optional port, which defaults to 443, as in
+    /// `example.com:50051`. It can be prefixed by `dns:` or `dns:///`, the gRPC
+    /// naming scheme for DNS names. Naming a DNS server, as in
+    /// `dns://8.8.8.8/example.com`, is not supported: the name is resolved with
+    /// the resolver of the system.
+    pub fn new(target: &str) -> Result<Self, Error> {
+        let name = match target.strip_prefix("dns:") {
+            Some(name) => match name.strip_prefix("//") {
+                Some(name) => name.strip_prefix('/').ok_or_else(Error::new_invalid_uri)?,
+                None => name,
+            },
+            None => target,
+        };
+
+        let authority = name
+            .parse::<Authority>()
+            .map_err(|e| Error::new_invalid_uri().with(e))?;
+        let host = authority.host();
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host)
+            .to_owned();
+        let port = authority.port_u16().unwrap_or(DEFAULT_PORT);
+
+        let uri = Uri::builder()
+            .scheme("http")
+            .authority(authority.clone())
+            .path_and_query("/")
+            .build()
+            .map_err(|e| Error::new_invalid_uri().with(e))?;
+
+        Ok(Self {
+            host,
+            authority,
+            port,
+            endpoint: Endpoint::from(uri),
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            policy: LoadBalancingPolicy::default(),
+        })
+    }
+
+    /// Connect to the resolved addresses with the settings of `endpoint`.
+    ///
+    /// The host and port of its URI are replaced by each address, its scheme
+    /// is kept.
+    pub fn endpoint(self, endpoint: Endpoint) -> Self {
+        DnsBalance { endpoint, ..self }
+    }
+
+    /// Resolve the name again after `refresh_interval`.
+    ///
+    /// Defaults to 30 seconds.
+    pub fn refresh_interval(self, refresh_interval: Duration) -> Self {
+        DnsBalance {
+            refresh_interval,
+            ..self
+        }
+    }
+
+    /// Balance calls across the addresses according to `policy`.
+    pub fn policy(self, policy: LoadBalancingPolicy) -> Self {
+        DnsBalance { policy, ..self }
+    }
+
+    /// Create the channel, resolving the name in the background.
+    ///
+    /// This must be called from within a tokio runtime.
+    pub fn channel(self) -> Channel {
+        let executor = self.endpoint.executor.clone();
+        let (channel, tx) = Channel::balance_channel_inner(
+            super::DEFAULT_BUFFER_SIZE,
+            Some(&self.endpoint),
+            self.policy.clone(),
+        );
+
+        let connectivity = channel.connectivity.clone();
+        executor.execute(Box::pin(self.resolve(tx, connectivity)));
+
+        channel
+    }
+
+    async fn resolve(self, tx: Sender<Change<SocketAddr, Endpoint>>, connectivity: Connectivity) {
+        let mut addrs = HashSet::new();
+
+        loop {
+            let resolved_at = Instant::now();
+
+            let resolved = lookup_host((self.host.as_str(), self.port))
+                .await
+                .map(|resolved| resolved.collect::<HashSet<_>>());
+
+            match resolved {
+                // Keep the known addresses rather than failing every call.
+                Ok(resolved) if resolved.is_empty() => {
+                    tracing::debug!("{} resolved to no addresses", self.host);
+                }
+                Ok(resolved) => {
+                    for addr in addrs.difference(&resolved) {
+                        if tx.send(Change::Remove(*addr)).await.is_err() {
+                            return;
+                        }
+                    }
+
+                    for addr in resolved.difference(&addrs) {
+                        let endpoint = self.endpoint_for(*addr);
+                        if tx.send(Change::Insert(*addr, endpoint)).await.is_err() {
+                            return;
+                        }
+                    }
+
+                    addrs = resolved;
+                }
+                Err(error) => tracing::debug!("failed to resolve {}: {error}", self.host),
+            }
+
+            // Resolve again after the refresh interval, or earlier when a
+            // connection fails.
+            let _ = timeout(self.refresh_interval, connectivity.member_failed()).await;
+            sleep_until(resolved_at + MIN_RESOLUTION_INTERVAL).await;
+
+            if tx.is_closed() {
+                return;
+            }
+        }
+    }
+
+    fn endpoint_for(&self, addr: SocketAddr) -> Endpoint {
+        let scheme = self.endpoint.uri().scheme_str().unwrap_or("http");
+        let uri = format!("{scheme}://{addr}")
+            .parse::<Uri>()
+            .expect("valid uri");
+
+        let mut endpoint = self.endpoint.clone();
+        if endpoint.origin.is_none() {
+            endpoint.origin = Some(
+                Uri::builder()
+                    .scheme(scheme)
+                    .authority(self.authority.clone())
+                    .path_and_query("/")
+                    .build()
+                    .expect("valid uri"),
+            );
+        }
+        endpoint.uri = EndpointType::Uri(uri);
+        endpoint
+    }
+}
+
+impl fmt::Debug for DnsBalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsBalance")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("refresh_interval", &self.refresh_interval)
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_targets() {
+        for (target, host, port) in [
+            ("dns:///example.com:50051", "example.com", 50051),
+            ("dns:example.com", "example.com", 443),
+            ("example.com:80", "example.com", 80),
+            ("dns:///[::1]:50051", "::1", 50051),
+        ] {
+            let balance = DnsBalance::new(target).unwrap();
+            assert_eq!((balance.host.as_str(), balance.port), (host, port));
+        }
+
+        assert!(DnsBalance::new("dns://8.8.8.8/example.com").is_err());
+    }
+
+    #[tokio::test]
+    async fn inserts_resolved_addresses() {
+        let balance = DnsBalance::new("dns:///localhost:50051").unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+        tokio::spawn(balance.resolve(tx, Connectivity::new()));
+
+        let Some(Change::Insert(addr, endpoint)) = rx.recv().await else {
+            panic!("expected an address to be inserted");
+        };
+        assert!(addr.ip().is_loopback());
+        assert_eq!(addr.port(), 50051);
+        assert_eq!(
+            endpoint.uri(),
+            &format!("http://{addr}").parse::<Uri>().unwrap()
+        );
+        assert_eq!(
+            endpoint.origin,
+            Some(Uri::from_static("http://localhost:50051/"))
+        );
+    }
+}
@@ -1,10 +1,15 @@
-use super::Channel;
 #[cfg(feature = "_tls-any")]
 use super::ClientTlsConfig;
+#[cfg(feature = "service-config")]
+use super::ServiceConfig;
 #[cfg(feature = "_tls-any")]
 use super::service::TlsConnector;
 use super::service::{self, Executor, SharedExec};
 use super::uds_connector::UdsConnector;
+use super::{
+    Channel, ConnectionBackoff, HedgingPolicy, RetryPolicy, RetryThrottle,
+    retry::{CallPolicy, Throttle},
+};
 use crate::transport::Error;
 #[cfg(feature = "_tls-any")]
 use crate::transport::error;
@@ -12,9 +17,9 @@ use bytes::Bytes;
 use http::{HeaderValue, uri::Uri};
 use hyper::rt;
 use hyper_util::client::legacy::connect::HttpConnector;
-#[cfg(feature = "_tls-any")]
-use std::sync::Arc;
-use std::{fmt, future::Future, net::IpAddr, pin::Pin, str, str::FromStr, time::Duration};
+use std::{
+    fmt, future::Future, net::IpAddr, pin::Pin, str, str::FromStr, sync::Arc, time::Duration,
+};
 #[cfg(feature = "_tls-any")]
 use tokio_rustls::rustls::client::danger::ServerCertVerifier;
 use tower_service::Service;
@@ -35,6 +40,11 @@ pub struct Endpoint {
     pub(crate) origin: Option<Uri>,
     pub(crate) user_agent: Option<HeaderValue>,
     pub(crate) timeout: Option<Duration>,
+    pub(crate) call_policy: Option<Arc<CallPolicy>>,
+    pub(crate) retry_throttle: Option<RetryThrottle>,
+    pub(crate) connection_backoff: Option<ConnectionBackoff>,
+    #[cfg(feature = "service-config")]
+    pub(crate) service_config: Option<Arc<ServiceConfig>>,
     pub(crate) concurrency_limit: Option<usize>,
     pub(crate) rate_limit: Option<(u64, Duration)>,
     #[cfg(feature = "_tls-any")]
@@ -86,6 +96,11 @@ impl Endpoint {
             concurrency_limit: None,
             rate_limit: None,
             timeout: None,
+            call_policy: None,
+            retry_throttle: None,
+            connection_backoff: None,
+            #[cfg(feature = "service-config")]
+            service_config: None,
             #[cfg(feature = "_tls-any")]
             tls: None,
             buffer_size: None,
@@ -117,6 +132,11 @@ impl Endpoint {
             concurrency_limit: None,
             rate_limit: None,
             timeout: None,
+            call_policy: None,
+            retry_throttle: None,
+            connection_backoff: None,
+            #[cfg(feature = "service-config")]
+            service_config: None,
             #[cfg(feature = "_tls-any")]
             tls: None,
             buffer_size: None,
@@ -250,6 +270,104 @@ impl Endpoint {
         }
     }
 
+    /// Retry failed requests according to the given [`RetryPolicy`].
+    ///
+    /// This replaces any [`HedgingPolicy`] set before. A policy set in the
+    /// extensions of a request takes precedence over this one. See the
+    /// [`retry`](super::retry) module for more details.
+    ///
+    /// ```
+    /// # use tonic::transport::{Endpoint, channel::RetryPolicy};
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.retry_policy(RetryPolicy::new().max_attempts(4));
+    /// ```
+    pub fn retry_policy(self, policy: RetryPolicy) -> Self {
+        Endpoint {
+            call_policy: Some(Arc::new(CallPolicy::Retry(policy))),
+            ..self
+        }
+    }
+
+    /// Hedge requests according to the given [`HedgingPolicy`].
+    ///
+    /// This replaces any [`RetryPolicy`] set before. A policy set in the
+    /// extensions of a request takes precedence over this one. See the
+    /// [`retry`](super::retry) module for more details.
+    ///
+    /// ```
+    /// # use tonic::transport::{Endpoint, channel::HedgingPolicy};
+    /// # use std::time::Duration;
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.hedging_policy(HedgingPolicy::new().hedging_delay(Duration::from_millis(20)));
+    /// ```
+    pub fn hedging_policy(self, policy: HedgingPolicy) -> Self {
+        Endpoint {
+            call_policy: Some(Arc::new(CallPolicy::Hedging(policy))),
+            ..self
+        }
+    }
+
+    /// Throttle retries and hedges once too many requests of the channel fail.
+    ///
+    /// ```
+    /// # use tonic::transport::{Endpoint, channel::RetryThrottle};
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.retry_throttle(RetryThrottle::new(10, 0.1));
+    /// ```
+    pub fn retry_throttle(self, throttle: RetryThrottle) -> Self {
+        Endpoint {
+            retry_throttle: Some(throttle),
+            ..self
+        }
+    }
+
+    /// Apply the per-method defaults of a gRPC [`ServiceConfig`] to every
+    /// request.
+    ///
+    /// ```
+    /// # use tonic::transport::{Endpoint, channel::ServiceConfig};
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// let config = r#"{ "methodConfig": [{ "name": [{}], "timeout": "5s" }] }"#;
+    /// builder.service_config(config.parse::<ServiceConfig>().unwrap());
+    /// ```
+    #[cfg(feature = "service-config")]
+    pub fn service_config(self, config: ServiceConfig) -> Self {
+        Endpoint {
+            service_config: Some(Arc::new(config)),
+            ..self
+        }
+    }
+
+    /// Wait before reconnecting after a connection attempt failed.
+    ///
+    /// By default a new connection attempt is made as soon as a call needs
+    /// one. With a [`ConnectionBackoff`], attempts are spaced by an
+    /// exponentially growing delay, during which calls fail immediately unless
+    /// they are wait-for-ready.
+    ///
+    /// ```
+    /// # use tonic::transport::{Endpoint, channel::ConnectionBackoff};
+    /// # let mut builder = Endpoint::from_static("https://example.com");
+    /// builder.connection_backoff(ConnectionBackoff::new());
+    /// ```
+    pub fn connection_backoff(self, backoff: ConnectionBackoff) -> Self {
+        Endpoint {
+            connection_backoff: Some(backoff),
+            ..self
+        }
+    }
+
+    pub(crate) fn throttle(&self) -> Option<Arc<Throttle>> {
+        #[cfg(feature = "service-config")]
+        let throttle = self
+            .retry_throttle
+            .or_else(|| self.service_config.as_ref()?.retry_throttle());
+        #[cfg(not(feature = "service-config"))]
+        let throttle = self.retry_throttle;
+
+        throttle.map(|t| Arc::new(Throttle::new(t)))
+    }
+
     /// Apply a timeout to connecting to the uri.
     ///
     /// Defaults to no timeout.
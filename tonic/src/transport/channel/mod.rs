@@ -1,34 +1,61 @@
 //! Client implementation and builder.
 
+mod backoff;
+mod balance;
+mod connectivity;
+mod dns;
 mod endpoint;
+pub mod retry;
 pub(crate) mod service;
+#[cfg(feature = "service-config")]
+mod service_config;
 #[cfg(feature = "_tls-any")]
 mod tls;
 mod uds_connector;
 
+pub use self::backoff::ConnectionBackoff;
+pub use self::balance::LoadBalancingPolicy;
+pub use self::connectivity::ConnectivityState;
+pub use self::dns::DnsBalance;
 pub use self::service::Change;
 pub use endpoint::Endpoint;
+pub use retry::{HedgingPolicy, RetryPolicy, RetryThrottle};
+#[cfg(feature = "service-config")]
+pub use service_config::{MethodConfig, ServiceConfig};
 #[cfg(feature = "_tls-any")]
 pub use tls::ClientTlsConfig;
 
-use self::service::{Connection, DynamicServiceStream, Executor, SharedExec};
-use crate::body::Body;
+use self::balance::Balancer;
+use self::connectivity::Connectivity;
+use self::retry::{ResponseFuture as RetryResponseFuture, Retry};
+#[cfg(all(feature = "server", feature = "router"))]
+use self::service::InProcess;
+use self::service::{Connection, DynamicServiceStream, Executor, SharedExec, TrackConnectivity};
+#[cfg(feature = "service-config")]
+use self::service_config::Applied;
+use crate::{
+    Status,
+    body::Body,
+    request::{WaitForReady, try_parse_grpc_timeout},
+};
 use bytes::Bytes;
 use http::{
     Request, Response,
     uri::{InvalidUri, Uri},
 };
+#[cfg(feature = "service-config")]
+use std::sync::Arc;
 use std::{
     fmt,
     future::Future,
     hash::Hash,
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, ready},
+    time::Duration,
 };
 use tokio::sync::mpsc::{Sender, channel};
 
 use hyper::rt;
-use tower::balance::p2c::Balance;
 use tower::{
     Service,
     buffer::{Buffer, future::ResponseFuture as BufferResponseFuture},
@@ -37,6 +64,7 @@ use tower::{
 };
 
 type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+type ConnectionFuture = BoxFuture<'static, Result<Response<Body>, crate::BoxError>>;
 
 const DEFAULT_BUFFER_SIZE: usize = 1024;
 
@@ -65,14 +93,25 @@ const DEFAULT_BUFFER_SIZE: usize = 1024;
 /// cloning the `Channel` type is cheap and encouraged.
 #[derive(Clone)]
 pub struct Channel {
-    svc: Buffer<Request<Body>, BoxFuture<'static, Result<Response<Body>, crate::BoxError>>>,
+    svc: Retry<Buffer<Request<Body>, ConnectionFuture>>,
+    #[cfg(feature = "service-config")]
+    service_config: Option<Arc<ServiceConfig>>,
+    connectivity: Connectivity,
+    /// Whether calls fail while the connection is in backoff.
+    fail_fast: bool,
+    /// The timeout of the endpoint, also bounding how long wait-for-ready
+    /// calls wait for the connection.
+    timeout: Option<Duration>,
 }
 
 /// A future that resolves to an HTTP response.
 ///
 /// This is returned by the `Service::call` on [`Channel`].
 pub struct ResponseFuture {
-    inner: BufferResponseFuture<BoxFuture<'static, Result<Response<Body>, crate::BoxError>>>,
+    inner: RetryResponseFuture<BufferResponseFuture<ConnectionFuture>, Body>,
+    #[cfg(feature = "service-config")]
+    applied: Applied,
+    fail_fast: Option<BoxFuture<'static, Status>>,
 }
 
 impl Channel {
@@ -107,8 +146,33 @@ impl Channel {
     ///
     /// This creates a [`Channel`] that will load balance across all the
     /// provided endpoints.
+    ///
+    /// The settings of the whole channel are taken from the first endpoint,
+    /// see [`Channel::balance_channel`]. Retried or hedged calls are balanced
+    /// on each attempt.
     pub fn balance_list(list: impl Iterator<Item = Endpoint>) -> Self {
-        let (channel, tx) = Self::balance_channel(DEFAULT_BUFFER_SIZE);
+        Self::balance_list_with_policy(list, LoadBalancingPolicy::default())
+    }
+
+    /// Balance a list of [`Endpoint`]'s according to `policy`.
+    ///
+    /// ```
+    /// # use tonic::transport::{Channel, Endpoint, channel::LoadBalancingPolicy};
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let endpoints = ["http://[::1]:50051", "http://[::1]:50052"].map(Endpoint::from_static);
+    /// let channel = Channel::balance_list_with_policy(
+    ///     endpoints.into_iter(),
+    ///     LoadBalancingPolicy::ConsistentHash("x-user-id".parse().unwrap()),
+    /// );
+    /// # }
+    /// ```
+    pub fn balance_list_with_policy(
+        list: impl Iterator<Item = Endpoint>,
+        policy: LoadBalancingPolicy,
+    ) -> Self {
+        let mut list = list.peekable();
+        let (channel, tx) = Self::balance_channel_inner(DEFAULT_BUFFER_SIZE, list.peek(), policy);
         list.for_each(|endpoint| {
             tx.try_send(Change::Insert(endpoint.uri.clone(), endpoint))
                 .unwrap();
@@ -117,9 +181,34 @@ impl Channel {
         channel
     }
 
+    /// Balance across all the addresses of a DNS name, resolving it again
+    /// periodically.
+    ///
+    /// The target is a name and port such as `dns:///example.com:50051`. See
+    /// [`DnsBalance`] to configure the connections and how often the name is
+    /// resolved.
+    ///
+    /// This must be called from within a tokio runtime.
+    pub fn balance_dns(target: &str) -> Result<Self, super::Error> {
+        Ok(DnsBalance::new(target)?.channel())
+    }
+
     /// Balance a list of [`Endpoint`]'s.
     ///
     /// This creates a [`Channel`] that will listen to a stream of change events and will add or remove provided endpoints.
+    ///
+    /// The settings of an endpoint sent through a [`Change`] only apply to its
+    /// own connection, such as its TLS config, timeouts and
+    /// [`ConnectionBackoff`]. Every balanced channel takes the settings of the
+    /// whole channel from a single endpoint instead: the [`RetryPolicy`] or
+    /// [`HedgingPolicy`] and [`RetryThrottle`] of calls, the service config,
+    /// the [`Endpoint::timeout`] bounding wait-for-ready calls, the
+    /// [`Endpoint::buffer_size`], the executor, and whether calls fail once
+    /// every endpoint is waiting to reconnect, which is the case if that
+    /// endpoint has a [`ConnectionBackoff`]. This endpoint is the first one
+    /// given to [`Channel::balance_list`], the one [`DnsBalance`] connects
+    /// with, or the one given to [`Channel::balance_channel_with_settings`].
+    /// Channels created with this function use the defaults.
     pub fn balance_channel<K>(capacity: usize) -> (Self, Sender<Change<K, Endpoint>>)
     where
         K: Hash + Eq + Send + Clone + 'static,
@@ -127,6 +216,52 @@ impl Channel {
         Self::balance_channel_with_executor(capacity, SharedExec::tokio())
     }
 
+    /// Balance a list of [`Endpoint`]'s according to `policy`, with the
+    /// settings of the whole channel taken from `settings`.
+    ///
+    /// This creates a [`Channel`] that will listen to a stream of change events and will add or remove provided endpoints.
+    /// See [`Channel::balance_channel`] for the settings used, the URI of
+    /// `settings` is ignored.
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use tonic::transport::{Channel, Endpoint, channel::{LoadBalancingPolicy, RetryPolicy}};
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let settings = Endpoint::from_static("http://[::1]")
+    ///     .timeout(Duration::from_secs(5))
+    ///     .retry_policy(RetryPolicy::new());
+    /// let (channel, tx) = Channel::balance_channel_with_settings::<usize>(
+    ///     1024,
+    ///     &settings,
+    ///     LoadBalancingPolicy::default(),
+    /// );
+    /// # }
+    /// ```
+    pub fn balance_channel_with_settings<K>(
+        capacity: usize,
+        settings: &Endpoint,
+        policy: LoadBalancingPolicy,
+    ) -> (Self, Sender<Change<K, Endpoint>>)
+    where
+        K: Hash + Eq + Send + Clone + 'static,
+    {
+        Self::balance_channel_inner(capacity, Some(settings), policy)
+    }
+
+    /// Balance a list of [`Endpoint`]'s according to `policy`.
+    ///
+    /// This creates a [`Channel`] that will listen to a stream of change events and will add or remove provided endpoints.
+    pub fn balance_channel_with_policy<K>(
+        capacity: usize,
+        policy: LoadBalancingPolicy,
+    ) -> (Self, Sender<Change<K, Endpoint>>)
+    where
+        K: Hash + Eq + Send + Clone + 'static,
+    {
+        Self::balance_channel_inner(capacity, None, policy)
+    }
+
     /// Balance a list of [`Endpoint`]'s.
     ///
     /// This creates a [`Channel`] that will listen to a stream of change events and will add or remove provided endpoints.
@@ -139,10 +274,24 @@ impl Channel {
     where
         K: Hash + Eq + Send + Clone + 'static,
         E: Executor<Pin<Box<dyn Future<Output = ()> + Send>>> + Send + Sync + 'static,
+    {
+        let settings = Endpoint::from_static("http://[::1]").executor(executor);
+        Self::balance_channel_inner(capacity, Some(&settings), LoadBalancingPolicy::default())
+    }
+
+    /// Create a balanced channel with the settings of the whole channel taken
+    /// from `settings`, if any.
+    pub(crate) fn balance_channel_inner<K>(
+        capacity: usize,
+        settings: Option<&Endpoint>,
+        policy: LoadBalancingPolicy,
+    ) -> (Self, Sender<Change<K, Endpoint>>)
+    where
+        K: Hash + Eq + Send + Clone + 'static,
     {
         let (tx, rx) = channel(capacity);
         let list = DynamicServiceStream::new(rx);
-        (Self::balance(list, DEFAULT_BUFFER_SIZE, executor), tx)
+        (Self::balance(list, settings, policy), tx)
     }
 
     /// Create a new [`Channel`] using a custom connector to the provided [Endpoint].
@@ -157,13 +306,28 @@ impl Channel {
     {
         let buffer_size = endpoint.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
         let executor = endpoint.executor.clone();
+        let call_policy = endpoint.call_policy.clone();
+        let throttle = endpoint.throttle();
+        #[cfg(feature = "service-config")]
+        let service_config = endpoint.service_config.clone();
+
+        let fail_fast = endpoint.connection_backoff.is_some();
+        let timeout = endpoint.timeout;
 
         let svc = Connection::lazy(connector, endpoint);
+        let connectivity = svc.connectivity();
         let (svc, worker) = Buffer::pair(svc, buffer_size);
 
         executor.execute(worker);
 
-        Channel { svc }
+        Channel {
+            svc: Retry::with_policy(svc, call_policy, throttle),
+            #[cfg(feature = "service-config")]
+            service_config,
+            connectivity,
+            fail_fast,
+            timeout,
+        }
     }
 
     /// Connect to the provided [`Endpoint`] using the provided connector, and return a new [`Channel`].
@@ -178,30 +342,143 @@ impl Channel {
     {
         let buffer_size = endpoint.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
         let executor = endpoint.executor.clone();
+        let call_policy = endpoint.call_policy.clone();
+        let throttle = endpoint.throttle();
+        #[cfg(feature = "service-config")]
+        let service_config = endpoint.service_config.clone();
+
+        let fail_fast = endpoint.connection_backoff.is_some();
+        let timeout = endpoint.timeout;
 
         let svc = Connection::connect(connector, endpoint)
             .await
             .map_err(super::Error::from_source)?;
+        let connectivity = svc.connectivity();
         let (svc, worker) = Buffer::pair(svc, buffer_size);
         executor.execute(worker);
 
-        Ok(Channel { svc })
+        Ok(Channel {
+            svc: Retry::with_policy(svc, call_policy, throttle),
+            #[cfg(feature = "service-config")]
+            service_config,
+            connectivity,
+            fail_fast,
+            timeout,
+        })
     }
 
-    pub(crate) fn balance<D, E>(discover: D, buffer_size: usize, executor: E) -> Self
+    /// Create a [`Channel`] sending requests directly to `routes`, in the
+    /// same process.
+    ///
+    /// Requests are handed to the services of `routes` without going through
+    /// a socket or HTTP/2, which makes for fast and deterministic tests of
+    /// clients and services together. Calls otherwise behave as they would
+    /// with a [`Server`](crate::transport::Server) serving `routes`: metadata,
+    /// trailers and compression are passed along, `grpc-timeout` deadlines
+    /// are enforced by the server side, and a call is cancelled on the server
+    /// side when the client drops it. Request and response extensions are not
+    /// passed from one side to the other.
+    ///
+    /// This must be called from within a tokio runtime.
+    ///
+    /// ```
+    /// # use tonic::{service::Routes, transport::Channel};
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// # let routes = Routes::default();
+    /// let channel = Channel::in_process(routes);
+    /// // let client = GreeterClient::new(channel);
+    /// # }
+    /// ```
+    #[cfg(all(feature = "server", feature = "router"))]
+    pub fn in_process(routes: crate::service::Routes) -> Self {
+        let svc = BoxService::new(InProcess::new(routes));
+        let (svc, worker) = Buffer::pair(svc, DEFAULT_BUFFER_SIZE);
+        SharedExec::tokio().execute(Box::pin(worker));
+
+        Channel {
+            svc: Retry::with_policy(svc, None, None),
+            #[cfg(feature = "service-config")]
+            service_config: None,
+            connectivity: Connectivity::with_state(ConnectivityState::Ready),
+            fail_fast: false,
+            timeout: None,
+        }
+    }
+
+    /// The state of the connection of this channel.
+    ///
+    /// A channel created with [`Endpoint::connect_lazy`] is
+    /// [`Idle`](ConnectivityState::Idle) until the first call is made.
+    ///
+    /// ```
+    /// # use tonic::transport::{Channel, channel::ConnectivityState};
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let channel = Channel::from_static("http://[::1]:50051").connect_lazy();
+    /// assert_eq!(channel.state(), ConnectivityState::Idle);
+    /// # }
+    /// ```
+    pub fn state(&self) -> ConnectivityState {
+        self.connectivity.state()
+    }
+
+    /// Wait for the state of this channel to be different from `current`,
+    /// and return the new state.
+    ///
+    /// This returns immediately if the channel is not in the `current` state.
+    /// Use [`tokio::time::timeout`] to give up after some time.
+    ///
+    /// ```no_run
+    /// # use tonic::transport::{Channel, channel::ConnectivityState};
+    /// # async fn wait(channel: Channel) {
+    /// let mut state = channel.state();
+    /// while state != ConnectivityState::Ready {
+    ///     state = channel.wait_for_state_change(state).await;
+    /// }
+    /// # }
+    /// ```
+    pub async fn wait_for_state_change(&self, current: ConnectivityState) -> ConnectivityState {
+        let mut state = self.connectivity.subscribe();
+        let changed = state.wait_for(|state| *state != current).await;
+        changed.map_or(ConnectivityState::Shutdown, |state| *state)
+    }
+
+    pub(crate) fn balance<D>(
+        discover: D,
+        settings: Option<&Endpoint>,
+        policy: LoadBalancingPolicy,
+    ) -> Self
     where
         D: Discover<Service = Connection> + Unpin + Send + 'static,
         D::Error: Into<crate::BoxError>,
         D::Key: Hash + Send + Clone,
-        E: Executor<BoxFuture<'static, ()>> + Send + Sync + 'static,
     {
-        let svc = Balance::new(discover);
+        let connectivity = Connectivity::new();
+        let discover = TrackConnectivity::new(discover, connectivity.clone());
+
+        let buffer_size = settings
+            .and_then(|settings| settings.buffer_size)
+            .unwrap_or(DEFAULT_BUFFER_SIZE);
+        let executor =
+            settings.map_or_else(SharedExec::tokio, |settings| settings.executor.clone());
 
-        let svc = BoxService::new(svc);
+        let svc = BoxService::new(Balancer::new(discover, policy));
         let (svc, worker) = Buffer::pair(svc, buffer_size);
         executor.execute(Box::pin(worker));
 
-        Channel { svc }
+        Channel {
+            svc: Retry::with_policy(
+                svc,
+                settings.and_then(|settings| settings.call_policy.clone()),
+                settings.and_then(Endpoint::throttle),
+            ),
+            #[cfg(feature = "service-config")]
+            service_config: settings.and_then(|settings| settings.service_config.clone()),
+            connectivity,
+            fail_fast: settings.is_some_and(|settings| settings.connection_backoff.is_some()),
+            timeout: settings.and_then(|settings| settings.timeout),
+        }
     }
 }
 
@@ -215,19 +492,77 @@ impl Service<http::Request<Body>> for Channel {
     }
 
     fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        #[cfg(feature = "service-config")]
+        let (request, applied) = match &self.service_config {
+            Some(config) => config.apply(request),
+            None => (request, Applied::default()),
+        };
+
+        let fail_fast = self
+            .fail_fast
+            .then(|| fail_fast(&self.connectivity, &request, self.timeout));
+
         let inner = Service::call(&mut self.svc, request);
 
-        ResponseFuture { inner }
+        ResponseFuture {
+            inner,
+            #[cfg(feature = "service-config")]
+            applied,
+            fail_fast,
+        }
     }
 }
 
+/// Fails a call once the connection is in backoff, unless it is wait-for-ready,
+/// in which case it fails once its timeout, or the timeout of the endpoint,
+/// expires.
+fn fail_fast(
+    connectivity: &Connectivity,
+    request: &Request<Body>,
+    endpoint_timeout: Option<Duration>,
+) -> BoxFuture<'static, Status> {
+    let wait_for_ready = request
+        .extensions()
+        .get::<WaitForReady>()
+        .is_some_and(|wait_for_ready| wait_for_ready.0);
+
+    if wait_for_ready {
+        let timeout = try_parse_grpc_timeout(request.headers()).ok().flatten();
+        let timeout = timeout.into_iter().chain(endpoint_timeout).min();
+        return Box::pin(async move {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+            Status::deadline_exceeded("deadline expired while waiting for the connection")
+        });
+    }
+
+    let connectivity = connectivity.clone();
+    let mut state = connectivity.subscribe();
+    Box::pin(async move {
+        let _ = state
+            .wait_for(|state| *state == ConnectivityState::TransientFailure)
+            .await;
+        connectivity.unavailable()
+    })
+}
+
 impl Future for ResponseFuture {
     type Output = Result<Response<Body>, super::Error>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        Pin::new(&mut self.inner)
-            .poll(cx)
-            .map_err(super::Error::from_source)
+        if let Some(fail_fast) = &mut self.fail_fast
+            && let Poll::Ready(status) = fail_fast.as_mut().poll(cx)
+        {
+            return Poll::Ready(Err(super::Error::from_source(status)));
+        }
+
+        let result = ready!(Pin::new(&mut self.inner).poll(cx));
+        #[cfg(feature = "service-config")]
+        let result = self.applied.response(result);
+
+        Poll::Ready(result.map_err(super::Error::from_source))
     }
 }
 
@@ -242,3 +577,66 @@ impl fmt::Debug for ResponseFuture {
         f.debug_struct("ResponseFuture").finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Code;
+    use tower::ServiceExt as _;
+
+    async fn unreachable_endpoint() -> Endpoint {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        Endpoint::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .connection_backoff(ConnectionBackoff::new().initial_backoff(Duration::from_secs(60)))
+            .timeout(Duration::from_millis(100))
+    }
+
+    fn call(channel: Channel, wait_for_ready: bool) -> impl Future<Output = Code> {
+        let mut request = Request::post("/test.Test/Call")
+            .body(Body::empty())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(WaitForReady(wait_for_ready));
+
+        async move {
+            let future = channel.oneshot(request);
+            let err = tokio::time::timeout(Duration::from_secs(5), future)
+                .await
+                .unwrap()
+                .unwrap_err();
+            Status::from_error(err.into()).code()
+        }
+    }
+
+    #[tokio::test]
+    async fn balanced_calls_fail_fast() {
+        let channel = Channel::balance_list([unreachable_endpoint().await].into_iter());
+
+        assert_eq!(call(channel.clone(), false).await, Code::Unavailable);
+        // Wait-for-ready calls give up once the endpoint timeout expires.
+        assert_eq!(call(channel, true).await, Code::DeadlineExceeded);
+    }
+
+    #[tokio::test]
+    async fn balanced_channel_settings() {
+        let settings = Endpoint::from_static("http://[::1]")
+            .connection_backoff(ConnectionBackoff::new())
+            .timeout(Duration::from_millis(100));
+        let (channel, tx) =
+            Channel::balance_channel_with_settings(1, &settings, LoadBalancingPolicy::default());
+
+        // The settings of inserted endpoints only apply to their connection.
+        let endpoint = unreachable_endpoint()
+            .await
+            .timeout(Duration::from_secs(60));
+        tx.send(Change::Insert(0, endpoint)).await.unwrap();
+
+        assert_eq!(call(channel.clone(), false).await, Code::Unavailable);
+        assert_eq!(call(channel, true).await, Code::DeadlineExceeded);
+    }
+}
@@ -0,0 +1,387 @@
+use super::{
+    BoxFuture, DEFAULT_BUFFER_LIMIT, GRPC_PREVIOUS_RPC_ATTEMPTS, MAX_ATTEMPTS, Outcome,
+    clone_request, throttle::Throttle,
+};
+use crate::{Code, body::Body, transport::channel::service::ReplayBody};
+use http::{HeaderValue, Request, Response};
+use std::{
+    future::{Future, poll_fn},
+    sync::Arc,
+    task::Poll,
+    time::Duration,
+};
+use tokio::time::Instant;
+use tower::ServiceExt;
+use tower_service::Service;
+
+/// Configures how calls are hedged.
+///
+/// Hedging sends the same call several times without waiting for the
+/// previous attempts to fail: a new attempt is started every
+/// `hedging_delay` until `max_attempts` attempts are in flight. The first
+/// attempt that succeeds, or fails with a fatal status code, is the result
+/// of the call and all other attempts are cancelled. An attempt failing with
+/// one of the non-fatal status codes immediately starts the next attempt.
+///
+/// When used with a balanced [`Channel`], attempts are preferably sent to
+/// the endpoints with the fewest outstanding requests, so hedges of the same
+/// call usually go to different endpoints.
+///
+/// By default a policy allows 3 attempts, started 100 milliseconds apart,
+/// treats no status code as non-fatal and buffers up to 1MiB of each
+/// request.
+///
+/// [`Channel`]: crate::transport::Channel
+#[derive(Debug, Clone)]
+pub struct HedgingPolicy {
+    pub(super) max_attempts: usize,
+    hedging_delay: Duration,
+    non_fatal_status_codes: Vec<Code>,
+    pub(super) buffer_limit: usize,
+}
+
+impl HedgingPolicy {
+    /// Create a new `HedgingPolicy` with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of attempts, including the original one.
+    ///
+    /// Values larger than 5 are treated as 5.
+    pub fn max_attempts(self, max_attempts: usize) -> Self {
+        HedgingPolicy {
+            max_attempts: max_attempts.min(MAX_ATTEMPTS),
+            ..self
+        }
+    }
+
+    /// Set the delay between two attempts.
+    ///
+    /// A delay of zero sends all attempts at once. Defaults to 100
+    /// milliseconds.
+    pub fn hedging_delay(self, hedging_delay: Duration) -> Self {
+        HedgingPolicy {
+            hedging_delay,
+            ..self
+        }
+    }
+
+    /// Set the status codes that do not end the call.
+    ///
+    /// When an attempt fails with any other code, that failure is returned
+    /// and all other attempts are cancelled.
+    pub fn non_fatal_status_codes(self, codes: impl IntoIterator<Item = Code>) -> Self {
+        HedgingPolicy {
+            non_fatal_status_codes: codes.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Set how many bytes of the request stream are buffered for replay.
+    ///
+    /// Once a call sent more than this many bytes no new attempt is started.
+    pub fn buffer_limit(self, limit: usize) -> Self {
+        HedgingPolicy {
+            buffer_limit: limit,
+            ..self
+        }
+    }
+
+    fn is_non_fatal(&self, code: Code) -> bool {
+        code != Code::Ok && self.non_fatal_status_codes.contains(&code)
+    }
+}
+
+impl Default for HedgingPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            hedging_delay: Duration::from_millis(100),
+            non_fatal_status_codes: Vec::new(),
+            buffer_limit: DEFAULT_BUFFER_LIMIT,
+        }
+    }
+}
+
+type AttemptFuture<B> = BoxFuture<Result<Response<B>, crate::BoxError>>;
+
+pub(super) async fn hedge<S, ResBody>(
+    mut svc: S,
+    policy: HedgingPolicy,
+    throttle: Option<Arc<Throttle>>,
+    parts: http::request::Parts,
+    body: ReplayBody,
+    first: S::Future,
+) -> Result<Response<ResBody>, crate::BoxError>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Error: Into<crate::BoxError>,
+    S::Future: Send + 'static,
+    ResBody: Send + 'static,
+{
+    let mut attempts: Vec<AttemptFuture<ResBody>> =
+        vec![Box::pin(async move { first.await.map_err(Into::into) })];
+    let mut sent = 1;
+    let mut next_hedge =
+        (policy.max_attempts > 1).then(|| Box::pin(tokio::time::sleep(policy.hedging_delay)));
+    let mut last_failure = None;
+
+    let result = poll_fn(|cx| {
+        loop {
+            let mut i = 0;
+            while i < attempts.len() {
+                let Poll::Ready(result) = attempts[i].as_mut().poll(cx) else {
+                    i += 1;
+                    continue;
+                };
+                drop(attempts.swap_remove(i));
+
+                let delay = match Outcome::of(&result) {
+                    Outcome::Failed(code) if policy.is_non_fatal(code) => Some(Duration::ZERO),
+                    Outcome::Pushback(code, delay) if policy.is_non_fatal(code) => delay,
+                    Outcome::Commit => {
+                        if let Some(throttle) = &throttle {
+                            throttle.record_success();
+                        }
+                        return Poll::Ready(result);
+                    }
+                    _ => return Poll::Ready(result),
+                };
+
+                if let Some(throttle) = &throttle {
+                    throttle.record_failure();
+                }
+                last_failure = Some(result);
+
+                // A pushback without a valid delay means no more hedges should
+                // be sent for this call.
+                next_hedge = match delay {
+                    Some(delay) if next_hedge.is_some() => {
+                        Some(Box::pin(tokio::time::sleep(delay)))
+                    }
+                    _ => None,
+                };
+            }
+
+            if next_hedge
+                .as_mut()
+                .is_some_and(|timer| timer.as_mut().poll(cx).is_ready())
+            {
+                let throttled = throttle.as_ref().is_some_and(|t| !t.allows_retry());
+
+                if throttled || body.is_committed() {
+                    next_hedge = None;
+                } else {
+                    tracing::debug!("sending hedged attempt {}", sent + 1);
+                    attempts.push(attempt(&mut svc, &parts, &body, sent));
+                    sent += 1;
+
+                    if sent >= policy.max_attempts {
+                        next_hedge = None;
+                    } else if let Some(timer) = next_hedge.as_mut() {
+                        timer.as_mut().reset(Instant::now() + policy.hedging_delay);
+                    }
+                }
+
+                // Poll the new attempt, or return the last failure if no
+                // attempt is left.
+                continue;
+            }
+
+            if attempts.is_empty() && next_hedge.is_none() {
+                return Poll::Ready(last_failure.take().expect("a failed attempt"));
+            }
+
+            return Poll::Pending;
+        }
+    })
+    .await;
+
+    body.commit();
+    result
+}
+
+/// Start another attempt of the call on a clone of `svc`.
+///
+/// Takes `svc` mutably so that the hedging future is `Send` without
+/// requiring the service to be `Sync`.
+fn attempt<S, ResBody>(
+    svc: &mut S,
+    parts: &http::request::Parts,
+    body: &ReplayBody,
+    previous_attempts: usize,
+) -> AttemptFuture<ResBody>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Error: Into<crate::BoxError>,
+    S::Future: Send + 'static,
+    ResBody: Send + 'static,
+{
+    let mut svc = svc.clone();
+    let mut request = clone_request(parts, Body::new(body.clone()));
+    request.headers_mut().insert(
+        GRPC_PREVIOUS_RPC_ATTEMPTS,
+        HeaderValue::from(previous_attempts),
+    );
+
+    Box::pin(async move {
+        let ready = svc.ready().await.map_err(Into::into);
+        match ready {
+            Ok(svc) => svc.call(request).await.map_err(Into::into),
+            Err(err) => Err(err),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Status,
+        transport::channel::retry::{RetryLayer, RetryThrottle},
+    };
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        task::Context,
+    };
+    use tower_layer::Layer;
+
+    /// Answers attempt `n` after `delays[n]` with `codes[n]`.
+    #[derive(Clone)]
+    struct Slow {
+        calls: Arc<AtomicUsize>,
+        delays: Vec<Duration>,
+        codes: Vec<Code>,
+    }
+
+    impl Slow {
+        fn new(attempts: &[(u64, Code)]) -> Self {
+            Self {
+                calls: Arc::new(AtomicUsize::new(0)),
+                delays: attempts
+                    .iter()
+                    .map(|(ms, _)| Duration::from_millis(*ms))
+                    .collect(),
+                codes: attempts.iter().map(|(_, code)| *code).collect(),
+            }
+        }
+    }
+
+    impl Service<Request<Body>> for Slow {
+        type Response = Response<Body>;
+        type Error = crate::BoxError;
+        type Future = BoxFuture<Result<Response<Body>, crate::BoxError>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: Request<Body>) -> Self::Future {
+            let attempt = self.calls.fetch_add(1, Ordering::SeqCst);
+            let delay = self.delays[attempt];
+            let code = self.codes[attempt];
+
+            Box::pin(async move {
+                tokio::time::sleep(delay).await;
+                let mut response = Status::new(code, "").into_http::<Body>();
+                response
+                    .headers_mut()
+                    .insert("attempt", HeaderValue::from(attempt));
+                Ok(response)
+            })
+        }
+    }
+
+    fn attempt(response: &Response<Body>) -> usize {
+        response.headers()["attempt"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hedge_wins_when_first_attempt_stalls() {
+        let svc = Slow::new(&[(1000, Code::Ok), (10, Code::Ok)]);
+        let mut hedge =
+            RetryLayer::hedging(HedgingPolicy::new().max_attempts(2)).layer(svc.clone());
+
+        let start = Instant::now();
+        let response = hedge
+            .ready()
+            .await
+            .unwrap()
+            .call(Request::new(Body::empty()))
+            .await
+            .unwrap();
+
+        assert_eq!(attempt(&response), 1);
+        assert_eq!(start.elapsed(), Duration::from_millis(110));
+        assert_eq!(svc.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn non_fatal_failure_sends_next_hedge_immediately() {
+        let svc = Slow::new(&[(10, Code::Unavailable), (10, Code::Ok)]);
+        let policy = HedgingPolicy::new()
+            .hedging_delay(Duration::from_secs(1))
+            .non_fatal_status_codes([Code::Unavailable]);
+        let mut hedge = RetryLayer::hedging(policy).layer(svc.clone());
+
+        let start = Instant::now();
+        let response = hedge
+            .ready()
+            .await
+            .unwrap()
+            .call(Request::new(Body::empty()))
+            .await
+            .unwrap();
+
+        assert_eq!(attempt(&response), 1);
+        assert_eq!(start.elapsed(), Duration::from_millis(20));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fatal_failure_ends_call() {
+        let svc = Slow::new(&[(10, Code::InvalidArgument), (10, Code::Ok)]);
+        let mut hedge = RetryLayer::hedging(HedgingPolicy::new()).layer(svc.clone());
+
+        let response = hedge
+            .ready()
+            .await
+            .unwrap()
+            .call(Request::new(Body::empty()))
+            .await
+            .unwrap();
+
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(svc.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn throttle_stops_hedging() {
+        let svc = Slow::new(&[
+            (10, Code::Unavailable),
+            (10, Code::Unavailable),
+            (10, Code::Ok),
+        ]);
+        let policy = HedgingPolicy::new().non_fatal_status_codes([Code::Unavailable]);
+        let mut hedge = RetryLayer::hedging(policy)
+            .throttle(RetryThrottle::new(3, 0.1))
+            .layer(svc.clone());
+
+        let response = hedge
+            .ready()
+            .await
+            .unwrap()
+            .call(Request::new(Body::empty()))
+            .await
+            .unwrap();
+
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert_eq!(svc.calls.load(Ordering::SeqCst), 2);
+    }
+}
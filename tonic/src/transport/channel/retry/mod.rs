@@ -0,0 +1,708 @@
+//! Transparent retries and hedging of calls.
+//!
+//! Retries follow the semantics of [gRFC A6]: a call is retried when it fails
+//! with one of the configured status codes _before_ the server committed to
+//! it by sending response headers. The outgoing request stream is buffered,
+//! up to a configurable limit, so that it can be replayed on a new attempt.
+//!
+//! Instead of waiting for an attempt to fail, a [`HedgingPolicy`] sends
+//! additional attempts of a call after a delay and uses whichever answers
+//! first. A [`RetryThrottle`] limits both retries and hedges once too many
+//! calls of a channel fail.
+//!
+//! A [`RetryPolicy`] or [`HedgingPolicy`] can be set for every call of a
+//! channel with [`Endpoint::retry_policy`] and [`Endpoint::hedging_policy`],
+//! applied to any client service (for example a balanced [`Channel`] or a
+//! generated client) with [`RetryLayer`], or set for a single call by
+//! inserting it into the request extensions.
+//!
+//! ```
+//! # use tonic::transport::channel::retry::RetryPolicy;
+//! # use tonic::Code;
+//! # use std::time::Duration;
+//! let policy = RetryPolicy::new()
+//!     .max_attempts(4)
+//!     .initial_backoff(Duration::from_millis(50))
+//!     .retryable_status_codes([Code::Unavailable, Code::ResourceExhausted]);
+//!
+//! let mut request = tonic::Request::new(());
+//! request.extensions_mut().insert(policy);
+//! ```
+//!
+//! [gRFC A6]: https://github.com/grpc/proposal/blob/master/A6-client-retries.md
+//! [`Endpoint::retry_policy`]: super::Endpoint::retry_policy
+//! [`Endpoint::hedging_policy`]: super::Endpoint::hedging_policy
+//! [`Channel`]: super::Channel
+
+mod hedge;
+mod throttle;
+
+pub use self::hedge::HedgingPolicy;
+pub use self::throttle::RetryThrottle;
+pub(crate) use self::throttle::Throttle;
+
+use super::service::ReplayBody;
+use crate::{Code, Status, body::Body};
+use http::{HeaderName, HeaderValue, Request, Response};
+use pin_project::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tower::{
+    ServiceExt,
+    util::rng::{HasherRng, Rng},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// The maximum number of attempts allowed by gRFC A6, larger values are capped.
+const MAX_ATTEMPTS: usize = 5;
+const DEFAULT_BUFFER_LIMIT: usize = 1024 * 1024;
+
+const GRPC_PREVIOUS_RPC_ATTEMPTS: HeaderName =
+    HeaderName::from_static("grpc-previous-rpc-attempts");
+const GRPC_RETRY_PUSHBACK_MS: HeaderName = HeaderName::from_static("grpc-retry-pushback-ms");
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Configures when and how often failed calls are retried.
+///
+/// The delay before retry `n` is chosen uniformly at random between zero and
+/// `min(initial_backoff * backoff_multiplier^(n - 1), max_backoff)`. A server
+/// can override this delay, or prevent any further retry, with the
+/// `grpc-retry-pushback-ms` response header.
+///
+/// By default a policy allows 3 attempts, retries calls failing with
+/// [`Code::Unavailable`] and buffers up to 1MiB of each request.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    backoff_multiplier: f64,
+    retryable_status_codes: Vec<Code>,
+    buffer_limit: usize,
+}
+
+impl RetryPolicy {
+    /// Create a new `RetryPolicy` with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of attempts, including the original one.
+    ///
+    /// Values larger than 5 are treated as 5.
+    pub fn max_attempts(self, max_attempts: usize) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.min(MAX_ATTEMPTS),
+            ..self
+        }
+    }
+
+    /// Set the upper bound of the delay before the first retry.
+    ///
+    /// Defaults to 100 milliseconds.
+    pub fn initial_backoff(self, initial_backoff: Duration) -> Self {
+        RetryPolicy {
+            initial_backoff,
+            ..self
+        }
+    }
+
+    /// Set the maximum delay between two attempts.
+    ///
+    /// Defaults to 1 second.
+    pub fn max_backoff(self, max_backoff: Duration) -> Self {
+        RetryPolicy {
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Set the factor the backoff grows by after each attempt.
+    ///
+    /// Defaults to 2.
+    pub fn backoff_multiplier(self, backoff_multiplier: f64) -> Self {
+        RetryPolicy {
+            backoff_multiplier,
+            ..self
+        }
+    }
+
+    /// Set the status codes that cause a call to be retried.
+    pub fn retryable_status_codes(self, codes: impl IntoIterator<Item = Code>) -> Self {
+        RetryPolicy {
+            retryable_status_codes: codes.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Set how many bytes of the request stream are buffered for replay.
+    ///
+    /// Once a call sent more than this many bytes it is no longer retried.
+    pub fn buffer_limit(self, limit: usize) -> Self {
+        RetryPolicy {
+            buffer_limit: limit,
+            ..self
+        }
+    }
+
+    pub(crate) fn is_retryable(&self, code: Code) -> bool {
+        code != Code::Ok && self.retryable_status_codes.contains(&code)
+    }
+
+    /// The randomized delay before the retry following `attempts` attempts.
+    pub(crate) fn backoff(&self, attempts: usize, rng: &mut impl Rng) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(i32::MAX as usize) as i32;
+        let max = self
+            .initial_backoff
+            .mul_f64(self.backoff_multiplier.max(1.0).powi(exponent).min(1e9))
+            .min(self.max_backoff);
+
+        max.mul_f64(rng.next_f64())
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            backoff_multiplier: 2.0,
+            retryable_status_codes: vec![Code::Unavailable],
+            buffer_limit: DEFAULT_BUFFER_LIMIT,
+        }
+    }
+}
+
+/// The policy a [`Retry`] service applies to a call.
+#[derive(Debug, Clone)]
+pub(crate) enum CallPolicy {
+    Retry(RetryPolicy),
+    Hedging(HedgingPolicy),
+}
+
+impl CallPolicy {
+    fn max_attempts(&self) -> usize {
+        match self {
+            CallPolicy::Retry(policy) => policy.max_attempts,
+            CallPolicy::Hedging(policy) => policy.max_attempts,
+        }
+    }
+
+    fn buffer_limit(&self) -> usize {
+        match self {
+            CallPolicy::Retry(policy) => policy.buffer_limit,
+            CallPolicy::Hedging(policy) => policy.buffer_limit,
+        }
+    }
+}
+
+/// What the outcome of a single attempt means for the call.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Outcome {
+    /// The attempt is the final result of the call.
+    Commit,
+    /// The attempt failed with the given code before the call was committed.
+    Failed(Code),
+    /// The attempt failed and the server asked to retry after the given
+    /// delay, or not at all.
+    Pushback(Code, Option<Duration>),
+}
+
+impl Outcome {
+    pub(crate) fn of<B>(result: &Result<Response<B>, crate::BoxError>) -> Self {
+        match result {
+            Ok(response) => {
+                // Only a Trailers-Only response can fail before the call is
+                // committed, any other response already carries the server's
+                // headers.
+                let Some(status) = Status::from_header_map(response.headers()) else {
+                    return Outcome::Commit;
+                };
+
+                if status.code() == Code::Ok {
+                    return Outcome::Commit;
+                }
+
+                match response.headers().get(GRPC_RETRY_PUSHBACK_MS) {
+                    Some(value) => {
+                        let delay = value
+                            .to_str()
+                            .ok()
+                            .and_then(|v| v.parse::<u64>().ok())
+                            .map(Duration::from_millis);
+                        Outcome::Pushback(status.code(), delay)
+                    }
+                    None => Outcome::Failed(status.code()),
+                }
+            }
+            Err(err) => match crate::status::find_status_in_source_chain(&**err) {
+                Some(status) => Outcome::Failed(status.code()),
+                None => Outcome::Commit,
+            },
+        }
+    }
+}
+
+/// Copy a request's head to send it again with another body.
+pub(crate) fn clone_request<B>(parts: &http::request::Parts, body: B) -> Request<B> {
+    let mut request = Request::new(body);
+    *request.method_mut() = parts.method.clone();
+    *request.uri_mut() = parts.uri.clone();
+    *request.version_mut() = parts.version;
+    *request.headers_mut() = parts.headers.clone();
+    *request.extensions_mut() = parts.extensions.clone();
+    request
+}
+
+/// A [`Layer`] that retries or hedges calls.
+///
+/// All services created by the same layer share its [`RetryThrottle`].
+///
+/// See the [module level documentation](self) for more details.
+#[derive(Debug, Clone)]
+pub struct RetryLayer {
+    policy: Arc<CallPolicy>,
+    throttle: Option<Arc<Throttle>>,
+}
+
+impl RetryLayer {
+    /// Create a new `RetryLayer` applying the retry `policy` to every call.
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy: Arc::new(CallPolicy::Retry(policy)),
+            throttle: None,
+        }
+    }
+
+    /// Create a new `RetryLayer` applying the hedging `policy` to every call.
+    pub fn hedging(policy: HedgingPolicy) -> Self {
+        Self {
+            policy: Arc::new(CallPolicy::Hedging(policy)),
+            throttle: None,
+        }
+    }
+
+    /// Throttle retries and hedges of all calls going through this layer.
+    pub fn throttle(self, throttle: RetryThrottle) -> Self {
+        RetryLayer {
+            throttle: Some(Arc::new(Throttle::new(throttle))),
+            ..self
+        }
+    }
+}
+
+impl<S> Layer<S> for RetryLayer {
+    type Service = Retry<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Retry {
+            inner,
+            policy: Some(self.policy.clone()),
+            throttle: self.throttle.clone(),
+        }
+    }
+}
+
+/// A service that retries or hedges calls.
+///
+/// A [`RetryPolicy`] or [`HedgingPolicy`] found in the extensions of a
+/// request takes precedence over the policy the service was created with.
+///
+/// See the [module level documentation](self) for more details.
+#[derive(Clone)]
+pub struct Retry<S> {
+    inner: S,
+    policy: Option<Arc<CallPolicy>>,
+    throttle: Option<Arc<Throttle>>,
+}
+
+impl<S> Retry<S> {
+    /// Create a new `Retry` service applying `policy` to every call.
+    pub fn new(inner: S, policy: RetryPolicy) -> Self {
+        Self {
+            inner,
+            policy: Some(Arc::new(CallPolicy::Retry(policy))),
+            throttle: None,
+        }
+    }
+
+    pub(crate) fn with_policy(
+        inner: S,
+        policy: Option<Arc<CallPolicy>>,
+        throttle: Option<Arc<Throttle>>,
+    ) -> Self {
+        Self {
+            inner,
+            policy,
+            throttle,
+        }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for Retry<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Retry")
+            .field("inner", &self.inner)
+            .field("policy", &self.policy)
+            .field("throttle", &self.throttle)
+            .finish()
+    }
+}
+
+impl<S, ResBody> Service<Request<Body>> for Retry<S>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Error: Into<crate::BoxError>,
+    S::Future: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = crate::BoxError;
+    type Future = ResponseFuture<S::Future, ResBody>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let extensions = request.extensions();
+        let policy = if let Some(policy) = extensions.get::<RetryPolicy>() {
+            Some(Arc::new(CallPolicy::Retry(policy.clone())))
+        } else if let Some(policy) = extensions.get::<HedgingPolicy>() {
+            Some(Arc::new(CallPolicy::Hedging(policy.clone())))
+        } else {
+            self.policy.clone()
+        };
+
+        let policy = match policy {
+            Some(policy) if policy.max_attempts() > 1 => policy,
+            _ => return ResponseFuture::direct(self.inner.call(request)),
+        };
+
+        let (parts, body) = request.into_parts();
+        let body = ReplayBody::new(body, policy.buffer_limit());
+
+        let first = self
+            .inner
+            .call(clone_request(&parts, Body::new(body.clone())));
+
+        // The service was made ready for the first attempt only, retries use
+        // a clone that is driven to readiness on its own.
+        let svc = self.inner.clone();
+        let throttle = self.throttle.clone();
+
+        let fut: BoxFuture<_> = match &*policy {
+            CallPolicy::Retry(policy) => {
+                Box::pin(retry(svc, policy.clone(), throttle, parts, body, first))
+            }
+            CallPolicy::Hedging(policy) => Box::pin(hedge::hedge(
+                svc,
+                policy.clone(),
+                throttle,
+                parts,
+                body,
+                first,
+            )),
+        };
+
+        ResponseFuture::retry(fut)
+    }
+}
+
+async fn retry<S, ResBody>(
+    mut svc: S,
+    policy: RetryPolicy,
+    throttle: Option<Arc<Throttle>>,
+    parts: http::request::Parts,
+    body: ReplayBody,
+    first: S::Future,
+) -> Result<Response<ResBody>, crate::BoxError>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>>,
+    S::Error: Into<crate::BoxError>,
+{
+    let mut rng = HasherRng::new();
+    let mut attempts = 1;
+    let mut result = first.await.map_err(Into::into);
+
+    loop {
+        let delay = match Outcome::of(&result) {
+            Outcome::Failed(code) if policy.is_retryable(code) => {
+                Some(policy.backoff(attempts, &mut rng))
+            }
+            Outcome::Pushback(code, delay) if policy.is_retryable(code) => delay,
+            Outcome::Commit => {
+                if let Some(throttle) = &throttle {
+                    throttle.record_success();
+                }
+                break;
+            }
+            _ => break,
+        };
+
+        if let Some(throttle) = &throttle {
+            throttle.record_failure();
+        }
+
+        let Some(delay) = delay else {
+            break;
+        };
+
+        let throttled = throttle.as_ref().is_some_and(|t| !t.allows_retry());
+        if throttled || attempts >= policy.max_attempts || body.is_committed() {
+            break;
+        }
+
+        tracing::debug!("retrying call after {:?}, attempt {}", delay, attempts + 1);
+        tokio::time::sleep(delay).await;
+
+        // The call may have sent more than the buffer limit while waiting.
+        if body.is_committed() {
+            break;
+        }
+
+        let mut request = clone_request(&parts, Body::new(body.clone()));
+        request
+            .headers_mut()
+            .insert(GRPC_PREVIOUS_RPC_ATTEMPTS, HeaderValue::from(attempts));
+        attempts += 1;
+
+        let ready = svc.ready().await.map_err(Into::into);
+        result = match ready {
+            Ok(svc) => svc.call(request).await.map_err(Into::into),
+            Err(err) => Err(err),
+        };
+    }
+
+    body.commit();
+    result
+}
+
+/// Response future for [`Retry`].
+#[pin_project]
+pub struct ResponseFuture<F, B> {
+    #[pin]
+    inner: Kind<F, B>,
+}
+
+#[pin_project(project = KindProj)]
+enum Kind<F, B> {
+    Direct(#[pin] F),
+    Retry(BoxFuture<Result<Response<B>, crate::BoxError>>),
+}
+
+impl<F, B> ResponseFuture<F, B> {
+    fn direct(inner: F) -> Self {
+        Self {
+            inner: Kind::Direct(inner),
+        }
+    }
+
+    fn retry(inner: BoxFuture<Result<Response<B>, crate::BoxError>>) -> Self {
+        Self {
+            inner: Kind::Retry(inner),
+        }
+    }
+}
+
+impl<F, E, B> Future for ResponseFuture<F, B>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+    E: Into<crate::BoxError>,
+{
+    type Output = Result<Response<B>, crate::BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().inner.project() {
+            KindProj::Direct(fut) => fut.poll(cx).map_err(Into::into),
+            KindProj::Retry(fut) => fut.as_mut().poll(cx),
+        }
+    }
+}
+
+impl<F, B> fmt::Debug for ResponseFuture<F, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+    use std::sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    #[derive(Clone)]
+    struct Flaky {
+        calls: Arc<AtomicUsize>,
+        failures: usize,
+        code: Code,
+        bodies: Arc<Mutex<Vec<bytes::Bytes>>>,
+    }
+
+    impl Flaky {
+        fn new(failures: usize, code: Code) -> Self {
+            Self {
+                calls: Arc::new(AtomicUsize::new(0)),
+                failures,
+                code,
+                bodies: Arc::default(),
+            }
+        }
+    }
+
+    impl Service<Request<Body>> for Flaky {
+        type Response = Response<Body>;
+        type Error = crate::BoxError;
+        type Future = BoxFuture<Result<Response<Body>, crate::BoxError>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<Body>) -> Self::Future {
+            let attempt = self.calls.fetch_add(1, Ordering::SeqCst);
+            let failures = self.failures;
+            let code = self.code;
+            let bodies = self.bodies.clone();
+
+            Box::pin(async move {
+                let previous = request
+                    .headers()
+                    .get(GRPC_PREVIOUS_RPC_ATTEMPTS)
+                    .map(|v| v.to_str().unwrap().parse::<usize>().unwrap());
+                assert_eq!(previous, (attempt > 0).then_some(attempt));
+
+                let body = request.into_body().collect().await?.to_bytes();
+                bodies.lock().unwrap().push(body);
+
+                if attempt < failures {
+                    Ok(Status::new(code, "flaky").into_http())
+                } else {
+                    Ok(Response::new(Body::empty()))
+                }
+            })
+        }
+    }
+
+    fn request() -> Request<Body> {
+        Request::new(Body::new(http_body_util::Full::new(
+            bytes::Bytes::from_static(b"payload"),
+        )))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_until_success() {
+        let svc = Flaky::new(2, Code::Unavailable);
+        let mut retry = Retry::new(svc.clone(), RetryPolicy::new());
+
+        let response = retry.ready().await.unwrap().call(request()).await.unwrap();
+
+        assert!(Status::from_header_map(response.headers()).is_none());
+        assert_eq!(svc.calls.load(Ordering::SeqCst), 3);
+        assert!(svc.bodies.lock().unwrap().iter().all(|b| b == "payload"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_max_attempts() {
+        let svc = Flaky::new(usize::MAX, Code::Unavailable);
+        let mut retry = Retry::new(svc.clone(), RetryPolicy::new().max_attempts(2));
+
+        let response = retry.ready().await.unwrap().call(request()).await.unwrap();
+
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert_eq!(svc.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_retry_other_codes() {
+        let svc = Flaky::new(1, Code::InvalidArgument);
+        let mut retry = Retry::new(svc.clone(), RetryPolicy::new());
+
+        retry.ready().await.unwrap().call(request()).await.unwrap();
+
+        assert_eq!(svc.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn request_extension_overrides_policy() {
+        let svc = Flaky::new(1, Code::Unavailable);
+        let mut retry = Retry::with_policy(svc.clone(), None, None);
+
+        let mut request = request();
+        request.extensions_mut().insert(RetryPolicy::new());
+        retry.ready().await.unwrap().call(request).await.unwrap();
+
+        assert_eq!(svc.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_retry_past_buffer_limit() {
+        let svc = Flaky::new(1, Code::Unavailable);
+        let mut retry = Retry::new(svc.clone(), RetryPolicy::new().buffer_limit(4));
+
+        retry.ready().await.unwrap().call(request()).await.unwrap();
+
+        assert_eq!(svc.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn throttle_stops_retries() {
+        let svc = Flaky::new(usize::MAX, Code::Unavailable);
+        let mut retry = RetryLayer::new(RetryPolicy::new().max_attempts(5))
+            .throttle(RetryThrottle::new(4, 0.1))
+            .layer(svc.clone());
+
+        retry.ready().await.unwrap().call(request()).await.unwrap();
+
+        assert_eq!(svc.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(300));
+        let mut rng = HasherRng::new();
+
+        for attempts in 1..10 {
+            let max = Duration::from_millis(100 * 2u64.pow(attempts as u32 - 1))
+                .min(Duration::from_millis(300));
+            assert!(policy.backoff(attempts, &mut rng) <= max);
+        }
+    }
+
+    #[test]
+    fn pushback_header() {
+        let mut response = Status::unavailable("").into_http::<()>();
+        response
+            .headers_mut()
+            .insert(GRPC_RETRY_PUSHBACK_MS, HeaderValue::from_static("20"));
+        assert_eq!(
+            Outcome::of(&Ok(response)),
+            Outcome::Pushback(Code::Unavailable, Some(Duration::from_millis(20)))
+        );
+
+        let mut response = Status::unavailable("").into_http::<()>();
+        response
+            .headers_mut()
+            .insert(GRPC_RETRY_PUSHBACK_MS, HeaderValue::from_static("-1"));
+        assert_eq!(
+            Outcome::of(&Ok(response)),
+            Outcome::Pushback(Code::Unavailable, None)
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Token tracking is done in thousandths of a token, the precision gRFC A6
+/// allows for the token ratio.
+const SCALE: f64 = 1000.0;
+
+/// Limits retries and hedges of a channel when too many calls fail.
+///
+/// Every failed attempt costs one token and every successful call adds
+/// `token_ratio` tokens, up to `max_tokens`. While fewer than half of the
+/// tokens are left calls are no longer retried or hedged, which prevents
+/// retries from amplifying the load on an already overloaded server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryThrottle {
+    max_tokens: u32,
+    token_ratio: f64,
+}
+
+impl RetryThrottle {
+    /// Create a new `RetryThrottle`.
+    ///
+    /// `max_tokens` is capped at 1000 and `token_ratio` is rounded to three
+    /// decimal places.
+    pub fn new(max_tokens: u32, token_ratio: f64) -> Self {
+        Self {
+            max_tokens: max_tokens.clamp(1, 1000),
+            token_ratio: (token_ratio.max(0.0) * SCALE).round() / SCALE,
+        }
+    }
+}
+
+/// The token bucket shared by all calls of a channel.
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    max_tokens: u64,
+    token_ratio: u64,
+    tokens: AtomicU64,
+}
+
+impl Throttle {
+    pub(crate) fn new(config: RetryThrottle) -> Self {
+        let max_tokens = u64::from(config.max_tokens) * SCALE as u64;
+
+        Self {
+            max_tokens,
+            token_ratio: (config.token_ratio * SCALE) as u64,
+            tokens: AtomicU64::new(max_tokens),
+        }
+    }
+
+    /// Returns `true` if another attempt of a failed call may be sent.
+    pub(crate) fn allows_retry(&self) -> bool {
+        self.tokens.load(Ordering::Relaxed) > self.max_tokens / 2
+    }
+
+    pub(crate) fn record_failure(&self) {
+        let _ = self
+            .tokens
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |tokens| {
+                Some(tokens.saturating_sub(SCALE as u64))
+            });
+    }
+
+    pub(crate) fn record_success(&self) {
+        let _ = self
+            .tokens
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |tokens| {
+                Some((tokens + self.token_ratio).min(self.max_tokens))
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttles_below_half_of_max_tokens() {
+        let throttle = Throttle::new(RetryThrottle::new(4, 0.5));
+        assert!(throttle.allows_retry());
+
+        throttle.record_failure();
+        assert!(throttle.allows_retry());
+
+        throttle.record_failure();
+        assert!(!throttle.allows_retry());
+
+        throttle.record_success();
+        assert!(throttle.allows_retry());
+    }
+
+    #[test]
+    fn tokens_are_bounded() {
+        let throttle = Throttle::new(RetryThrottle::new(2, 1.0));
+
+        for _ in 0..10 {
+            throttle.record_success();
+        }
+        throttle.record_failure();
+        assert!(!throttle.allows_retry());
+
+        for _ in 0..10 {
+            throttle.record_failure();
+        }
+        throttle.record_success();
+        throttle.record_success();
+        assert!(throttle.allows_retry());
+    }
+}
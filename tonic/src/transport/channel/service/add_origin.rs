@@ -1,3 +1,4 @@
+use crate::request::AuthorityOverride;
 use crate::transport::channel::BoxFuture;
 use http::uri::Authority;
 use http::uri::Scheme;
@@ -49,13 +50,19 @@ where
         // Split the request into the head and the body.
         let (mut head, body) = req.into_parts();
 
+        // An authority set on the call takes precedence over the origin.
+        let authority = match head.extensions.remove::<AuthorityOverride>() {
+            Some(authority) => Some(authority.0),
+            None => self.authority.clone(),
+        };
+
         // Update the request URI
         head.uri = {
             // Split the request URI into parts.
             let mut uri: http::uri::Parts = head.uri.into();
             // Update the URI parts, setting the scheme and authority
             uri.scheme = self.scheme.clone();
-            uri.authority = self.authority.clone();
+            uri.authority = authority;
 
             http::Uri::from_parts(uri).expect("valid uri")
         };
@@ -67,3 +74,40 @@ where
         Box::pin(async move { fut.await.map_err(Into::into) })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Svc;
+
+    impl Service<Request<()>> for Svc {
+        type Response = Uri;
+        type Error = std::convert::Infallible;
+        type Future = std::future::Ready<Result<Uri, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: Request<()>) -> Self::Future {
+            std::future::ready(Ok(req.uri().clone()))
+        }
+    }
+
+    #[tokio::test]
+    async fn call_authority_overrides_origin() {
+        let mut svc = AddOrigin::new(Svc, Uri::from_static("https://example.com"));
+
+        let request = Request::builder().uri("/svc/Method").body(()).unwrap();
+        let uri = svc.call(request).await.unwrap();
+        assert_eq!(uri, "https://example.com/svc/Method");
+
+        let mut request = Request::builder().uri("/svc/Method").body(()).unwrap();
+        request
+            .extensions_mut()
+            .insert(AuthorityOverride(Authority::from_static("api.example.com")));
+        let uri = svc.call(request).await.unwrap();
+        assert_eq!(uri, "https://api.example.com/svc/Method");
+    }
+}
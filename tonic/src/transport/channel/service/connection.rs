@@ -1,7 +1,11 @@
 use super::{AddOrigin, Reconnect, SharedExec, UserAgent};
 use crate::{
     body::Body,
-    transport::{Endpoint, channel::BoxFuture, service::GrpcTimeout},
+    transport::{
+        Endpoint,
+        channel::{BoxFuture, connectivity::Connectivity},
+        service::GrpcTimeout,
+    },
 };
 use http::{Request, Response, Uri};
 use hyper::rt;
@@ -9,6 +13,10 @@ use hyper::{client::conn::http2::Builder, rt::Executor};
 use hyper_util::rt::TokioTimer;
 use std::{
     fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
     task::{Context, Poll},
 };
 use tower::load::Load;
@@ -22,6 +30,11 @@ use tower_service::Service;
 
 pub(crate) struct Connection {
     inner: BoxService<Request<Body>, Response<Body>, crate::BoxError>,
+    /// Number of requests waiting for their response headers, used as the
+    /// load so that the balancer spreads concurrent attempts of a call, like
+    /// hedges, across endpoints.
+    pending: Arc<AtomicUsize>,
+    connectivity: Connectivity,
 }
 
 impl Connection {
@@ -75,13 +88,26 @@ impl Connection {
             .option_layer(endpoint.rate_limit.map(|(l, d)| RateLimitLayer::new(l, d)))
             .into_inner();
 
-        let make_service =
-            MakeSendRequestService::new(connector, endpoint.executor.clone(), settings);
+        let connectivity = Connectivity::new();
+        let make_service = MakeSendRequestService::new(
+            connector,
+            endpoint.executor.clone(),
+            settings,
+            connectivity.clone(),
+        );
 
-        let conn = Reconnect::new(make_service, endpoint.uri().clone(), is_lazy);
+        let conn = Reconnect::new(
+            make_service,
+            endpoint.uri().clone(),
+            is_lazy,
+            endpoint.connection_backoff.clone(),
+            connectivity.clone(),
+        );
 
         Self {
             inner: BoxService::new(stack.layer(conn)),
+            pending: Arc::new(AtomicUsize::new(0)),
+            connectivity,
         }
     }
 
@@ -107,6 +133,11 @@ impl Connection {
     {
         Self::new(connector, endpoint, true)
     }
+
+    /// The connectivity state of this connection.
+    pub(crate) fn connectivity(&self) -> Connectivity {
+        self.connectivity.clone()
+    }
 }
 
 impl Service<Request<Body>> for Connection {
@@ -119,7 +150,13 @@ impl Service<Request<Body>> for Connection {
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
-        self.inner.call(req)
+        let pending = PendingGuard::new(self.pending.clone());
+        let fut = self.inner.call(req);
+
+        Box::pin(async move {
+            let _pending = pending;
+            fut.await
+        })
     }
 }
 
@@ -127,7 +164,22 @@ impl Load for Connection {
     type Metric = usize;
 
     fn load(&self) -> Self::Metric {
-        0
+        self.pending.load(Ordering::Relaxed)
+    }
+}
+
+struct PendingGuard(Arc<AtomicUsize>);
+
+impl PendingGuard {
+    fn new(pending: Arc<AtomicUsize>) -> Self {
+        pending.fetch_add(1, Ordering::Relaxed);
+        Self(pending)
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
     }
 }
 
@@ -167,14 +219,22 @@ struct MakeSendRequestService<C> {
     connector: C,
     executor: SharedExec,
     settings: Builder<SharedExec>,
+    /// Moved back to idle when a connection closes, even without calls.
+    connectivity: Connectivity,
 }
 
 impl<C> MakeSendRequestService<C> {
-    fn new(connector: C, executor: SharedExec, settings: Builder<SharedExec>) -> Self {
+    fn new(
+        connector: C,
+        executor: SharedExec,
+        settings: Builder<SharedExec>,
+        connectivity: Connectivity,
+    ) -> Self {
         Self {
             connector,
             executor,
             settings,
+            connectivity,
         }
     }
 }
@@ -198,6 +258,7 @@ where
         let fut = self.connector.call(req);
         let builder = self.settings.clone();
         let executor = self.executor.clone();
+        let connectivity = self.connectivity.clone();
 
         Box::pin(async move {
             let io = fut.await.map_err(Into::into)?;
@@ -209,6 +270,7 @@ where
                     if let Err(e) = conn.await {
                         tracing::debug!("connection task error: {:?}", e);
                     }
+                    connectivity.closed();
                 }) as _,
             );
 
@@ -216,3 +278,46 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        service::Routes,
+        transport::{Endpoint, Server, channel::ConnectivityState, server::TcpIncoming},
+    };
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn idle_after_connection_closes() {
+        let incoming = TcpIncoming::bind(([127, 0, 0, 1], 0).into()).unwrap();
+        let addr = incoming.local_addr().unwrap();
+
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(
+            Server::builder()
+                .add_routes(Routes::default())
+                .serve_with_incoming_shutdown(incoming, async move {
+                    let _ = shutdown_rx.await;
+                }),
+        );
+
+        let channel = Endpoint::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(channel.state(), ConnectivityState::Ready);
+
+        // The connection closes without any call being made.
+        shutdown_tx.send(()).unwrap();
+        server.await.unwrap().unwrap();
+
+        let state = tokio::time::timeout(
+            Duration::from_secs(5),
+            channel.wait_for_state_change(ConnectivityState::Ready),
+        )
+        .await
+        .unwrap();
+        assert_eq!(state, ConnectivityState::Idle);
+    }
+}
@@ -1,13 +1,17 @@
-use super::super::{Connection, Endpoint};
+use super::super::{
+    Connection, Endpoint,
+    connectivity::{Connectivity, ConnectivityState},
+};
 
 use std::{
+    collections::HashMap,
     hash::Hash,
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, ready},
 };
 use tokio::sync::mpsc::Receiver;
 use tokio_stream::Stream;
-use tower::discover::Change as TowerChange;
+use tower::discover::{Change as TowerChange, Discover};
 
 /// A change in the service set.
 #[derive(Debug, Clone)]
@@ -46,3 +50,59 @@ impl<K: Hash + Eq + Clone> Stream for DynamicServiceStream<K> {
 }
 
 impl<K: Hash + Eq + Clone> Unpin for DynamicServiceStream<K> {}
+
+/// Keeps the connectivity state of a balanced channel up to date with the
+/// connections it balances.
+pub(crate) struct TrackConnectivity<D: Discover> {
+    discover: D,
+    connections: HashMap<D::Key, Connectivity>,
+    connectivity: Connectivity,
+}
+
+impl<D: Discover> TrackConnectivity<D> {
+    pub(crate) fn new(discover: D, connectivity: Connectivity) -> Self {
+        Self {
+            discover,
+            connections: HashMap::new(),
+            connectivity,
+        }
+    }
+}
+
+impl<D> Stream for TrackConnectivity<D>
+where
+    D: Discover<Service = Connection> + Unpin,
+    D::Key: Hash + Clone,
+{
+    type Item = Result<TowerChange<D::Key, Connection>, D::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let change = ready!(Pin::new(&mut self.discover).poll_discover(cx));
+
+        match &change {
+            Some(Ok(TowerChange::Insert(k, connection))) => {
+                let connectivity = connection.connectivity();
+                self.connectivity.add_member(connectivity.clone());
+                if let Some(old) = self.connections.insert(k.clone(), connectivity) {
+                    self.connectivity.remove_member(&old);
+                }
+            }
+            Some(Ok(TowerChange::Remove(k))) => {
+                if let Some(old) = self.connections.remove(k) {
+                    self.connectivity.remove_member(&old);
+                }
+            }
+            Some(Err(_)) | None => {}
+        }
+
+        Poll::Ready(change)
+    }
+}
+
+impl<D: Discover + Unpin> Unpin for TrackConnectivity<D> {}
+
+impl<D: Discover> Drop for TrackConnectivity<D> {
+    fn drop(&mut self) {
+        self.connectivity.set(ConnectivityState::Shutdown);
+    }
+}
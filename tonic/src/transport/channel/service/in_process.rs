@@ -0,0 +1,168 @@
+use crate::{
+    body::Body,
+    service::{RecoverError, Routes},
+    transport::{
+        server::{CancelGuard, CancelOnDropBody, CancellationToken},
+        service::GrpcTimeout,
+    },
+};
+use http::{Request, Response, Version};
+use std::{
+    fmt,
+    task::{Context, Poll},
+};
+use tower::Service;
+
+/// Dispatches requests directly to [`Routes`] in the same process.
+///
+/// Requests and responses go through the same steps they would on a
+/// connection: the extensions of the client are not passed to the server and
+/// the other way around, the server applies `grpc-timeout` and cancels calls
+/// dropped before they completed.
+#[derive(Clone)]
+pub(crate) struct InProcess {
+    routes: RecoverError<GrpcTimeout<Routes>>,
+}
+
+impl InProcess {
+    pub(crate) fn new(routes: Routes) -> Self {
+        Self {
+            routes: RecoverError::new(GrpcTimeout::new(routes.prepare(), None)),
+        }
+    }
+}
+
+impl Service<Request<Body>> for InProcess {
+    type Response = Response<Body>;
+    type Error = crate::BoxError;
+    type Future = crate::transport::channel::ConnectionFuture;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Service::<Request<Body>>::poll_ready(&mut self.routes, cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let (client_parts, body) = request.into_parts();
+
+        let mut request = Request::new(body);
+        *request.method_mut() = client_parts.method;
+        *request.uri_mut() = client_parts.uri;
+        *request.version_mut() = Version::HTTP_2;
+        *request.headers_mut() = client_parts.headers;
+
+        let token = CancellationToken::new();
+        request.extensions_mut().insert(token.clone());
+        let mut guard = CancelGuard::new(token);
+
+        let future = self.routes.call(request);
+
+        Box::pin(async move {
+            let response = match future.await {
+                Ok(response) => response,
+                Err(err) => {
+                    guard.disarm();
+                    return Err(err);
+                }
+            };
+
+            let (server_parts, body) = response.into_parts();
+            let mut response = Response::new(Body::new(CancelOnDropBody::new(body, guard)));
+            *response.status_mut() = server_parts.status;
+            *response.version_mut() = server_parts.version;
+            *response.headers_mut() = server_parts.headers;
+
+            Ok(response)
+        })
+    }
+}
+
+impl fmt::Debug for InProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InProcess").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Code, Status, server::NamedService, transport::Channel};
+    use std::{convert::Infallible, future::Future, pin::Pin, time::Duration};
+    use tower::ServiceExt;
+
+    #[derive(Clone)]
+    struct Echo;
+
+    #[derive(Clone)]
+    struct ClientOnly;
+
+    impl NamedService for Echo {
+        const NAME: &'static str = "test.Echo";
+    }
+
+    impl Service<Request<Body>> for Echo {
+        type Response = Response<Body>;
+        type Error = Infallible;
+        type Future = Pin<Box<dyn Future<Output = Result<Response<Body>, Infallible>> + Send>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<Body>) -> Self::Future {
+            Box::pin(async move {
+                assert!(request.extensions().get::<ClientOnly>().is_none());
+                let token = request.extensions().get::<CancellationToken>().unwrap();
+
+                if request.uri().path() == "/test.Echo/Hang" {
+                    token.cancelled().await;
+                }
+
+                let mut response = Status::ok("").into_http::<Body>();
+                response
+                    .headers_mut()
+                    .insert("x-echo", request.headers()["x-request"].clone());
+                response.extensions_mut().insert(ClientOnly);
+                Ok(response)
+            })
+        }
+    }
+
+    fn request(path: &str) -> Request<Body> {
+        let mut request = Request::builder()
+            .uri(path)
+            .header("x-request", "hello")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(ClientOnly);
+        request
+    }
+
+    #[tokio::test]
+    async fn passes_metadata_but_not_extensions() {
+        let channel = Channel::in_process(Routes::new(Echo));
+
+        let response = channel.oneshot(request("/test.Echo/Get")).await.unwrap();
+
+        assert_eq!(response.headers()["x-echo"], "hello");
+        assert_eq!(response.headers()["grpc-status"], "0");
+        assert!(response.extensions().get::<ClientOnly>().is_none());
+    }
+
+    #[tokio::test]
+    async fn enforces_deadlines() {
+        let channel = Channel::in_process(Routes::new(Echo));
+
+        let mut request = request("/test.Echo/Hang");
+        request
+            .headers_mut()
+            .insert("grpc-timeout", "10m".parse().unwrap());
+        let response = tokio::time::timeout(Duration::from_secs(5), channel.oneshot(request))
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Same as a server over a connection.
+        let status = Status::from_header_map(response.headers()).unwrap();
+        assert_eq!(status.code(), Code::Cancelled);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{Status, body::Body};
+use bytes::{Buf, Bytes};
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use pin_project::pin_project;
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+/// The size of the length prefix of every gRPC message.
+const HEADER_SIZE: usize = 5;
+
+/// Which side of a call a [`MessageLimitBody`] carries.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Direction {
+    Request,
+    Response,
+}
+
+/// Where the status of a request body exceeding its limit is reported.
+pub(crate) type LimitExceeded = Arc<Mutex<Option<Status>>>;
+
+/// A body that fails once it carries a gRPC message larger than a limit.
+///
+/// Messages are not decoded, only their length prefix is inspected as the
+/// frames pass through.
+#[pin_project]
+#[derive(Debug)]
+pub(crate) struct MessageLimitBody {
+    #[pin]
+    inner: Body,
+    limit: usize,
+    direction: Direction,
+    header: [u8; HEADER_SIZE],
+    header_len: usize,
+    /// Bytes of the current message that still have to pass.
+    remaining: usize,
+    exceeded: Option<LimitExceeded>,
+}
+
+impl MessageLimitBody {
+    pub(crate) fn new(inner: Body, limit: usize, direction: Direction) -> Self {
+        Self {
+            inner,
+            limit,
+            direction,
+            header: [0; HEADER_SIZE],
+            header_len: 0,
+            remaining: 0,
+            exceeded: None,
+        }
+    }
+
+    /// Report the status to `exceeded` as well when the limit is exceeded.
+    pub(crate) fn report_to(self, exceeded: LimitExceeded) -> Self {
+        Self {
+            exceeded: Some(exceeded),
+            ..self
+        }
+    }
+}
+
+/// Returns the length of the first message in `data` that is larger than
+/// `limit`, keeping track of messages split across several frames.
+fn check(
+    mut data: &[u8],
+    limit: usize,
+    header: &mut [u8; HEADER_SIZE],
+    header_len: &mut usize,
+    remaining: &mut usize,
+) -> Option<usize> {
+    while data.has_remaining() {
+        if *remaining > 0 {
+            let n = (*remaining).min(data.len());
+            *remaining -= n;
+            data.advance(n);
+            continue;
+        }
+
+        let n = (HEADER_SIZE - *header_len).min(data.len());
+        header[*header_len..*header_len + n].copy_from_slice(&data[..n]);
+        *header_len += n;
+        data.advance(n);
+
+        if *header_len == HEADER_SIZE {
+            *header_len = 0;
+            let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+            if len > limit {
+                return Some(len);
+            }
+            *remaining = len;
+        }
+    }
+
+    None
+}
+
+impl HttpBody for MessageLimitBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+
+        let frame = match std::task::ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            other => return Poll::Ready(other),
+        };
+
+        if let Some(data) = frame.data_ref()
+            && let Some(len) = check(
+                data,
+                *this.limit,
+                this.header,
+                this.header_len,
+                this.remaining,
+            )
+        {
+            let limit = *this.limit;
+            // The length is the one on the wire, which may be compressed.
+            let status = Status::out_of_range(match this.direction {
+                Direction::Request => format!(
+                    "Error, encoded message length too large: found {len} bytes, the limit is: {limit} bytes"
+                ),
+                Direction::Response => format!(
+                    "Error, received message length too large: found {len} bytes, the limit is: {limit} bytes"
+                ),
+            });
+
+            if let Some(exceeded) = this.exceeded {
+                *exceeded.lock().unwrap() = Some(status.clone());
+            }
+
+            return Poll::Ready(Some(Err(status)));
+        }
+
+        Poll::Ready(Some(Ok(frame)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::{BodyExt, StreamBody};
+    use tokio_stream::iter;
+
+    fn message(len: usize) -> Vec<u8> {
+        let mut buf = vec![0];
+        buf.extend_from_slice(&(len as u32).to_be_bytes());
+        buf.resize(HEADER_SIZE + len, 1);
+        buf
+    }
+
+    fn body(chunks: Vec<Vec<u8>>) -> Body {
+        let frames = chunks
+            .into_iter()
+            .map(|c| Ok::<_, Status>(Frame::data(Bytes::from(c))));
+        Body::new(StreamBody::new(iter(frames)))
+    }
+
+    #[tokio::test]
+    async fn passes_messages_within_limit() {
+        let mut data = message(4);
+        data.extend(message(8));
+        let chunks = data.chunks(3).map(<[u8]>::to_vec).collect();
+
+        let body = MessageLimitBody::new(body(chunks), 8, Direction::Response);
+        let collected = body.collect().await.unwrap().to_bytes();
+
+        assert_eq!(collected.len(), 2 * HEADER_SIZE + 12);
+    }
+
+    #[tokio::test]
+    async fn fails_on_message_split_across_frames() {
+        let mut data = message(4);
+        data.extend(message(9));
+        let chunks = data.chunks(2).map(<[u8]>::to_vec).collect();
+
+        let exceeded = LimitExceeded::default();
+        let body =
+            MessageLimitBody::new(body(chunks), 8, Direction::Request).report_to(exceeded.clone());
+        let status = body.collect().await.unwrap_err();
+
+        assert_eq!(status.code(), crate::Code::OutOfRange);
+        assert_eq!(
+            exceeded.lock().unwrap().as_ref().map(Status::code),
+            Some(crate::Code::OutOfRange)
+        );
+    }
+}
@@ -10,9 +10,22 @@ use self::reconnect::Reconnect;
 mod connection;
 pub(super) use self::connection::Connection;
 
+#[cfg(all(feature = "server", feature = "router"))]
+mod in_process;
+#[cfg(all(feature = "server", feature = "router"))]
+pub(super) use self::in_process::InProcess;
+
 mod discover;
 pub use self::discover::Change;
-pub(super) use self::discover::DynamicServiceStream;
+pub(super) use self::discover::{DynamicServiceStream, TrackConnectivity};
+
+mod replay_body;
+pub(crate) use self::replay_body::ReplayBody;
+
+#[cfg(feature = "service-config")]
+mod message_limit;
+#[cfg(feature = "service-config")]
+pub(crate) use self::message_limit::{Direction, LimitExceeded, MessageLimitBody};
 
 mod io;
 use self::io::BoxedIo;
@@ -1,10 +1,16 @@
+use crate::transport::channel::{
+    backoff::{Backoff, ConnectionBackoff},
+    connectivity::{Connectivity, ConnectivityState},
+};
 use pin_project::pin_project;
 use std::fmt;
 use std::{
     future::Future,
+    io,
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, ready},
 };
+use tokio::time::{Instant, Sleep};
 use tower::make::MakeService;
 use tower_service::Service;
 use tracing::trace;
@@ -20,12 +26,17 @@ where
     error: Option<crate::BoxError>,
     has_been_connected: bool,
     is_lazy: bool,
+    backoff: Option<Backoff>,
+    /// When the next attempt may start, if the current one fails.
+    next_attempt: Instant,
+    connectivity: Connectivity,
 }
 
 #[derive(Debug)]
 enum State<F, S> {
     Idle,
-    Connecting(F),
+    Connecting(F, Option<Pin<Box<Sleep>>>),
+    Backoff(Pin<Box<Sleep>>),
     Connected(S),
 }
 
@@ -34,7 +45,13 @@ where
     M: Service<Target>,
     M::Error: Into<crate::BoxError>,
 {
-    pub(crate) fn new(mk_service: M, target: Target, is_lazy: bool) -> Self {
+    pub(crate) fn new(
+        mk_service: M,
+        target: Target,
+        is_lazy: bool,
+        backoff: Option<ConnectionBackoff>,
+        connectivity: Connectivity,
+    ) -> Self {
         Reconnect {
             mk_service,
             state: State::Idle,
@@ -42,6 +59,9 @@ where
             error: None,
             has_been_connected: false,
             is_lazy,
+            backoff: backoff.map(Backoff::new),
+            next_attempt: Instant::now(),
+            connectivity,
         }
     }
 }
@@ -78,52 +98,98 @@ where
                         }
                     }
 
+                    let timeout = self.backoff.as_mut().map(|backoff| {
+                        let delay = backoff.delay();
+                        self.next_attempt = Instant::now() + delay;
+                        Box::pin(tokio::time::sleep(backoff.connect_timeout(delay)))
+                    });
+
                     let fut = self.mk_service.make_service(self.target.clone());
-                    self.state = State::Connecting(fut);
+                    self.connectivity.set(ConnectivityState::Connecting);
+                    self.state = State::Connecting(fut, timeout);
                     continue;
                 }
-                State::Connecting(ref mut f) => {
+                State::Connecting(ref mut f, ref mut timeout) => {
                     trace!("poll_ready; connecting");
-                    match Pin::new(f).poll(cx) {
-                        Poll::Ready(Ok(service)) => {
-                            state = State::Connected(service);
-                        }
+                    let result = match Pin::new(f).poll(cx) {
+                        Poll::Ready(result) => result.map_err(Into::into),
                         Poll::Pending => {
-                            trace!("poll_ready; not ready");
-                            return Poll::Pending;
+                            let timed_out = timeout
+                                .as_mut()
+                                .is_some_and(|timeout| timeout.as_mut().poll(cx).is_ready());
+                            if !timed_out {
+                                trace!("poll_ready; not ready");
+                                return Poll::Pending;
+                            }
+
+                            Err(io::Error::new(
+                                io::ErrorKind::TimedOut,
+                                "connection attempt timed out",
+                            )
+                            .into())
                         }
-                        Poll::Ready(Err(e)) => {
-                            trace!("poll_ready; error");
+                    };
 
-                            state = State::Idle;
+                    match result {
+                        Ok(service) => {
+                            state = State::Connected(service);
+                        }
+                        Err(e) => {
+                            trace!("poll_ready; error");
+                            self.connectivity.failed(&e);
 
                             if !(self.has_been_connected || self.is_lazy) {
-                                return Poll::Ready(Err(e.into()));
-                            } else {
-                                let error = e.into();
-                                tracing::debug!("reconnect::poll_ready: {:?}", error);
-                                self.error = Some(error);
-                                break;
+                                self.state = State::Idle;
+                                return Poll::Ready(Err(e));
+                            }
+
+                            tracing::debug!("reconnect::poll_ready: {:?}", e);
+                            match &mut self.backoff {
+                                // Calls wait for the backoff to elapse, or are
+                                // failed by the channel unless they are
+                                // wait-for-ready.
+                                Some(backoff) => {
+                                    backoff.failed();
+                                    state = State::Backoff(Box::pin(tokio::time::sleep_until(
+                                        self.next_attempt,
+                                    )));
+                                }
+                                None => {
+                                    state = State::Idle;
+                                    self.error = Some(e);
+                                    break;
+                                }
                             }
                         }
                     }
                 }
+                State::Backoff(ref mut sleep) => {
+                    trace!("poll_ready; backoff");
+                    ready!(sleep.as_mut().poll(cx));
+                    state = State::Idle;
+                }
                 State::Connected(ref mut inner) => {
                     trace!("poll_ready; connected");
 
                     self.has_been_connected = true;
+                    if let Some(backoff) = &mut self.backoff {
+                        backoff.reset();
+                    }
 
                     match inner.poll_ready(cx) {
                         Poll::Ready(Ok(())) => {
                             trace!("poll_ready; ready");
+                            self.connectivity.set(ConnectivityState::Ready);
                             return Poll::Ready(Ok(()));
                         }
                         Poll::Pending => {
                             trace!("poll_ready; not ready");
+                            self.connectivity.set(ConnectivityState::Ready);
                             return Poll::Pending;
                         }
                         Poll::Ready(Err(_)) => {
                             trace!("poll_ready; error");
+                            self.connectivity.set(ConnectivityState::Idle);
                             state = State::Idle;
                         }
                     }
@@ -153,6 +219,16 @@ where
     }
 }
 
+impl<M, Target> Drop for Reconnect<M, Target>
+where
+    M: Service<Target>,
+    M::Error: Into<crate::BoxError>,
+{
+    fn drop(&mut self) {
+        self.connectivity.set(ConnectivityState::Shutdown);
+    }
+}
+
 impl<M, Target> fmt::Debug for Reconnect<M, Target>
 where
     M: Service<Target> + fmt::Debug,
@@ -218,3 +294,62 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        convert::Infallible,
+        future::{Ready, poll_fn, ready},
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+    use tokio::time::timeout;
+    use tower::util::ServiceFn;
+
+    type Connected = ServiceFn<fn(()) -> Ready<Result<(), Infallible>>>;
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_between_failed_attempts() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let mk_service = tower::service_fn({
+            let attempts = attempts.clone();
+            move |_: ()| {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                ready(Err::<Connected, _>(io::Error::from(
+                    io::ErrorKind::ConnectionRefused,
+                )))
+            }
+        });
+
+        let connectivity = Connectivity::new();
+        let backoff = ConnectionBackoff::new()
+            .initial_backoff(Duration::from_secs(1))
+            .jitter(0.0);
+        let mut reconnect =
+            Reconnect::new(mk_service, (), true, Some(backoff), connectivity.clone());
+        let state = connectivity.subscribe();
+        // The backoff follows the tokio clock, however far it moved.
+        tokio::time::advance(Duration::from_secs(10)).await;
+
+        let poll_ready = poll_fn(|cx| Service::<()>::poll_ready(&mut reconnect, cx));
+        assert!(
+            timeout(Duration::from_millis(500), poll_ready)
+                .await
+                .is_err()
+        );
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(*state.borrow(), ConnectivityState::TransientFailure);
+
+        // The second attempt is made once the first backoff elapsed.
+        let poll_ready = poll_fn(|cx| Service::<()>::poll_ready(&mut reconnect, cx));
+        assert!(timeout(Duration::from_secs(1), poll_ready).await.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        drop(reconnect);
+        assert_eq!(*state.borrow(), ConnectivityState::Shutdown);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{Status, body::Body};
+use bytes::Bytes;
+use http::HeaderMap;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// A request body that can be replayed for several attempts of the same call.
+///
+/// Every clone of a `ReplayBody` yields the same sequence of frames. Frames
+/// are pulled from the original body on demand and kept in a shared buffer
+/// so that attempts started later can catch up. Once the buffered data
+/// exceeds the configured limit the buffer is released and the body is
+/// considered _committed_: attempts that have already fallen behind will
+/// fail and no new attempt can be started.
+#[derive(Debug)]
+pub(crate) struct ReplayBody {
+    shared: Arc<Mutex<Shared>>,
+    /// Absolute index of the next data frame this replica yields.
+    position: usize,
+    trailers_sent: bool,
+}
+
+#[derive(Debug)]
+struct Shared {
+    source: Option<Body>,
+    /// Buffered data frames, `frames[0]` has the absolute index `base`.
+    frames: Vec<Bytes>,
+    base: usize,
+    buffered_bytes: usize,
+    limit: usize,
+    committed: bool,
+    trailers: Option<HeaderMap>,
+    error: Option<Status>,
+    waiters: Vec<Waker>,
+}
+
+impl Shared {
+    fn end(&self) -> usize {
+        self.base + self.frames.len()
+    }
+
+    fn wake_all(&mut self) {
+        for waker in self.waiters.drain(..) {
+            waker.wake();
+        }
+    }
+
+    fn push(&mut self, data: Bytes) {
+        self.buffered_bytes += data.len();
+        self.frames.push(data);
+
+        if !self.committed && self.buffered_bytes > self.limit {
+            tracing::trace!(
+                "request body exceeded the replay buffer limit of {} bytes",
+                self.limit
+            );
+            self.committed = true;
+        }
+
+        // Once committed only the most recent frame has to be kept around
+        // for replicas that are still in sync with the source.
+        if self.committed {
+            let drop = self.frames.len() - 1;
+            self.base += drop;
+            self.frames.drain(..drop);
+            self.buffered_bytes = self.frames.iter().map(Bytes::len).sum();
+        }
+    }
+}
+
+impl ReplayBody {
+    pub(crate) fn new(body: Body, limit: usize) -> Self {
+        let source = (!body.is_end_stream()).then_some(body);
+
+        Self {
+            shared: Arc::new(Mutex::new(Shared {
+                source,
+                frames: Vec::new(),
+                base: 0,
+                buffered_bytes: 0,
+                limit,
+                committed: false,
+                trailers: None,
+                error: None,
+                waiters: Vec::new(),
+            })),
+            position: 0,
+            trailers_sent: false,
+        }
+    }
+
+    /// Returns `true` once the body can no longer be replayed from the start.
+    pub(crate) fn is_committed(&self) -> bool {
+        self.shared.lock().unwrap().committed
+    }
+
+    /// Prevent any further replay of this body.
+    pub(crate) fn commit(&self) {
+        self.shared.lock().unwrap().committed = true;
+    }
+}
+
+impl Clone for ReplayBody {
+    /// Create a new replica that yields the body from its first frame.
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            position: 0,
+            trailers_sent: false,
+        }
+    }
+}
+
+impl Drop for ReplayBody {
+    fn drop(&mut self) {
+        // This replica may be the one whose waker is registered with the
+        // source, let the others know they have to take over.
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.wake_all();
+        }
+    }
+}
+
+impl HttpBody for ReplayBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        let mut shared = this.shared.lock().unwrap();
+
+        if this.position < shared.base {
+            return Poll::Ready(Some(Err(Status::unavailable(
+                "request body is no longer available for replay",
+            ))));
+        }
+
+        if this.position < shared.end() {
+            let data = shared.frames[this.position - shared.base].clone();
+            this.position += 1;
+            return Poll::Ready(Some(Ok(Frame::data(data))));
+        }
+
+        while let Some(source) = shared.source.as_mut() {
+            match Pin::new(source).poll_frame(cx) {
+                Poll::Pending => {
+                    if !shared.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+                        shared.waiters.push(cx.waker().clone());
+                    }
+                    return Poll::Pending;
+                }
+                Poll::Ready(Some(Ok(frame))) => {
+                    shared.wake_all();
+                    match frame.into_data() {
+                        Ok(data) => {
+                            shared.push(data.clone());
+                            this.position += 1;
+                            return Poll::Ready(Some(Ok(Frame::data(data))));
+                        }
+                        Err(frame) => {
+                            if let Ok(trailers) = frame.into_trailers() {
+                                shared.trailers = Some(trailers);
+                            }
+                        }
+                    }
+                }
+                Poll::Ready(Some(Err(status))) => {
+                    shared.wake_all();
+                    shared.error = Some(status);
+                    shared.source = None;
+                }
+                Poll::Ready(None) => {
+                    shared.wake_all();
+                    shared.source = None;
+                }
+            }
+        }
+
+        if let Some(status) = &shared.error {
+            return Poll::Ready(Some(Err(status.clone())));
+        }
+
+        if !this.trailers_sent {
+            this.trailers_sent = true;
+            if let Some(trailers) = shared.trailers.clone() {
+                return Poll::Ready(Some(Ok(Frame::trailers(trailers))));
+            }
+        }
+
+        Poll::Ready(None)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        let shared = self.shared.lock().unwrap();
+        shared.source.is_none()
+            && shared.error.is_none()
+            && self.position >= shared.end()
+            && (self.trailers_sent || shared.trailers.is_none())
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::{BodyExt, StreamBody};
+    use tokio_stream::iter;
+
+    fn body(chunks: &[&'static str]) -> Body {
+        let frames = chunks
+            .iter()
+            .map(|c| Ok::<_, Status>(Frame::data(Bytes::from_static(c.as_bytes()))))
+            .collect::<Vec<_>>();
+        Body::new(StreamBody::new(iter(frames)))
+    }
+
+    async fn collect(body: ReplayBody) -> Result<Bytes, Status> {
+        body.collect().await.map(|c| c.to_bytes())
+    }
+
+    #[tokio::test]
+    async fn replicas_yield_the_same_frames() {
+        let first = ReplayBody::new(body(&["hello", " ", "world"]), 1024);
+        let second = first.clone();
+
+        assert_eq!(collect(first).await.unwrap(), "hello world");
+        assert_eq!(collect(second).await.unwrap(), "hello world");
+    }
+
+    #[tokio::test]
+    async fn commits_when_limit_is_exceeded() {
+        let first = ReplayBody::new(body(&["hello", " ", "world"]), 4);
+        let second = first.clone();
+
+        assert_eq!(collect(first).await.unwrap(), "hello world");
+        assert!(second.is_committed());
+        assert_eq!(
+            collect(second).await.unwrap_err().code(),
+            crate::Code::Unavailable
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_body_is_end_stream() {
+        let body = ReplayBody::new(Body::empty(), 1024);
+        assert!(body.is_end_stream());
+        assert!(body.clone().is_end_stream());
+    }
+}
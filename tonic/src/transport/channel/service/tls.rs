@@ -15,25 +15,30 @@ use tokio_rustls::{
 };
 
 use super::io::BoxedIo;
+use crate::transport::CertificateProvider;
 use crate::transport::service::tls::{
-    ALPN_H2, TlsError, convert_certificate_to_pki_types, convert_identity_to_pki_types,
+    ALPN_H2, TlsConfig, TlsError, convert_certificate_to_pki_types, convert_identity_to_pki_types,
 };
 use crate::transport::tls::{Certificate, Identity};
 
 #[derive(Clone)]
 pub(crate) struct TlsConnector {
-    config: Arc<ClientConfig>,
+    config: TlsConfig<ClientConfig>,
     domain: Arc<ServerName<'static>>,
     assume_http2: bool,
     timeout: Option<Duration>,
 }
 
 impl TlsConnector {
+    /// Create a connector presenting `identity` and trusting `ca_certs`, or
+    /// presenting the identity of `certificate_provider` if any, in which
+    /// case its CA certificates are also trusted.
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         ca_certs: Vec<Certificate>,
         trust_anchors: Vec<TrustAnchor<'static>>,
         identity: Option<Identity>,
+        certificate_provider: Option<Arc<dyn CertificateProvider>>,
         server_cert_verifier: Option<Arc<dyn ServerCertVerifier>>,
         domain: &str,
         assume_http2: bool,
@@ -42,6 +47,78 @@ impl TlsConnector {
         #[cfg(feature = "tls-native-roots")] with_native_roots: bool,
         #[cfg(feature = "tls-webpki-roots")] with_webpki_roots: bool,
     ) -> Result<Self, crate::BoxError> {
+        let options = ClientOptions {
+            ca_certs,
+            trust_anchors,
+            server_cert_verifier,
+            use_key_log,
+            #[cfg(feature = "tls-native-roots")]
+            with_native_roots,
+            #[cfg(feature = "tls-webpki-roots")]
+            with_webpki_roots,
+        };
+
+        let config = match certificate_provider {
+            Some(provider) => TlsConfig::provided(provider, move |certificates| {
+                options.client_config(
+                    certificates.identity.as_ref(),
+                    &certificates.ca_certificates,
+                )
+            })?,
+            None => TlsConfig::Static(Arc::new(options.client_config(identity.as_ref(), &[])?)),
+        };
+
+        Ok(Self {
+            config,
+            domain: Arc::new(ServerName::try_from(domain)?.to_owned()),
+            assume_http2,
+            timeout,
+        })
+    }
+
+    pub(crate) async fn connect<I>(&self, io: I) -> Result<BoxedIo, crate::BoxError>
+    where
+        I: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let conn_fut =
+            RustlsConnector::from(self.config.get()).connect(self.domain.as_ref().to_owned(), io);
+        let io = match self.timeout {
+            Some(timeout) => time::timeout(timeout, conn_fut)
+                .await
+                .map_err(|_| TlsError::HandshakeTimeout)?,
+            None => conn_fut.await,
+        }?;
+
+        // Generally we require ALPN to be negotiated, but if the user has
+        // explicitly set `assume_http2` to true, we'll allow it to be missing.
+        let (_, session) = io.get_ref();
+        let alpn_protocol = session.alpn_protocol();
+        if !(alpn_protocol == Some(ALPN_H2) || self.assume_http2) {
+            return Err(TlsError::H2NotNegotiated.into());
+        }
+        Ok(BoxedIo::new(TokioIo::new(io)))
+    }
+}
+
+/// The options of a [`TlsConnector`] besides its certificates, which may be
+/// provided.
+struct ClientOptions {
+    ca_certs: Vec<Certificate>,
+    trust_anchors: Vec<TrustAnchor<'static>>,
+    server_cert_verifier: Option<Arc<dyn ServerCertVerifier>>,
+    use_key_log: bool,
+    #[cfg(feature = "tls-native-roots")]
+    with_native_roots: bool,
+    #[cfg(feature = "tls-webpki-roots")]
+    with_webpki_roots: bool,
+}
+
+impl ClientOptions {
+    fn client_config(
+        &self,
+        identity: Option<&Identity>,
+        provided_ca_certs: &[Certificate],
+    ) -> Result<ClientConfig, crate::BoxError> {
         fn with_provider(
             provider: Arc<crypto::CryptoProvider>,
         ) -> ConfigBuilder<ClientConfig, WantsVerifier> {
@@ -61,29 +138,31 @@ impl TlsConnector {
             _ => ClientConfig::builder(),
         };
 
-        let builder = match server_cert_verifier {
+        let ca_certs = self.ca_certs.iter().chain(provided_ca_certs);
+
+        let builder = match &self.server_cert_verifier {
             Some(verifier) => {
-                if !ca_certs.is_empty() || !trust_anchors.is_empty() {
+                if ca_certs.count() > 0 || !self.trust_anchors.is_empty() {
                     return Err(TlsError::VerifierConflict.into());
                 }
                 #[cfg(feature = "tls-native-roots")]
-                if with_native_roots {
+                if self.with_native_roots {
                     return Err(TlsError::VerifierConflict.into());
                 }
                 #[cfg(feature = "tls-webpki-roots")]
-                if with_webpki_roots {
+                if self.with_webpki_roots {
                     return Err(TlsError::VerifierConflict.into());
                 }
 
                 builder
                     .dangerous()
-                    .with_custom_certificate_verifier(verifier)
+                    .with_custom_certificate_verifier(verifier.clone())
             }
             None => {
-                let mut roots = RootCertStore::from_iter(trust_anchors);
+                let mut roots = RootCertStore::from_iter(self.trust_anchors.iter().cloned());
 
                 #[cfg(feature = "tls-native-roots")]
-                if with_native_roots {
+                if self.with_native_roots {
                     let rustls_native_certs::CertificateResult { certs, errors, .. } =
                         rustls_native_certs::load_native_certs();
                     if !errors.is_empty() {
@@ -96,12 +175,12 @@ impl TlsConnector {
                 }
 
                 #[cfg(feature = "tls-webpki-roots")]
-                if with_webpki_roots {
+                if self.with_webpki_roots {
                     roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
                 }
 
                 for cert in ca_certs {
-                    roots.add_parsable_certificates(convert_certificate_to_pki_types(&cert)?);
+                    roots.add_parsable_certificates(convert_certificate_to_pki_types(cert)?);
                 }
 
                 builder.with_root_certificates(roots)
@@ -110,46 +189,18 @@ impl TlsConnector {
 
         let mut config = match identity {
             Some(identity) => {
-                let (client_cert, client_key) = convert_identity_to_pki_types(&identity)?;
+                let (client_cert, client_key) = convert_identity_to_pki_types(identity)?;
                 builder.with_client_auth_cert(client_cert, client_key)?
             }
             None => builder.with_no_client_auth(),
         };
 
-        if use_key_log {
+        if self.use_key_log {
             config.key_log = Arc::new(tokio_rustls::rustls::KeyLogFile::new());
         }
 
         config.alpn_protocols.push(ALPN_H2.into());
-        Ok(Self {
-            config: Arc::new(config),
-            domain: Arc::new(ServerName::try_from(domain)?.to_owned()),
-            assume_http2,
-            timeout,
-        })
-    }
-
-    pub(crate) async fn connect<I>(&self, io: I) -> Result<BoxedIo, crate::BoxError>
-    where
-        I: AsyncRead + AsyncWrite + Send + Unpin + 'static,
-    {
-        let conn_fut =
-            RustlsConnector::from(self.config.clone()).connect(self.domain.as_ref().to_owned(), io);
-        let io = match self.timeout {
-            Some(timeout) => time::timeout(timeout, conn_fut)
-                .await
-                .map_err(|_| TlsError::HandshakeTimeout)?,
-            None => conn_fut.await,
-        }?;
-
-        // Generally we require ALPN to be negotiated, but if the user has
-        // explicitly set `assume_http2` to true, we'll allow it to be missing.
-        let (_, session) = io.get_ref();
-        let alpn_protocol = session.alpn_protocol();
-        if !(alpn_protocol == Some(ALPN_H2) || self.assume_http2) {
-            return Err(TlsError::H2NotNegotiated.into());
-        }
-        Ok(BoxedIo::new(TokioIo::new(io)))
+        Ok(config)
     }
 }
 
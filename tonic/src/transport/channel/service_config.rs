@@ -0,0 +1,574 @@
+use super::{
+    HedgingPolicy, RetryPolicy, RetryThrottle,
+    retry::CallPolicy,
+    service::{Direction, LimitExceeded, MessageLimitBody},
+};
+use crate::{
+    Code,
+    body::Body,
+    metadata::GRPC_TIMEOUT_HEADER,
+    request::{WaitForReady, duration_to_grpc_timeout, try_parse_grpc_timeout},
+    transport::Error,
+};
+use http::{HeaderValue, Request, Response};
+use serde::Deserialize;
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+
+/// A gRPC [service config] providing per-method defaults for a channel.
+///
+/// The `methodConfig` entries of the config are matched against the path of
+/// every call: an entry naming both the service and the method takes
+/// precedence over one naming only the service, which takes precedence over
+/// an entry with an empty name. The following fields are applied:
+///
+/// - `timeout` sets the `grpc-timeout` of the call, unless the call has a
+///   shorter one.
+/// - `maxRequestMessageBytes` and `maxResponseMessageBytes` fail the call with
+///   [`Code::OutOfRange`] when a larger message is sent or received.
+/// - `retryPolicy` and `hedgingPolicy` are used unless a [`RetryPolicy`] or
+///   [`HedgingPolicy`] is set in the request extensions.
+/// - `waitForReady` is used unless the call sets
+///   [`Request::set_wait_for_ready`](crate::Request::set_wait_for_ready).
+///
+/// `retryThrottling` is used unless the endpoint sets its own
+/// [`RetryThrottle`].
+///
+/// ```
+/// # use tonic::transport::channel::ServiceConfig;
+/// let config: ServiceConfig = r#"{
+///     "methodConfig": [{
+///         "name": [{ "service": "helloworld.Greeter", "method": "SayHello" }],
+///         "timeout": "1.5s",
+///         "maxResponseMessageBytes": 1048576,
+///         "retryPolicy": {
+///             "maxAttempts": 3,
+///             "initialBackoff": "0.1s",
+///             "maxBackoff": "1s",
+///             "backoffMultiplier": 2,
+///             "retryableStatusCodes": ["UNAVAILABLE"]
+///         }
+///     }]
+/// }"#
+/// .parse()
+/// .unwrap();
+///
+/// let method = config.method_config("/helloworld.Greeter/SayHello").unwrap();
+/// assert_eq!(method.timeout(), Some(std::time::Duration::from_millis(1500)));
+/// ```
+///
+/// [service config]: https://github.com/grpc/grpc/blob/master/doc/service_config.md
+#[derive(Debug, Clone, Default)]
+pub struct ServiceConfig {
+    methods: HashMap<(String, String), Arc<MethodConfig>>,
+    services: HashMap<String, Arc<MethodConfig>>,
+    default: Option<Arc<MethodConfig>>,
+    retry_throttle: Option<RetryThrottle>,
+}
+
+/// The settings of a single `methodConfig` entry of a [`ServiceConfig`].
+#[derive(Debug, Clone, Default)]
+pub struct MethodConfig {
+    timeout: Option<Duration>,
+    wait_for_ready: Option<bool>,
+    max_request_message_bytes: Option<usize>,
+    max_response_message_bytes: Option<usize>,
+    policy: Option<CallPolicy>,
+}
+
+impl ServiceConfig {
+    /// Parse a service config from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let raw: RawServiceConfig =
+            serde_json::from_str(json).map_err(Error::new_invalid_service_config)?;
+        raw.try_into().map_err(Error::new_invalid_service_config)
+    }
+
+    /// Returns the config applying to calls of `path`, for example
+    /// `/helloworld.Greeter/SayHello`.
+    pub fn method_config(&self, path: &str) -> Option<&MethodConfig> {
+        let (service, method) = path
+            .strip_prefix('/')
+            .and_then(|path| path.rsplit_once('/'))
+            .unwrap_or_default();
+
+        self.methods
+            .get(&(service.to_owned(), method.to_owned()))
+            .or_else(|| self.services.get(service))
+            .or(self.default.as_ref())
+            .map(|config| &**config)
+    }
+
+    pub(crate) fn retry_throttle(&self) -> Option<RetryThrottle> {
+        self.retry_throttle
+    }
+
+    /// Apply the method config matching `request` to it.
+    pub(crate) fn apply(&self, mut request: Request<Body>) -> (Request<Body>, Applied) {
+        let Some(config) = self.method_config(request.uri().path()) else {
+            return (request, Applied::default());
+        };
+
+        if let Some(timeout) = config.timeout {
+            let current = try_parse_grpc_timeout(request.headers()).ok().flatten();
+
+            if current.is_none_or(|current| timeout < current) {
+                let value = duration_to_grpc_timeout(timeout);
+                request.headers_mut().insert(
+                    GRPC_TIMEOUT_HEADER,
+                    HeaderValue::try_from(value).expect("valid grpc-timeout"),
+                );
+            }
+        }
+
+        let extensions = request.extensions_mut();
+        if extensions.get::<RetryPolicy>().is_none() && extensions.get::<HedgingPolicy>().is_none()
+        {
+            match &config.policy {
+                Some(CallPolicy::Retry(policy)) => {
+                    extensions.insert(policy.clone());
+                }
+                Some(CallPolicy::Hedging(policy)) => {
+                    extensions.insert(policy.clone());
+                }
+                None => {}
+            }
+        }
+
+        if let Some(wait_for_ready) = config.wait_for_ready
+            && extensions.get::<WaitForReady>().is_none()
+        {
+            extensions.insert(WaitForReady(wait_for_ready));
+        }
+
+        let mut applied = Applied {
+            max_response_message_bytes: config.max_response_message_bytes,
+            request_limit_exceeded: None,
+        };
+
+        if let Some(limit) = config.max_request_message_bytes {
+            let exceeded = LimitExceeded::default();
+            request = request.map(|body| {
+                Body::new(
+                    MessageLimitBody::new(body, limit, Direction::Request)
+                        .report_to(exceeded.clone()),
+                )
+            });
+            applied.request_limit_exceeded = Some(exceeded);
+        }
+
+        (request, applied)
+    }
+}
+
+impl FromStr for ServiceConfig {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_json(s)
+    }
+}
+
+impl MethodConfig {
+    /// The default timeout of calls.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Whether calls should wait for the channel to become ready.
+    pub fn wait_for_ready(&self) -> Option<bool> {
+        self.wait_for_ready
+    }
+
+    /// The maximum size of a message sent by the client.
+    pub fn max_request_message_bytes(&self) -> Option<usize> {
+        self.max_request_message_bytes
+    }
+
+    /// The maximum size of a message received by the client.
+    pub fn max_response_message_bytes(&self) -> Option<usize> {
+        self.max_response_message_bytes
+    }
+
+    /// The retry policy of calls.
+    pub fn retry_policy(&self) -> Option<&RetryPolicy> {
+        match &self.policy {
+            Some(CallPolicy::Retry(policy)) => Some(policy),
+            _ => None,
+        }
+    }
+
+    /// The hedging policy of calls.
+    pub fn hedging_policy(&self) -> Option<&HedgingPolicy> {
+        match &self.policy {
+            Some(CallPolicy::Hedging(policy)) => Some(policy),
+            _ => None,
+        }
+    }
+}
+
+/// What is left to do for a call once its response arrives.
+#[derive(Debug, Default)]
+pub(crate) struct Applied {
+    max_response_message_bytes: Option<usize>,
+    request_limit_exceeded: Option<LimitExceeded>,
+}
+
+impl Applied {
+    pub(crate) fn response(
+        &self,
+        result: Result<Response<Body>, crate::BoxError>,
+    ) -> Result<Response<Body>, crate::BoxError> {
+        // A request message that exceeded its limit aborted the request
+        // stream, report why instead of the resulting transport error.
+        if let Some(exceeded) = &self.request_limit_exceeded
+            && let Some(status) = exceeded.lock().unwrap().take()
+        {
+            return Err(status.into());
+        }
+
+        let response = result?;
+        Ok(match self.max_response_message_bytes {
+            Some(limit) => response
+                .map(|body| Body::new(MessageLimitBody::new(body, limit, Direction::Response))),
+            None => response,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawServiceConfig {
+    #[serde(default)]
+    method_config: Vec<RawMethodConfig>,
+    retry_throttling: Option<RawRetryThrottling>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMethodConfig {
+    #[serde(default)]
+    name: Vec<RawName>,
+    timeout: Option<String>,
+    wait_for_ready: Option<bool>,
+    max_request_message_bytes: Option<RawNumber>,
+    max_response_message_bytes: Option<RawNumber>,
+    retry_policy: Option<RawRetryPolicy>,
+    hedging_policy: Option<RawHedgingPolicy>,
+}
+
+#[derive(Deserialize)]
+struct RawName {
+    #[serde(default)]
+    service: String,
+    #[serde(default)]
+    method: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawRetryPolicy {
+    max_attempts: usize,
+    initial_backoff: String,
+    max_backoff: String,
+    backoff_multiplier: f64,
+    retryable_status_codes: Vec<RawCode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawHedgingPolicy {
+    max_attempts: usize,
+    hedging_delay: Option<String>,
+    #[serde(default)]
+    non_fatal_status_codes: Vec<RawCode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawRetryThrottling {
+    max_tokens: u32,
+    token_ratio: f64,
+}
+
+/// Protobuf JSON encodes 64 bit integers as strings, but numbers are
+/// accepted as well.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawNumber {
+    Number(u64),
+    String(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawCode {
+    Number(i32),
+    Name(String),
+}
+
+impl TryFrom<RawServiceConfig> for ServiceConfig {
+    type Error = String;
+
+    fn try_from(raw: RawServiceConfig) -> Result<Self, Self::Error> {
+        let mut config = ServiceConfig {
+            retry_throttle: raw
+                .retry_throttling
+                .map(|t| {
+                    if t.max_tokens == 0 || t.token_ratio <= 0.0 {
+                        return Err("retryThrottling requires positive maxTokens and tokenRatio");
+                    }
+                    Ok(RetryThrottle::new(t.max_tokens, t.token_ratio))
+                })
+                .transpose()?,
+            ..Default::default()
+        };
+
+        for mut raw in raw.method_config {
+            let names = std::mem::take(&mut raw.name);
+            let method = Arc::new(MethodConfig::try_from(raw)?);
+
+            for name in names {
+                let duplicate = match (name.service.is_empty(), name.method.is_empty()) {
+                    (true, false) => {
+                        return Err("a method name requires a service name".to_owned());
+                    }
+                    (true, true) => config.default.replace(method.clone()).is_some(),
+                    (false, true) => config
+                        .services
+                        .insert(name.service.clone(), method.clone())
+                        .is_some(),
+                    (false, false) => config
+                        .methods
+                        .insert((name.service.clone(), name.method.clone()), method.clone())
+                        .is_some(),
+                };
+
+                if duplicate {
+                    return Err(format!(
+                        "duplicate method config for {}/{}",
+                        name.service, name.method
+                    ));
+                }
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+impl TryFrom<RawMethodConfig> for MethodConfig {
+    type Error = String;
+
+    fn try_from(raw: RawMethodConfig) -> Result<Self, Self::Error> {
+        let policy = match (raw.retry_policy, raw.hedging_policy) {
+            (Some(_), Some(_)) => {
+                return Err("retryPolicy and hedgingPolicy are mutually exclusive".to_owned());
+            }
+            (Some(retry), None) => Some(CallPolicy::Retry(retry.try_into()?)),
+            (None, Some(hedging)) => Some(CallPolicy::Hedging(hedging.try_into()?)),
+            (None, None) => None,
+        };
+
+        Ok(MethodConfig {
+            timeout: raw.timeout.as_deref().map(parse_duration).transpose()?,
+            wait_for_ready: raw.wait_for_ready,
+            max_request_message_bytes: raw
+                .max_request_message_bytes
+                .map(RawNumber::into_usize)
+                .transpose()?,
+            max_response_message_bytes: raw
+                .max_response_message_bytes
+                .map(RawNumber::into_usize)
+                .transpose()?,
+            policy,
+        })
+    }
+}
+
+impl TryFrom<RawRetryPolicy> for RetryPolicy {
+    type Error = String;
+
+    fn try_from(raw: RawRetryPolicy) -> Result<Self, Self::Error> {
+        let initial_backoff = parse_duration(&raw.initial_backoff)?;
+        let max_backoff = parse_duration(&raw.max_backoff)?;
+
+        if raw.max_attempts < 2 {
+            return Err("retryPolicy.maxAttempts must be greater than 1".to_owned());
+        }
+        if initial_backoff.is_zero() || max_backoff.is_zero() {
+            return Err("retryPolicy backoffs must be greater than 0".to_owned());
+        }
+        if raw.backoff_multiplier <= 0.0 {
+            return Err("retryPolicy.backoffMultiplier must be greater than 0".to_owned());
+        }
+        if raw.retryable_status_codes.is_empty() {
+            return Err("retryPolicy.retryableStatusCodes must not be empty".to_owned());
+        }
+
+        Ok(RetryPolicy::new()
+            .max_attempts(raw.max_attempts)
+            .initial_backoff(initial_backoff)
+            .max_backoff(max_backoff)
+            .backoff_multiplier(raw.backoff_multiplier)
+            .retryable_status_codes(parse_codes(raw.retryable_status_codes)?))
+    }
+}
+
+impl TryFrom<RawHedgingPolicy> for HedgingPolicy {
+    type Error = String;
+
+    fn try_from(raw: RawHedgingPolicy) -> Result<Self, Self::Error> {
+        if raw.max_attempts < 2 {
+            return Err("hedgingPolicy.maxAttempts must be greater than 1".to_owned());
+        }
+
+        let hedging_delay = raw
+            .hedging_delay
+            .as_deref()
+            .map(parse_duration)
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(HedgingPolicy::new()
+            .max_attempts(raw.max_attempts)
+            .hedging_delay(hedging_delay)
+            .non_fatal_status_codes(parse_codes(raw.non_fatal_status_codes)?))
+    }
+}
+
+impl RawNumber {
+    fn into_usize(self) -> Result<usize, String> {
+        match self {
+            RawNumber::Number(n) => Ok(n),
+            RawNumber::String(s) => s.parse().map_err(|_| format!("invalid number {s:?}")),
+        }
+        .map(|n| usize::try_from(n).unwrap_or(usize::MAX))
+    }
+}
+
+/// Parse a protobuf JSON duration, for example `1.5s`.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    s.strip_suffix('s')
+        .and_then(|secs| secs.parse::<f64>().ok())
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| format!("invalid duration {s:?}"))
+}
+
+fn parse_codes(codes: Vec<RawCode>) -> Result<Vec<Code>, String> {
+    codes
+        .into_iter()
+        .map(|code| match code {
+            RawCode::Number(n) if (0..=16).contains(&n) => Ok(Code::from_i32(n)),
+            RawCode::Name(name) => code_from_name(&name),
+            RawCode::Number(n) => Err(format!("invalid status code {n}")),
+        })
+        .collect()
+}
+
+fn code_from_name(name: &str) -> Result<Code, String> {
+    Ok(match name {
+        "OK" => Code::Ok,
+        "CANCELLED" => Code::Cancelled,
+        "UNKNOWN" => Code::Unknown,
+        "INVALID_ARGUMENT" => Code::InvalidArgument,
+        "DEADLINE_EXCEEDED" => Code::DeadlineExceeded,
+        "NOT_FOUND" => Code::NotFound,
+        "ALREADY_EXISTS" => Code::AlreadyExists,
+        "PERMISSION_DENIED" => Code::PermissionDenied,
+        "RESOURCE_EXHAUSTED" => Code::ResourceExhausted,
+        "FAILED_PRECONDITION" => Code::FailedPrecondition,
+        "ABORTED" => Code::Aborted,
+        "OUT_OF_RANGE" => Code::OutOfRange,
+        "UNIMPLEMENTED" => Code::Unimplemented,
+        "INTERNAL" => Code::Internal,
+        "UNAVAILABLE" => Code::Unavailable,
+        "DATA_LOSS" => Code::DataLoss,
+        "UNAUTHENTICATED" => Code::Unauthenticated,
+        _ => return Err(format!("invalid status code {name:?}")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"{
+        "methodConfig": [
+            {
+                "name": [{ "service": "pkg.Svc", "method": "Get" }],
+                "timeout": "0.5s",
+                "maxRequestMessageBytes": "16",
+                "hedgingPolicy": { "maxAttempts": 2, "hedgingDelay": "0.01s" }
+            },
+            {
+                "name": [{ "service": "pkg.Svc" }],
+                "waitForReady": true,
+                "retryPolicy": {
+                    "maxAttempts": 4,
+                    "initialBackoff": "0.1s",
+                    "maxBackoff": "1s",
+                    "backoffMultiplier": 2,
+                    "retryableStatusCodes": ["UNAVAILABLE", 8]
+                }
+            },
+            { "name": [{}], "timeout": "10s" }
+        ],
+        "retryThrottling": { "maxTokens": 10, "tokenRatio": 0.1 }
+    }"#;
+
+    #[test]
+    fn matches_most_specific_method_config() {
+        let config = ServiceConfig::from_json(CONFIG).unwrap();
+
+        let get = config.method_config("/pkg.Svc/Get").unwrap();
+        assert_eq!(get.timeout(), Some(Duration::from_millis(500)));
+        assert_eq!(get.max_request_message_bytes(), Some(16));
+        assert!(get.hedging_policy().is_some());
+
+        let put = config.method_config("/pkg.Svc/Put").unwrap();
+        assert_eq!(put.wait_for_ready(), Some(true));
+        assert!(put.retry_policy().is_some());
+
+        let other = config.method_config("/pkg.Other/Get").unwrap();
+        assert_eq!(other.timeout(), Some(Duration::from_secs(10)));
+
+        assert_eq!(config.retry_throttle(), Some(RetryThrottle::new(10, 0.1)));
+    }
+
+    #[test]
+    fn rejects_invalid_configs() {
+        for json in [
+            r#"{ "methodConfig": [{ "name": [{ "method": "Get" }] }] }"#,
+            r#"{ "methodConfig": [{ "name": [{}] }, { "name": [{}] }] }"#,
+            r#"{ "methodConfig": [{ "timeout": "soon" }] }"#,
+            r#"{ "methodConfig": [{ "retryPolicy": { "maxAttempts": 1, "initialBackoff": "1s", "maxBackoff": "1s", "backoffMultiplier": 1, "retryableStatusCodes": ["UNAVAILABLE"] } }] }"#,
+            r#"{ "methodConfig": [{ "retryPolicy": { "maxAttempts": 2, "initialBackoff": "1s", "maxBackoff": "1s", "backoffMultiplier": 1, "retryableStatusCodes": ["NOPE"] } }] }"#,
+            "not json",
+        ] {
+            assert!(ServiceConfig::from_json(json).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    fn applies_timeout_and_policy() {
+        let config = ServiceConfig::from_json(CONFIG).unwrap();
+
+        let request = Request::builder()
+            .uri("http://example.com/pkg.Svc/Put")
+            .header(GRPC_TIMEOUT_HEADER, "1S")
+            .body(Body::empty())
+            .unwrap();
+        let (request, _) = config.apply(request);
+        assert_eq!(request.headers()[GRPC_TIMEOUT_HEADER], "1S");
+        assert!(request.extensions().get::<RetryPolicy>().is_some());
+
+        let request = Request::builder()
+            .uri("http://example.com/pkg.Svc/Get")
+            .header(GRPC_TIMEOUT_HEADER, "1S")
+            .body(Body::empty())
+            .unwrap();
+        let (request, _) = config.apply(request);
+        assert_eq!(request.headers()[GRPC_TIMEOUT_HEADER], "500000u");
+        assert!(request.extensions().get::<HedgingPolicy>().is_some());
+    }
+}
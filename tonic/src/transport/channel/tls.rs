@@ -1,6 +1,6 @@
 use super::service::TlsConnector;
 use crate::transport::{
-    Error,
+    CertificateProvider, Error,
     tls::{Certificate, Identity},
 };
 use http::Uri;
@@ -16,6 +16,7 @@ pub struct ClientTlsConfig {
     certs: Vec<Certificate>,
     trust_anchors: Vec<TrustAnchor<'static>>,
     identity: Option<Identity>,
+    certificate_provider: Option<Arc<dyn CertificateProvider>>,
     assume_http2: bool,
     #[cfg(feature = "tls-native-roots")]
     with_native_roots: bool,
@@ -80,6 +81,19 @@ impl ClientTlsConfig {
         }
     }
 
+    /// Sets a [`CertificateProvider`] for the client identity and CA
+    /// certificates, so that rotated certificates are used by new handshakes.
+    ///
+    /// The provider's identity is used instead of the one set with
+    /// [`ClientTlsConfig::identity`], and its CA certificates are trusted in
+    /// addition to the ones added to this config.
+    pub fn certificate_provider(self, provider: Arc<dyn CertificateProvider>) -> Self {
+        ClientTlsConfig {
+            certificate_provider: Some(provider),
+            ..self
+        }
+    }
+
     /// If true, the connector should assume that the server supports HTTP/2,
     /// even if it doesn't provide protocol negotiation via ALPN.
     pub fn assume_http2(self, assume_http2: bool) -> Self {
@@ -160,6 +174,7 @@ impl ClientTlsConfig {
             self.certs,
             self.trust_anchors,
             self.identity,
+            self.certificate_provider,
             server_cert_verifier,
             domain,
             self.assume_http2,
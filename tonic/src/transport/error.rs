@@ -21,6 +21,8 @@ pub(crate) enum Kind {
     InvalidUserAgent,
     #[cfg(all(feature = "_tls-any", feature = "channel"))]
     InvalidTlsConfigForUds,
+    #[cfg(feature = "service-config")]
+    InvalidServiceConfig,
 }
 
 impl Error {
@@ -49,6 +51,11 @@ impl Error {
         Error::new(Kind::InvalidUserAgent)
     }
 
+    #[cfg(feature = "service-config")]
+    pub(crate) fn new_invalid_service_config(source: impl Into<Source>) -> Self {
+        Error::new(Kind::InvalidServiceConfig).with(source)
+    }
+
     fn description(&self) -> &str {
         match &self.inner.kind {
             Kind::Transport => "transport error",
@@ -58,6 +65,8 @@ impl Error {
             Kind::InvalidUserAgent => "user agent is not a valid header value",
             #[cfg(all(feature = "_tls-any", feature = "channel"))]
             Kind::InvalidTlsConfigForUds => "cannot apply TLS config for unix domain socket",
+            #[cfg(feature = "service-config")]
+            Kind::InvalidServiceConfig => "invalid service config",
         }
     }
 }
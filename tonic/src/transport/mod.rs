@@ -93,6 +93,8 @@ pub mod channel;
 #[cfg(feature = "server")]
 pub mod server;
 
+#[cfg(feature = "_tls-any")]
+mod cert_provider;
 mod error;
 mod service;
 #[cfg(feature = "_tls-any")]
@@ -106,6 +108,10 @@ pub use self::error::Error;
 #[cfg(feature = "server")]
 pub use self::server::Server;
 
+#[cfg(feature = "_tls-any")]
+pub use self::cert_provider::{
+    CertificateProvider, Certificates, FileWatcherConfig, FileWatcherProvider,
+};
 #[cfg(feature = "_tls-any")]
 pub use self::tls::Certificate;
 pub use hyper::{Uri, body::Body};
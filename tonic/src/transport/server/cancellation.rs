@@ -0,0 +1,188 @@
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+use pin_project::pin_project;
+use std::{
+    fmt,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll},
+};
+use tokio::sync::Notify;
+
+/// Signals that a call handled by the server was cancelled.
+///
+/// A call is cancelled when the client resets its stream, the connection is
+/// closed, or its deadline expires before the response was sent completely.
+/// The server then drops the handler future, but work spawned by the handler
+/// can hold on to a clone of this token to stop early and clean up.
+///
+/// The token of a call is available through [`Request::cancelled`] and
+/// [`Request::cancellation_token`].
+///
+/// [`Request::cancelled`]: crate::Request::cancelled
+/// [`Request::cancellation_token`]: crate::Request::cancellation_token
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` once the call has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Waits until the call is cancelled.
+    pub async fn cancelled(&self) {
+        let notified = self.inner.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    pub(crate) fn cancel(&self) {
+        if !self.inner.cancelled.swap(true, Ordering::AcqRel) {
+            self.inner.notify.notify_waiters();
+        }
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+/// Cancels a call when dropped before it completed.
+#[derive(Debug)]
+pub(crate) struct CancelGuard(Option<CancellationToken>);
+
+impl CancelGuard {
+    pub(crate) fn new(token: CancellationToken) -> Self {
+        Self(Some(token))
+    }
+
+    pub(crate) fn disarm(&mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        if let Some(token) = self.0.take() {
+            token.cancel();
+        }
+    }
+}
+
+/// A response body that cancels its call when dropped before it ended.
+#[pin_project]
+pub(crate) struct CancelOnDropBody<B> {
+    #[pin]
+    inner: B,
+    guard: CancelGuard,
+}
+
+impl<B> CancelOnDropBody<B> {
+    pub(crate) fn new(inner: B, guard: CancelGuard) -> Self {
+        Self { inner, guard }
+    }
+}
+
+impl<B> Body for CancelOnDropBody<B>
+where
+    B: Body<Data = Bytes>,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        let mut inner = this.inner;
+        let frame = std::task::ready!(inner.as_mut().poll_frame(cx));
+
+        match &frame {
+            Some(Ok(_)) if !inner.is_end_stream() => {}
+            // The body ended, or failed on its own.
+            _ => this.guard.disarm(),
+        }
+
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::{BodyExt, Full};
+
+    #[tokio::test]
+    async fn cancel_wakes_waiters() {
+        let token = CancellationToken::new();
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.cancelled().await }
+        });
+
+        tokio::task::yield_now().await;
+        assert!(!token.is_cancelled());
+
+        token.cancel();
+        waiter.await.unwrap();
+        assert!(token.is_cancelled());
+
+        // Resolves immediately once cancelled.
+        token.cancelled().await;
+    }
+
+    #[tokio::test]
+    async fn dropping_unfinished_body_cancels() {
+        let token = CancellationToken::new();
+        let body = CancelOnDropBody::new(
+            Full::new(Bytes::from_static(b"hello")),
+            CancelGuard::new(token.clone()),
+        );
+
+        drop(body);
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn finished_body_does_not_cancel() {
+        let token = CancellationToken::new();
+        let body = CancelOnDropBody::new(
+            Full::new(Bytes::from_static(b"hello")),
+            CancelGuard::new(token.clone()),
+        );
+
+        body.collect().await.unwrap();
+        assert!(!token.is_cancelled());
+    }
+}
@@ -0,0 +1,265 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// What a [`Server`](super::Server) does with the connections beyond its
+/// connection limits.
+///
+/// See [`Server::max_connections`](super::Server::max_connections) and
+/// [`Server::max_connections_per_peer`](super::Server::max_connections_per_peer).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConnectionLimitBehavior {
+    /// Close the connections beyond the limits as soon as they are accepted.
+    #[default]
+    Reject,
+    /// Wait for connections to close before serving more.
+    ///
+    /// Beyond the global limit, the server stops accepting connections, which
+    /// queue in the backlog of the listener. Beyond the limit of a peer, its
+    /// connections are accepted and wait to be served, still counting towards
+    /// the global limit.
+    Queue,
+}
+
+/// Counts the connections a [`Server`](super::Server) did not serve right
+/// away because of its connection limits.
+///
+/// A handle is obtained with [`Server::stats`](super::Server::stats). Servers
+/// cloned from the same builder share their stats.
+#[derive(Debug, Clone)]
+pub struct ServerStats {
+    inner: Arc<StatsInner>,
+}
+
+#[derive(Debug, Default)]
+struct StatsInner {
+    rejected: AtomicU64,
+    queued: AtomicUsize,
+}
+
+/// Enforces the connection limits of a server.
+pub(crate) struct ConnectionLimiter {
+    behavior: ConnectionLimitBehavior,
+    global: Option<Arc<Semaphore>>,
+    max_per_peer: Option<usize>,
+    peers: Mutex<HashMap<IpAddr, Arc<Semaphore>>>,
+    stats: ServerStats,
+}
+
+/// Allows a connection to be served, until dropped.
+#[derive(Debug)]
+pub(crate) struct ConnectionPermit {
+    _global: Option<OwnedSemaphorePermit>,
+    _peer: Option<OwnedSemaphorePermit>,
+}
+
+pub(crate) enum Admission<F> {
+    Serve(ConnectionPermit),
+    /// The connection is served once the future resolves.
+    Queue(F),
+}
+
+impl ConnectionLimiter {
+    pub(crate) fn new(
+        max_connections: Option<usize>,
+        max_per_peer: Option<usize>,
+        behavior: ConnectionLimitBehavior,
+        stats: ServerStats,
+    ) -> Self {
+        Self {
+            behavior,
+            global: max_connections.map(|max| Arc::new(Semaphore::new(max))),
+            max_per_peer,
+            peers: Mutex::new(HashMap::new()),
+            stats,
+        }
+    }
+
+    /// Waits until a connection may be accepted.
+    ///
+    /// Returns the permit to serve it when connections beyond the global
+    /// limit are queued.
+    pub(crate) async fn ready(&self) -> Option<OwnedSemaphorePermit> {
+        match (&self.global, self.behavior) {
+            (Some(global), ConnectionLimitBehavior::Queue) => {
+                let permit = global.clone().acquire_owned().await;
+                Some(permit.expect("semaphore is never closed"))
+            }
+            _ => None,
+        }
+    }
+
+    /// Decides whether a connection accepted from `remote_addr` is served,
+    /// returning `None` if it is rejected.
+    ///
+    /// `permit` is the one returned by [`ConnectionLimiter::ready`].
+    pub(crate) fn admit(
+        &self,
+        remote_addr: Option<SocketAddr>,
+        permit: Option<OwnedSemaphorePermit>,
+    ) -> Option<Admission<impl Future<Output = ConnectionPermit> + Send + 'static>> {
+        let global = match (permit, &self.global) {
+            (Some(permit), _) => Some(permit),
+            (None, Some(global)) => match global.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    tracing::debug!("rejecting connection beyond max_connections");
+                    self.stats.connection_rejected();
+                    return None;
+                }
+            },
+            (None, None) => None,
+        };
+
+        let (Some(max_per_peer), Some(remote_addr)) = (self.max_per_peer, remote_addr) else {
+            return Some(Admission::Serve(ConnectionPermit {
+                _global: global,
+                _peer: None,
+            }));
+        };
+
+        let peer = {
+            let mut peers = self.peers.lock().unwrap();
+            // Forget the peers without connections.
+            peers.retain(|_, peer| Arc::strong_count(peer) > 1);
+            peers
+                .entry(remote_addr.ip())
+                .or_insert_with(|| Arc::new(Semaphore::new(max_per_peer)))
+                .clone()
+        };
+
+        match peer.clone().try_acquire_owned() {
+            Ok(permit) => Some(Admission::Serve(ConnectionPermit {
+                _global: global,
+                _peer: Some(permit),
+            })),
+            Err(_) if self.behavior == ConnectionLimitBehavior::Queue => {
+                let queued = self.stats.connection_queued();
+                Some(Admission::Queue(async move {
+                    let permit = peer.acquire_owned().await;
+                    drop(queued);
+                    ConnectionPermit {
+                        _global: global,
+                        _peer: Some(permit.expect("semaphore is never closed")),
+                    }
+                }))
+            }
+            Err(_) => {
+                tracing::debug!(
+                    "rejecting connection from {} beyond max_connections_per_peer",
+                    remote_addr.ip()
+                );
+                self.stats.connection_rejected();
+                None
+            }
+        }
+    }
+}
+
+impl ServerStats {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Arc::new(StatsInner::default()),
+        }
+    }
+
+    /// The number of connections closed because of the connection limits of
+    /// the server.
+    ///
+    /// See [`Server::max_connections`](super::Server::max_connections).
+    pub fn rejected_connections(&self) -> u64 {
+        self.inner.rejected.load(Ordering::Relaxed)
+    }
+
+    /// The number of connections waiting for other connections of their peer
+    /// to close before being served.
+    ///
+    /// See [`Server::max_connections_per_peer`](super::Server::max_connections_per_peer).
+    pub fn queued_connections(&self) -> usize {
+        self.inner.queued.load(Ordering::Relaxed)
+    }
+
+    fn connection_rejected(&self) {
+        self.inner.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a connection as queued until the returned guard is dropped.
+    fn connection_queued(&self) -> Queued {
+        self.inner.queued.fetch_add(1, Ordering::Relaxed);
+        Queued(self.inner.clone())
+    }
+}
+
+/// A connection waiting to be served, until dropped.
+struct Queued(Arc<StatsInner>);
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        self.0.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(port: u16) -> Option<SocketAddr> {
+        Some(SocketAddr::from(([127, 0, 0, 1], port)))
+    }
+
+    #[tokio::test]
+    async fn rejects_beyond_limits() {
+        let stats = ServerStats::new();
+        let limiter = ConnectionLimiter::new(
+            Some(3),
+            Some(2),
+            ConnectionLimitBehavior::Reject,
+            stats.clone(),
+        );
+
+        // The peers share their IP address.
+        let first = limiter.admit(peer(1), None);
+        let second = limiter.admit(peer(2), None);
+        assert!(matches!(first, Some(Admission::Serve(_))));
+        assert!(matches!(second, Some(Admission::Serve(_))));
+        assert!(limiter.admit(peer(3), None).is_none());
+
+        let third = limiter.admit(None, None);
+        assert!(matches!(third, Some(Admission::Serve(_))));
+        assert!(limiter.admit(None, None).is_none());
+        assert_eq!(stats.rejected_connections(), 2);
+
+        drop(first);
+        assert!(matches!(
+            limiter.admit(peer(3), None),
+            Some(Admission::Serve(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn queues_beyond_peer_limit() {
+        let stats = ServerStats::new();
+        let limiter =
+            ConnectionLimiter::new(None, Some(1), ConnectionLimitBehavior::Queue, stats.clone());
+
+        let Some(Admission::Serve(first)) = limiter.admit(peer(1), None) else {
+            panic!("expected the first connection to be served");
+        };
+        let Some(Admission::Queue(queued)) = limiter.admit(peer(2), None) else {
+            panic!("expected the second connection to be queued");
+        };
+        let queued = tokio::spawn(queued);
+        assert_eq!(stats.queued_connections(), 1);
+
+        drop(first);
+        queued.await.unwrap();
+        assert_eq!(stats.queued_connections(), 0);
+    }
+}
@@ -0,0 +1,282 @@
+use super::{BoxService, Connected, Server, limit::ConnectionLimiter};
+#[cfg(feature = "_tls-any")]
+use super::{ServerTlsConfig, service::TlsAcceptor};
+use crate::transport::Error;
+use std::{
+    fmt,
+    future::{Future, poll_fn},
+    pin::{Pin, pin},
+    sync::Arc,
+    task::Poll,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::watch,
+};
+use tokio_stream::Stream;
+
+type ServeFuture = Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+type ServeFn = Box<
+    dyn FnOnce(
+            Server,
+            BoxService,
+            Option<watch::Receiver<()>>,
+            Arc<ConnectionLimiter>,
+        ) -> ServeFuture
+        + Send,
+>;
+
+/// The listeners a [`Router`](super::Router) is served on at once.
+///
+/// Every listener serves the same services, with the same layers, connection
+/// limits and shutdown signal. The server's TLS config applies to the
+/// listeners added with [`Listeners::incoming`], while the others are served
+/// in plaintext or with a TLS config of their own.
+///
+/// See [`Router::serve_multi`](super::Router::serve_multi).
+///
+/// ```no_run
+/// # use tonic::transport::server::{Listeners, TcpIncoming};
+/// # use tonic::transport::Server;
+/// # #[cfg(unix)]
+/// # async fn run(routes: tonic::service::Routes) -> Result<(), Box<dyn std::error::Error>> {
+/// let tcp = TcpIncoming::bind("[::1]:50051".parse()?)?;
+/// let uds = tokio_stream::wrappers::UnixListenerStream::new(
+///     tokio::net::UnixListener::bind("/tmp/tonic.sock")?,
+/// );
+///
+/// Server::builder()
+///     .add_routes(routes)
+///     .serve_multi(Listeners::new().incoming(tcp).incoming(uds))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct Listeners {
+    listeners: Vec<Listener>,
+}
+
+struct Listener {
+    #[cfg(feature = "_tls-any")]
+    tls: ListenerTls,
+    serve: ServeFn,
+}
+
+#[cfg(feature = "_tls-any")]
+enum ListenerTls {
+    /// The TLS config of the server, if any.
+    Server,
+    Plaintext,
+    Config(TlsAcceptor),
+}
+
+impl Listeners {
+    /// Create an empty set of listeners.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve the connections of `incoming`, with the TLS config of the server
+    /// if it has one.
+    #[must_use]
+    pub fn incoming<I, IO, IE>(self, incoming: I) -> Self
+    where
+        I: Stream<Item = Result<IO, IE>> + Send + 'static,
+        IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
+        IE: Into<crate::BoxError> + 'static,
+    {
+        self.listen(
+            incoming,
+            #[cfg(feature = "_tls-any")]
+            ListenerTls::Server,
+        )
+    }
+
+    /// Serve the connections of `incoming` in plaintext, even if the server
+    /// has a TLS config.
+    #[must_use]
+    pub fn incoming_plaintext<I, IO, IE>(self, incoming: I) -> Self
+    where
+        I: Stream<Item = Result<IO, IE>> + Send + 'static,
+        IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
+        IE: Into<crate::BoxError> + 'static,
+    {
+        self.listen(
+            incoming,
+            #[cfg(feature = "_tls-any")]
+            ListenerTls::Plaintext,
+        )
+    }
+
+    /// Serve the connections of `incoming` with `tls_config`, instead of the
+    /// TLS config of the server.
+    #[cfg(feature = "_tls-any")]
+    pub fn incoming_with_tls<I, IO, IE>(
+        self,
+        incoming: I,
+        tls_config: ServerTlsConfig,
+    ) -> Result<Self, Error>
+    where
+        I: Stream<Item = Result<IO, IE>> + Send + 'static,
+        IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
+        IE: Into<crate::BoxError> + 'static,
+    {
+        let tls = tls_config.tls_acceptor().map_err(Error::from_source)?;
+        Ok(self.listen(incoming, ListenerTls::Config(tls)))
+    }
+
+    fn listen<I, IO, IE>(
+        mut self,
+        incoming: I,
+        #[cfg(feature = "_tls-any")] tls: ListenerTls,
+    ) -> Self
+    where
+        I: Stream<Item = Result<IO, IE>> + Send + 'static,
+        IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
+        IE: Into<crate::BoxError> + 'static,
+    {
+        self.listeners.push(Listener {
+            #[cfg(feature = "_tls-any")]
+            tls,
+            serve: Box::new(|server, svc, signal, limiter| {
+                let signal = signal.map(|mut signal| async move {
+                    // The sender is dropped once the listeners are done serving.
+                    let _ = signal.changed().await;
+                });
+                Box::pin(server.serve_listener(svc, incoming, signal, limiter))
+            }),
+        });
+        self
+    }
+
+    /// Serve `svc` on every listener until they all stop, or one of them fails.
+    pub(crate) async fn serve<F>(
+        self,
+        server: Server,
+        svc: BoxService,
+        signal: Option<F>,
+    ) -> Result<(), Error>
+    where
+        F: Future<Output = ()>,
+    {
+        let limiter = Arc::new(server.connection_limiter());
+        let (signal_tx, signal_rx) = watch::channel(());
+        let graceful = signal.is_some();
+
+        let mut serving = self
+            .listeners
+            .into_iter()
+            .map(|listener| {
+                let signal_rx = graceful.then(|| signal_rx.clone());
+                listener.serve(server.clone(), svc.clone(), signal_rx, limiter.clone())
+            })
+            .collect::<Vec<_>>();
+
+        let mut signal = pin!(signal);
+        poll_fn(|cx| {
+            if let Some(sig) = signal.as_mut().as_pin_mut()
+                && sig.poll(cx).is_ready()
+            {
+                let _ = signal_tx.send(());
+                signal.set(None);
+            }
+
+            let mut i = 0;
+            while i < serving.len() {
+                match serving[i].as_mut().poll(cx) {
+                    Poll::Ready(Ok(())) => drop(serving.swap_remove(i)),
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Pending => i += 1,
+                }
+            }
+
+            if serving.is_empty() {
+                Poll::Ready(Ok(()))
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl Listener {
+    fn serve(
+        self,
+        server: Server,
+        svc: BoxService,
+        signal: Option<watch::Receiver<()>>,
+        limiter: Arc<ConnectionLimiter>,
+    ) -> ServeFuture {
+        #[cfg(feature = "_tls-any")]
+        let server = match self.tls {
+            ListenerTls::Server => server,
+            ListenerTls::Plaintext => Server {
+                tls: None,
+                ..server
+            },
+            ListenerTls::Config(tls) => Server {
+                tls: Some(tls),
+                ..server
+            },
+        };
+
+        (self.serve)(server, svc, signal, limiter)
+    }
+}
+
+impl fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Listeners")
+            .field("listeners", &self.listeners.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        service::Routes,
+        transport::{Endpoint, server::TcpIncoming},
+    };
+    use http::Request;
+    use tower::ServiceExt as _;
+
+    #[tokio::test]
+    async fn serves_every_listener() {
+        let first = TcpIncoming::bind(([127, 0, 0, 1], 0).into()).unwrap();
+        let second = TcpIncoming::bind(([127, 0, 0, 1], 0).into()).unwrap();
+        let addrs = [first.local_addr().unwrap(), second.local_addr().unwrap()];
+
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(
+            Server::builder()
+                .add_routes(Routes::default())
+                .serve_multi_with_shutdown(
+                    Listeners::new().incoming(first).incoming_plaintext(second),
+                    async move {
+                        let _ = shutdown_rx.await;
+                    },
+                ),
+        );
+
+        for addr in addrs {
+            let channel = Endpoint::from_shared(format!("http://{addr}"))
+                .unwrap()
+                .connect()
+                .await
+                .unwrap();
+            let request = Request::post("/test.Test/Call")
+                .body(crate::body::Body::empty())
+                .unwrap();
+            let response = channel.oneshot(request).await.unwrap();
+            // Calls to unknown services are answered with `Unimplemented`.
+            assert_eq!(response.headers()["grpc-status"], "12");
+        }
+
+        shutdown_tx.send(()).unwrap();
+        server.await.unwrap().unwrap();
+    }
+}
@@ -1,10 +1,15 @@
 //! Server implementation and builder.
 
+mod cancellation;
 mod conn;
 mod display_error_stack;
 mod incoming;
 mod io_stream;
+mod limit;
+#[cfg(feature = "router")]
+mod listeners;
 mod service;
+mod shutdown;
 #[cfg(feature = "_tls-any")]
 mod tls;
 #[cfg(unix)]
@@ -19,12 +24,17 @@ use crate::{server::NamedService, service::Routes};
 #[cfg(feature = "router")]
 use std::convert::Infallible;
 
+pub use cancellation::CancellationToken;
 pub use conn::{Connected, TcpConnectInfo};
 use hyper_util::{
     rt::{TokioExecutor, TokioIo, TokioTimer},
     server::conn::auto::{Builder as ConnectionBuilder, HttpServerConnExec},
     service::TowerToHyperService,
 };
+pub use limit::{ConnectionLimitBehavior, ServerStats};
+#[cfg(feature = "router")]
+pub use listeners::Listeners;
+pub use shutdown::{ConnectionStats, ShutdownHandle};
 #[cfg(feature = "_tls-any")]
 pub use tls::ServerTlsConfig;
 
@@ -42,7 +52,10 @@ pub use incoming::TcpIncoming;
 #[cfg(feature = "_tls-any")]
 use crate::transport::Error;
 
+pub(crate) use self::cancellation::{CancelGuard, CancelOnDropBody};
+use self::limit::{Admission, ConnectionLimiter, ConnectionPermit};
 use self::service::{ConnectInfoLayer, ServerIo};
+use self::shutdown::{ConnectionState, DrainState, InFlight};
 use super::service::GrpcTimeout;
 use crate::body::Body;
 use crate::service::RecoverErrorLayer;
@@ -90,6 +103,9 @@ const DEFAULT_HTTP2_KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(20);
 pub struct Server<L = Identity> {
     trace_interceptor: Option<TraceInterceptor>,
     concurrency_limit: Option<usize>,
+    max_connections: Option<usize>,
+    max_connections_per_peer: Option<usize>,
+    connection_limit_behavior: ConnectionLimitBehavior,
     load_shed: bool,
     timeout: Option<Duration>,
     #[cfg(feature = "_tls-any")]
@@ -112,6 +128,9 @@ pub struct Server<L = Identity> {
     service_builder: ServiceBuilder<L>,
     max_connection_age: Option<Duration>,
     max_connection_age_grace: Option<Duration>,
+    max_connection_idle: Option<Duration>,
+    shutdown: ShutdownHandle,
+    stats: ServerStats,
 }
 
 impl Default for Server<Identity> {
@@ -119,6 +138,9 @@ impl Default for Server<Identity> {
         Self {
             trace_interceptor: None,
             concurrency_limit: None,
+            max_connections: None,
+            max_connections_per_peer: None,
+            connection_limit_behavior: ConnectionLimitBehavior::default(),
             load_shed: false,
             timeout: None,
             #[cfg(feature = "_tls-any")]
@@ -141,6 +163,9 @@ impl Default for Server<Identity> {
             service_builder: Default::default(),
             max_connection_age: None,
             max_connection_age_grace: None,
+            max_connection_idle: None,
+            shutdown: ShutdownHandle::new(),
+            stats: ServerStats::new(),
         }
     }
 }
@@ -188,6 +213,67 @@ impl<L> Server<L> {
         }
     }
 
+    /// Set the maximum number of connections the server serves at once.
+    ///
+    /// Connections beyond the limit are handled according to
+    /// [`Server::connection_limit_behavior`], and counted by
+    /// [`ServerStats::rejected_connections`].
+    ///
+    /// Default is no limit (`None`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use tower_service::Service;
+    /// # let builder = Server::builder();
+    /// builder.max_connections(10_000);
+    /// ```
+    #[must_use]
+    pub fn max_connections(self, max: usize) -> Self {
+        Server {
+            max_connections: Some(max),
+            ..self
+        }
+    }
+
+    /// Set the maximum number of connections the server serves at once for
+    /// each client IP address.
+    ///
+    /// This only applies to TCP connections, with or without TLS. Connections
+    /// beyond the limit are handled according to
+    /// [`Server::connection_limit_behavior`].
+    ///
+    /// Default is no limit (`None`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use tower_service::Service;
+    /// # let builder = Server::builder();
+    /// builder.max_connections_per_peer(16);
+    /// ```
+    #[must_use]
+    pub fn max_connections_per_peer(self, max: usize) -> Self {
+        Server {
+            max_connections_per_peer: Some(max),
+            ..self
+        }
+    }
+
+    /// Set what happens to the connections beyond [`Server::max_connections`]
+    /// and [`Server::max_connections_per_peer`].
+    ///
+    /// Default is [`ConnectionLimitBehavior::Reject`].
+    #[must_use]
+    pub fn connection_limit_behavior(self, behavior: ConnectionLimitBehavior) -> Self {
+        Server {
+            connection_limit_behavior: behavior,
+            ..self
+        }
+    }
+
     /// Enable or disable load shedding. The default is disabled.
     ///
     /// When load shedding is enabled, if the service responds with not ready
@@ -318,6 +404,29 @@ impl<L> Server<L> {
         }
     }
 
+    /// Sets the maximum duration a connection may stay without calls in
+    /// flight before it is closed.
+    ///
+    /// The server sends a GOAWAY frame to close the connection gracefully, so
+    /// clients can open a new connection for their next calls.
+    ///
+    /// Default is no limit (`None`).
+    ///
+    /// ```
+    /// # use tonic::transport::Server;
+    /// # use tower_service::Service;
+    /// # use std::time::Duration;
+    /// # let builder = Server::builder();
+    /// builder.max_connection_idle(Duration::from_secs(300));
+    /// ```
+    #[must_use]
+    pub fn max_connection_idle(self, max_connection_idle: Duration) -> Self {
+        Server {
+            max_connection_idle: Some(max_connection_idle),
+            ..self
+        }
+    }
+
     /// Set whether HTTP2 Ping frames are enabled on accepted connections.
     ///
     /// If `None` is specified, HTTP2 keepalive is disabled, otherwise the duration
@@ -325,6 +434,9 @@ impl<L> Server<L> {
     /// The timeout for receiving an acknowledgement of the keepalive ping
     /// can be set with [`Server::http2_keepalive_timeout`].
     ///
+    /// This only controls the pings sent by the server. Pings sent by clients
+    /// are always acknowledged, no matter how often they arrive.
+    ///
     /// Default is no HTTP2 keepalive (`None`)
     ///
     #[must_use]
@@ -491,6 +603,21 @@ impl<L> Server<L> {
         }
     }
 
+    /// Returns a handle to drain the server and observe the calls in flight.
+    ///
+    /// See [`ShutdownHandle`] for more details.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Returns a handle to observe the connections held back by the
+    /// connection limits of the server.
+    ///
+    /// See [`ServerStats`] for more details.
+    pub fn stats(&self) -> ServerStats {
+        self.stats.clone()
+    }
+
     /// Intercept inbound headers and add a [`tracing::Span`] to each response future.
     #[must_use]
     pub fn trace_fn<F>(self, f: F) -> Self
@@ -622,10 +749,20 @@ impl<L> Server<L> {
     /// [`ServiceBuilder`]: tower::ServiceBuilder
     /// [interceptors]: crate::service::Interceptor
     pub fn layer<NewLayer>(self, new_layer: NewLayer) -> Server<Stack<NewLayer, L>> {
+        self.map_service_builder(|builder| builder.layer(new_layer))
+    }
+
+    fn map_service_builder<NewL>(
+        self,
+        f: impl FnOnce(ServiceBuilder<L>) -> ServiceBuilder<NewL>,
+    ) -> Server<NewL> {
         Server {
-            service_builder: self.service_builder.layer(new_layer),
+            service_builder: f(self.service_builder),
             trace_interceptor: self.trace_interceptor,
             concurrency_limit: self.concurrency_limit,
+            max_connections: self.max_connections,
+            max_connections_per_peer: self.max_connections_per_peer,
+            connection_limit_behavior: self.connection_limit_behavior,
             load_shed: self.load_shed,
             timeout: self.timeout,
             #[cfg(feature = "_tls-any")]
@@ -647,6 +784,9 @@ impl<L> Server<L> {
             accept_http1: self.accept_http1,
             max_connection_age: self.max_connection_age,
             max_connection_age_grace: self.max_connection_age_grace,
+            max_connection_idle: self.max_connection_idle,
+            shutdown: self.shutdown,
+            stats: self.stats,
         }
     }
 
@@ -761,6 +901,40 @@ impl<L> Server<L> {
         F: Future<Output = ()>,
         ResBody: http_body::Body<Data = Bytes> + Send + 'static,
         ResBody::Error: Into<crate::BoxError>,
+    {
+        let limiter = Arc::new(self.connection_limiter());
+        let svc = self.service_builder.service(svc);
+        self.serve_listener(svc, incoming, signal, limiter).await
+    }
+
+    fn connection_limiter(&self) -> ConnectionLimiter {
+        ConnectionLimiter::new(
+            self.max_connections,
+            self.max_connections_per_peer,
+            self.connection_limit_behavior,
+            self.stats.clone(),
+        )
+    }
+
+    /// Serve `svc`, with the layers of the server already applied, on the
+    /// connections of `incoming`.
+    async fn serve_listener<S, I, F, IO, IE, ResBody>(
+        self,
+        svc: S,
+        incoming: I,
+        signal: Option<F>,
+        limiter: Arc<ConnectionLimiter>,
+    ) -> Result<(), super::Error>
+    where
+        S: Service<Request<Body>, Response = Response<ResBody>> + Clone + Send + 'static,
+        S::Future: Send,
+        S::Error: Into<crate::BoxError> + Send + 'static,
+        I: Stream<Item = Result<IO, IE>>,
+        IO: AsyncRead + AsyncWrite + Connected + Unpin + Send + 'static,
+        IE: Into<crate::BoxError>,
+        F: Future<Output = ()>,
+        ResBody: http_body::Body<Data = Bytes> + Send + 'static,
+        ResBody::Error: Into<crate::BoxError>,
     {
         let trace_interceptor = self.trace_interceptor.clone();
         let concurrency_limit = self.concurrency_limit;
@@ -780,8 +954,8 @@ impl<L> Server<L> {
         let http2_max_local_error_reset_streams = self.http2_max_local_error_reset_streams;
         let max_connection_age = self.max_connection_age;
         let max_connection_age_grace = self.max_connection_age_grace;
-
-        let svc = self.service_builder.service(svc);
+        let max_connection_idle = self.max_connection_idle;
+        let shutdown = self.shutdown;
 
         let incoming = io_stream::ServerIoStream::new(
             incoming,
@@ -829,6 +1003,7 @@ impl<L> Server<L> {
 
         let graceful = signal.is_some();
         let mut sig = pin!(Fuse { inner: signal });
+        let mut draining = pin!(shutdown.draining());
         let mut incoming = pin!(incoming);
 
         loop {
@@ -837,7 +1012,11 @@ impl<L> Server<L> {
                     trace!("signal received, shutting down");
                     break;
                 },
-                io = incoming.next() => {
+                _ = &mut draining => {
+                    trace!("draining, shutting down");
+                    break;
+                },
+                (io, permit) = async { let permit = limiter.ready().await; (incoming.next().await, permit) } => {
                     let io = match io {
                         Some(Ok(io)) => io,
                         Some(Err(e)) => {
@@ -851,20 +1030,47 @@ impl<L> Server<L> {
 
                     trace!("connection accepted");
 
+                    let remote_addr = io.connect_info().remote_addr();
+                    let Some(admission) = limiter.admit(remote_addr, permit) else {
+                        continue;
+                    };
+
+                    let connection = shutdown.connection(remote_addr);
                     let req_svc = svc
-                        .call(&io)
+                        .call((&io, connection.clone()))
                         .await
                         .map_err(super::Error::from_source)?;
 
                     let hyper_io = TokioIo::new(io);
                     let hyper_svc = TowerToHyperService::new(req_svc.map_request(|req: Request<Incoming>| req.map(Body::new)));
 
-                    serve_connection(hyper_io, hyper_svc, server.clone(), graceful.then(|| signal_rx.clone()), max_connection_age, max_connection_age_grace);
+                    let lifetime = ConnectionLifetime { max_connection_age, max_connection_age_grace, max_connection_idle };
+
+                    match admission {
+                        Admission::Serve(permit) => {
+                            let context = ConnectionContext { signal: signal_rx.clone(), drain: shutdown.subscribe(), connection, permit, lifetime };
+                            serve_connection(hyper_io, hyper_svc, server.clone(), context);
+                        }
+                        Admission::Queue(permit) => {
+                            let (server, signal_rx, drain, shutdown) = (server.clone(), signal_rx.clone(), shutdown.subscribe(), shutdown.clone());
+                            tokio::spawn(async move {
+                                tokio::select! {
+                                    permit = permit => {
+                                        let context = ConnectionContext { signal: signal_rx, drain, connection, permit, lifetime };
+                                        serve_connection(hyper_io, hyper_svc, server, context);
+                                    },
+                                    _ = shutdown.draining() => {
+                                        trace!("closing queued connection");
+                                    },
+                                }
+                            });
+                        }
+                    }
                 }
             }
         }
 
-        if graceful {
+        if graceful || shutdown.is_draining() {
             let _ = signal_tx.send(());
             drop(signal_rx);
             trace!(
@@ -880,6 +1086,23 @@ impl<L> Server<L> {
     }
 }
 
+/// What a connection is served with, besides its IO and service.
+struct ConnectionContext {
+    /// Changes once the server shuts down.
+    signal: tokio::sync::watch::Receiver<()>,
+    drain: tokio::sync::watch::Receiver<DrainState>,
+    connection: Arc<ConnectionState>,
+    permit: ConnectionPermit,
+    lifetime: ConnectionLifetime,
+}
+
+#[derive(Clone, Copy)]
+struct ConnectionLifetime {
+    max_connection_age: Option<Duration>,
+    max_connection_age_grace: Option<Duration>,
+    max_connection_idle: Option<Duration>,
+}
+
 enum TimeoutAction {
     GracefulShutdown,
     ForcefulShutdown,
@@ -903,15 +1126,32 @@ async fn connection_timeout_future(
     }
 }
 
+/// Completes once the connection had no calls in flight for `max_connection_idle`.
+async fn connection_idle_future(
+    connection: Arc<ConnectionState>,
+    max_connection_idle: Option<Duration>,
+) {
+    let Some(max_idle) = max_connection_idle else {
+        return future::pending().await;
+    };
+
+    loop {
+        match connection.idle_since() {
+            Some(idle_since) if idle_since.elapsed() >= max_idle => return,
+            Some(idle_since) => tokio::time::sleep_until(idle_since + max_idle).await,
+            // Check again later, as calls are not expected to complete soon.
+            None => tokio::time::sleep(max_idle).await,
+        }
+    }
+}
+
 // This is moved to its own function as a way to get around
 // https://github.com/rust-lang/rust/issues/102211
 fn serve_connection<B, IO, S, E>(
     hyper_io: IO,
     hyper_svc: S,
     builder: ConnectionBuilder<E>,
-    mut watcher: Option<tokio::sync::watch::Receiver<()>>,
-    max_connection_age: Option<Duration>,
-    max_connection_age_grace: Option<Duration>,
+    context: ConnectionContext,
 ) where
     B: http_body::Body + Send + 'static,
     B::Data: Send,
@@ -922,19 +1162,40 @@ fn serve_connection<B, IO, S, E>(
     S::Error: Into<Box<dyn std::error::Error + Send + Sync>> + Send,
     E: HttpServerConnExec<S::Future, B> + Send + Sync + 'static,
 {
+    let ConnectionContext {
+        signal: mut watcher,
+        mut drain,
+        connection,
+        permit,
+        lifetime,
+    } = context;
+
     tokio::spawn(async move {
         {
             let mut sig = pin!(Fuse {
-                inner: watcher.as_mut().map(|w| w.changed()),
+                inner: Some(watcher.changed()),
             });
 
             let mut conn = pin!(builder.serve_connection(hyper_io, hyper_svc));
 
             let mut connection_timeout = pin!(connection_timeout_future(
-                max_connection_age,
-                max_connection_age_grace,
+                lifetime.max_connection_age,
+                lifetime.max_connection_age_grace,
             ));
 
+            let mut connection_idle = pin!(Fuse {
+                inner: Some(connection_idle_future(
+                    connection.clone(),
+                    lifetime.max_connection_idle,
+                )),
+            });
+
+            // The server may have started draining since the connection was
+            // accepted.
+            if *drain.borrow_and_update() != DrainState::Serving {
+                conn.as_mut().graceful_shutdown();
+            }
+
             loop {
                 tokio::select! {
                     rv = &mut conn => {
@@ -954,14 +1215,33 @@ fn serve_connection<B, IO, S, E>(
                             }
                         }
                     },
-                    _ = &mut sig => {
+                    _ = &mut connection_idle => {
+                        debug!("closing idle connection");
+                        conn.as_mut().graceful_shutdown();
+                    },
+                    // Fails once the server stopped without shutting down.
+                    Ok(()) = &mut sig => {
                         conn.as_mut().graceful_shutdown();
                     },
+                    Ok(()) = drain.changed() => {
+                        match *drain.borrow_and_update() {
+                            DrainState::Serving => {},
+                            DrainState::Draining => {
+                                conn.as_mut().graceful_shutdown();
+                            },
+                            DrainState::Closing => {
+                                debug!("drain deadline elapsed, closing connection");
+                                break;
+                            },
+                        }
+                    },
                 }
             }
         }
 
         drop(watcher);
+        drop(connection);
+        drop(permit);
         trace!("connection closed");
     });
 }
@@ -1013,6 +1293,47 @@ impl<L> Router<L> {
         self
     }
 
+    /// Serve `routes` to the hosts matching `pattern`.
+    ///
+    /// Services added to this router directly are served to the hosts matching
+    /// no pattern. See [`Routes::add_host`] for more details.
+    pub fn add_host(mut self, pattern: impl Into<String>, routes: Routes) -> Self {
+        self.routes = self.routes.add_host(pattern, routes);
+        self
+    }
+
+    /// Returns a handle to drain the server and observe the calls in flight.
+    ///
+    /// See [`ShutdownHandle`] for more details.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.server.shutdown_handle()
+    }
+
+    /// Returns a handle to observe the connections held back by the
+    /// connection limits of the server.
+    ///
+    /// See [`ServerStats`] for more details.
+    pub fn stats(&self) -> ServerStats {
+        self.server.stats()
+    }
+
+    /// Add a new service wrapped in `layer` to this router.
+    ///
+    /// The layer only applies to calls to this service. See
+    /// [`Routes::add_service_with_layer`] for more details.
+    pub fn add_service_layered<S, LS>(mut self, svc: S, layer: LS) -> Self
+    where
+        S: NamedService,
+        LS: Layer<S>,
+        LS::Service: Service<Request<Body>> + Clone + Send + Sync + 'static,
+        <LS::Service as Service<Request<Body>>>::Response: axum::response::IntoResponse + Send,
+        <LS::Service as Service<Request<Body>>>::Error: Into<crate::BoxError> + Send,
+        <LS::Service as Service<Request<Body>>>::Future: Send + 'static,
+    {
+        self.routes = self.routes.add_service_with_layer(svc, layer);
+        self
+    }
+
     /// Consume this [`Server`] creating a future that will execute the server
     /// on [tokio]'s default executor.
     ///
@@ -1113,6 +1434,82 @@ impl<L> Router<L> {
             .serve_with_incoming_shutdown(self.routes.prepare(), incoming, signal)
             .await
     }
+
+    /// Consume this [`Server`] creating a future that will execute the server
+    /// on every one of the provided `listeners` at once.
+    ///
+    /// The listeners share the services, layers, connection limits and
+    /// [`ShutdownHandle`] of the server. This method discards any provided
+    /// [`Server`] TCP configuration.
+    ///
+    /// See [`Listeners`] for more details.
+    ///
+    /// [`Server`]: struct.Server.html
+    pub async fn serve_multi<ResBody>(self, listeners: Listeners) -> Result<(), super::Error>
+    where
+        L: Layer<Routes>,
+        L::Service: Service<Request<Body>, Response = Response<ResBody>> + Clone + Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<Body>>>::Future: Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<Body>>>::Error:
+            Into<crate::BoxError> + Send,
+        ResBody: http_body::Body<Data = Bytes> + Send + 'static,
+        ResBody::Error: Into<crate::BoxError>,
+    {
+        self.serve_multi_internal(listeners, None::<std::future::Ready<()>>)
+            .await
+    }
+
+    /// Consume this [`Server`] creating a future that will execute the server
+    /// on every one of the provided `listeners` at once. Similar to
+    /// `serve_with_shutdown` this method will also take a signal future to
+    /// gracefully shutdown all the listeners.
+    ///
+    /// See [`Router::serve_multi`] for more details.
+    ///
+    /// [`Server`]: struct.Server.html
+    pub async fn serve_multi_with_shutdown<F, ResBody>(
+        self,
+        listeners: Listeners,
+        signal: F,
+    ) -> Result<(), super::Error>
+    where
+        F: Future<Output = ()>,
+        L: Layer<Routes>,
+        L::Service: Service<Request<Body>, Response = Response<ResBody>> + Clone + Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<Body>>>::Future: Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<Body>>>::Error:
+            Into<crate::BoxError> + Send,
+        ResBody: http_body::Body<Data = Bytes> + Send + 'static,
+        ResBody::Error: Into<crate::BoxError>,
+    {
+        self.serve_multi_internal(listeners, Some(signal)).await
+    }
+
+    async fn serve_multi_internal<F, ResBody>(
+        self,
+        listeners: Listeners,
+        signal: Option<F>,
+    ) -> Result<(), super::Error>
+    where
+        F: Future<Output = ()>,
+        L: Layer<Routes>,
+        L::Service: Service<Request<Body>, Response = Response<ResBody>> + Clone + Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<Body>>>::Future: Send + 'static,
+        <<L as Layer<Routes>>::Service as Service<Request<Body>>>::Error:
+            Into<crate::BoxError> + Send,
+        ResBody: http_body::Body<Data = Bytes> + Send + 'static,
+        ResBody::Error: Into<crate::BoxError>,
+    {
+        // Apply the layers once, so the listeners share the same service stack.
+        let svc = self.server.service_builder.service(self.routes.prepare());
+        let svc = BoxCloneService::new(
+            svc.map_response(|res| res.map(Body::new))
+                .map_err(Into::into),
+        );
+        let server = self.server.map_service_builder(|_| ServiceBuilder::new());
+
+        listeners.serve(server, svc, signal).await
+    }
 }
 
 impl<L> fmt::Debug for Server<L> {
@@ -1125,6 +1522,7 @@ impl<L> fmt::Debug for Server<L> {
 struct Svc<S> {
     inner: S,
     trace_interceptor: Option<TraceInterceptor>,
+    connection: Arc<ConnectionState>,
 }
 
 impl<S, ResBody> Service<Request<Body>> for Svc<S>
@@ -1157,9 +1555,14 @@ where
             tracing::Span::none()
         };
 
+        let token = CancellationToken::new();
+        req.extensions_mut().insert(token.clone());
+
         SvcFuture {
             inner: self.inner.call(req),
             span,
+            guard: Some(CancelGuard::new(token)),
+            in_flight: Some(self.connection.call()),
         }
     }
 }
@@ -1169,6 +1572,11 @@ struct SvcFuture<F> {
     #[pin]
     inner: F,
     span: tracing::Span,
+    /// Cancels the call if the future, or the body of its response, is
+    /// dropped before the response was sent completely.
+    guard: Option<CancelGuard>,
+    /// Counts the call as in flight until its response was sent.
+    in_flight: Option<InFlight>,
 }
 
 impl<F, E, ResBody> Future for SvcFuture<F>
@@ -1184,8 +1592,25 @@ where
         let this = self.project();
         let _guard = this.span.enter();
 
-        let response: Response<ResBody> = ready!(this.inner.poll(cx)).map_err(Into::into)?;
-        let response = response.map(|body| Body::new(body.map_err(Into::into)));
+        let result = ready!(this.inner.poll(cx)).map_err(Into::into);
+        let mut guard = this.guard.take().expect("polled after completion");
+        let in_flight = this.in_flight.take();
+
+        let response: Response<ResBody> = match result {
+            Ok(response) => response,
+            Err(err) => {
+                guard.disarm();
+                return Poll::Ready(Err(err));
+            }
+        };
+        let response = response.map(|body| {
+            // Dropping the body drops the count of the call as in flight.
+            let body = body.map_frame(move |frame| {
+                let _in_flight = &in_flight;
+                frame
+            });
+            Body::new(CancelOnDropBody::new(body.map_err(Into::into), guard))
+        });
         Poll::Ready(Ok(response))
     }
 }
@@ -1206,7 +1631,7 @@ struct MakeSvc<S, IO> {
     _io: PhantomData<fn() -> IO>,
 }
 
-impl<S, ResBody, IO> Service<&ServerIo<IO>> for MakeSvc<S, IO>
+impl<S, ResBody, IO> Service<(&ServerIo<IO>, Arc<ConnectionState>)> for MakeSvc<S, IO>
 where
     IO: Connected + 'static,
     S: Service<Request<Body>, Response = Response<ResBody>> + Clone + Send + 'static,
@@ -1223,7 +1648,7 @@ where
         Ok(()).into()
     }
 
-    fn call(&mut self, io: &ServerIo<IO>) -> Self::Future {
+    fn call(&mut self, (io, connection): (&ServerIo<IO>, Arc<ConnectionState>)) -> Self::Future {
         let conn_info = io.connect_info();
 
         let svc = self.inner.clone();
@@ -1244,6 +1669,7 @@ where
             .service(Svc {
                 inner: svc,
                 trace_interceptor,
+                connection,
             });
 
         future::ready(Ok(svc))
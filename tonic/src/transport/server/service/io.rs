@@ -1,6 +1,8 @@
-use crate::transport::server::Connected;
+use crate::transport::server::{Connected, TcpConnectInfo};
+use std::any::Any;
 use std::io;
 use std::io::IoSlice;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
@@ -86,6 +88,21 @@ pub(crate) enum ServerIoConnectInfo<IO: Connected> {
     TlsIo(<TlsStream<IO> as Connected>::ConnectInfo),
 }
 
+impl<IO: Connected> ServerIoConnectInfo<IO> {
+    /// The address of the peer, if connected over TCP.
+    pub(crate) fn remote_addr(&self) -> Option<SocketAddr> {
+        let connect_info: &dyn Any = match self {
+            Self::Io(connect_info) => connect_info,
+            #[cfg(feature = "_tls-any")]
+            Self::TlsIo(connect_info) => connect_info.get_ref(),
+        };
+
+        connect_info
+            .downcast_ref::<TcpConnectInfo>()
+            .and_then(TcpConnectInfo::remote_addr)
+    }
+}
+
 impl<IO: Connected> Clone for ServerIoConnectInfo<IO> {
     fn clone(&self) -> Self {
         match self {
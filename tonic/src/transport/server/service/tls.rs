@@ -9,64 +9,65 @@ use tokio_rustls::{
 };
 
 use crate::transport::{
-    Certificate, Identity,
+    Certificate, CertificateProvider, Identity,
     service::tls::{
-        ALPN_H2, TlsError, convert_certificate_to_pki_types, convert_identity_to_pki_types,
+        ALPN_H2, TlsConfig, TlsError, convert_certificate_to_pki_types,
+        convert_identity_to_pki_types,
     },
 };
 
 #[derive(Clone)]
 pub(crate) struct TlsAcceptor {
-    inner: Arc<ServerConfig>,
+    inner: TlsConfig<ServerConfig>,
     timeout: Option<Duration>,
 }
 
 impl TlsAcceptor {
+    /// Create an acceptor presenting `identity`, or the identity of
+    /// `certificate_provider` if any, in which case the CA certificates of
+    /// the provider are also trusted to verify clients.
     pub(crate) fn new(
-        identity: &Identity,
+        identity: Option<&Identity>,
         client_ca_root: Option<&Certificate>,
+        certificate_provider: Option<Arc<dyn CertificateProvider>>,
         client_auth_optional: bool,
         ignore_client_order: bool,
         use_key_log: bool,
         timeout: Option<Duration>,
     ) -> Result<Self, crate::BoxError> {
-        let builder = ServerConfig::builder();
+        let client_ca_root = client_ca_root.cloned();
+        let build = move |identity: &Identity, client_ca_certs: &[Certificate]| {
+            server_config(
+                identity,
+                client_ca_root.iter().chain(client_ca_certs),
+                client_auth_optional,
+                ignore_client_order,
+                use_key_log,
+            )
+        };
 
-        let builder = match client_ca_root {
-            None => builder.with_no_client_auth(),
-            Some(cert) => {
-                let mut roots = RootCertStore::empty();
-                roots.add_parsable_certificates(convert_certificate_to_pki_types(cert)?);
-                let verifier = if client_auth_optional {
-                    WebPkiClientVerifier::builder(roots.into()).allow_unauthenticated()
-                } else {
-                    WebPkiClientVerifier::builder(roots.into())
-                }
-                .build()?;
-                builder.with_client_cert_verifier(verifier)
+        let inner = match certificate_provider {
+            Some(provider) => TlsConfig::provided(provider, move |certificates| {
+                let identity = certificates
+                    .identity
+                    .as_ref()
+                    .ok_or(TlsError::IdentityNotFound)?;
+                build(identity, &certificates.ca_certificates)
+            })?,
+            None => {
+                let identity = identity.ok_or(TlsError::IdentityNotFound)?;
+                TlsConfig::Static(Arc::new(build(identity, &[])?))
             }
         };
 
-        let (cert, key) = convert_identity_to_pki_types(identity)?;
-        let mut config = builder.with_single_cert(cert, key)?;
-        config.ignore_client_order = ignore_client_order;
-
-        if use_key_log {
-            config.key_log = Arc::new(tokio_rustls::rustls::KeyLogFile::new());
-        }
-
-        config.alpn_protocols.push(ALPN_H2.into());
-        Ok(Self {
-            inner: Arc::new(config),
-            timeout,
-        })
+        Ok(Self { inner, timeout })
     }
 
     pub(crate) async fn accept<IO>(&self, io: IO) -> Result<TlsStream<IO>, crate::BoxError>
     where
         IO: AsyncRead + AsyncWrite + Unpin,
     {
-        let acceptor = RustlsAcceptor::from(self.inner.clone());
+        let acceptor = RustlsAcceptor::from(self.inner.get());
         let accept_fut = acceptor.accept(io);
         match self.timeout {
             Some(timeout) => time::timeout(timeout, accept_fut)
@@ -78,6 +79,44 @@ impl TlsAcceptor {
     }
 }
 
+fn server_config<'a>(
+    identity: &Identity,
+    client_ca_certs: impl Iterator<Item = &'a Certificate>,
+    client_auth_optional: bool,
+    ignore_client_order: bool,
+    use_key_log: bool,
+) -> Result<ServerConfig, crate::BoxError> {
+    let builder = ServerConfig::builder();
+
+    let client_ca_certs = client_ca_certs.collect::<Vec<_>>();
+    let builder = if client_ca_certs.is_empty() {
+        builder.with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore::empty();
+        for cert in client_ca_certs {
+            roots.add_parsable_certificates(convert_certificate_to_pki_types(cert)?);
+        }
+        let verifier = if client_auth_optional {
+            WebPkiClientVerifier::builder(roots.into()).allow_unauthenticated()
+        } else {
+            WebPkiClientVerifier::builder(roots.into())
+        }
+        .build()?;
+        builder.with_client_cert_verifier(verifier)
+    };
+
+    let (cert, key) = convert_identity_to_pki_types(identity)?;
+    let mut config = builder.with_single_cert(cert, key)?;
+    config.ignore_client_order = ignore_client_order;
+
+    if use_key_log {
+        config.key_log = Arc::new(tokio_rustls::rustls::KeyLogFile::new());
+    }
+
+    config.alpn_protocols.push(ALPN_H2.into());
+    Ok(config)
+}
+
 impl fmt::Debug for TlsAcceptor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("TlsAcceptor").finish()
@@ -0,0 +1,310 @@
+use std::{
+    fmt,
+    net::SocketAddr,
+    pin::pin,
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{Notify, watch},
+    time::Instant,
+};
+
+/// Drains a [`Server`](super::Server) and observes its connections and the
+/// calls they serve.
+///
+/// Draining stops the server from accepting connections and sends a GOAWAY to
+/// every connection, so that clients stop sending new calls on them while the
+/// calls in flight complete. Calls still in flight when the drain deadline
+/// elapses are cancelled by closing their connection.
+///
+/// A handle is obtained with [`Server::shutdown_handle`]. Servers cloned from
+/// the same builder share their handle, and are drained together.
+///
+/// ```
+/// # use tonic::transport::Server;
+/// # use std::time::Duration;
+/// # async fn shutdown_requested() {}
+/// # async fn run() {
+/// let server = Server::builder();
+/// let shutdown = server.shutdown_handle();
+///
+/// tokio::spawn(async move {
+///     shutdown_requested().await;
+///     shutdown.drain(Duration::from_secs(30)).await;
+/// });
+/// # }
+/// ```
+///
+/// [`Server::shutdown_handle`]: super::Server::shutdown_handle
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    state: watch::Sender<DrainState>,
+    connections: Mutex<Vec<Weak<ConnectionState>>>,
+    /// Notified when a connection closes.
+    closed: Notify,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DrainState {
+    Serving,
+    /// Connections are sent a GOAWAY, and close once their calls complete.
+    Draining,
+    /// Connections are closed, cancelling their calls.
+    Closing,
+}
+
+/// The calls in flight on a connection, shared by the services serving it.
+///
+/// The connection is closed once the last of them is dropped.
+#[derive(Debug)]
+pub(crate) struct ConnectionState {
+    remote_addr: Option<SocketAddr>,
+    in_flight: AtomicUsize,
+    /// When a call last started or completed.
+    last_active: Mutex<Instant>,
+    handle: Weak<Inner>,
+}
+
+/// The calls in flight on a connection of a [`Server`](super::Server).
+#[derive(Debug, Clone)]
+pub struct ConnectionStats {
+    remote_addr: Option<SocketAddr>,
+    in_flight: usize,
+}
+
+impl ShutdownHandle {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                state: watch::Sender::new(DrainState::Serving),
+                connections: Mutex::new(Vec::new()),
+                closed: Notify::new(),
+            }),
+        }
+    }
+
+    /// Drain the server, waiting for its connections to close.
+    ///
+    /// Connections still open after `deadline` are closed, cancelling the
+    /// calls in flight on them.
+    pub async fn drain(&self, deadline: Duration) {
+        self.set_state(DrainState::Draining);
+
+        if tokio::time::timeout(deadline, self.connections_closed())
+            .await
+            .is_err()
+        {
+            tracing::debug!(
+                "cancelling {} calls still in flight after the drain deadline",
+                self.in_flight()
+            );
+            self.set_state(DrainState::Closing);
+            self.connections_closed().await;
+        }
+    }
+
+    /// Returns `true` once the server started draining.
+    pub fn is_draining(&self) -> bool {
+        *self.inner.state.borrow() != DrainState::Serving
+    }
+
+    /// Waits until the server starts draining.
+    pub async fn draining(&self) {
+        let mut state = self.inner.state.subscribe();
+        // The sender is owned by `self`, so it is not dropped while waiting.
+        let _ = state.wait_for(|state| *state != DrainState::Serving).await;
+    }
+
+    /// The number of calls in flight on all the connections of the server.
+    pub fn in_flight(&self) -> usize {
+        self.connections()
+            .iter()
+            .map(ConnectionStats::in_flight)
+            .sum()
+    }
+
+    /// The calls in flight on each open connection of the server.
+    pub fn connections(&self) -> Vec<ConnectionStats> {
+        let mut connections = self.inner.connections.lock().unwrap();
+        connections.retain(|connection| connection.strong_count() > 0);
+        connections
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|connection| ConnectionStats {
+                remote_addr: connection.remote_addr,
+                in_flight: connection.in_flight(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<DrainState> {
+        self.inner.state.subscribe()
+    }
+
+    /// Track the calls in flight on a new connection.
+    pub(crate) fn connection(&self, remote_addr: Option<SocketAddr>) -> Arc<ConnectionState> {
+        let connection = Arc::new(ConnectionState {
+            remote_addr,
+            in_flight: AtomicUsize::new(0),
+            last_active: Mutex::new(Instant::now()),
+            handle: Arc::downgrade(&self.inner),
+        });
+
+        let mut connections = self.inner.connections.lock().unwrap();
+        connections.retain(|connection| connection.strong_count() > 0);
+        connections.push(Arc::downgrade(&connection));
+
+        connection
+    }
+
+    fn set_state(&self, state: DrainState) {
+        self.inner.state.send_if_modified(|current| {
+            // Draining never goes back to serving.
+            let changed = *current != state && *current != DrainState::Closing;
+            if changed {
+                *current = state;
+            }
+            changed
+        });
+    }
+
+    async fn connections_closed(&self) {
+        loop {
+            let mut closed = pin!(self.inner.closed.notified());
+            closed.as_mut().enable();
+
+            let open = self
+                .inner
+                .connections
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|c| c.strong_count() > 0);
+            if !open {
+                return;
+            }
+            closed.await;
+        }
+    }
+}
+
+impl fmt::Debug for ShutdownHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShutdownHandle")
+            .field("state", &*self.inner.state.borrow())
+            .finish()
+    }
+}
+
+impl ConnectionState {
+    /// Count a call as in flight until the returned guard is dropped.
+    pub(crate) fn call(self: &Arc<Self>) -> InFlight {
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        *self.last_active.lock().unwrap() = Instant::now();
+        InFlight(self.clone())
+    }
+
+    pub(crate) fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    /// When the connection became idle, if no calls are in flight.
+    pub(crate) fn idle_since(&self) -> Option<Instant> {
+        let last_active = self.last_active.lock().unwrap();
+        (self.in_flight() == 0).then_some(*last_active)
+    }
+}
+
+impl Drop for ConnectionState {
+    fn drop(&mut self) {
+        if let Some(inner) = self.handle.upgrade() {
+            inner.closed.notify_waiters();
+        }
+    }
+}
+
+/// A call in flight on a connection, until dropped.
+#[derive(Debug)]
+pub(crate) struct InFlight(Arc<ConnectionState>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        // Update the activity first, so the connection is not seen idle since
+        // before the call.
+        *self.0.last_active.lock().unwrap() = Instant::now();
+        self.0.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl ConnectionStats {
+    /// The address of the client, if connected over TCP.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    /// The number of calls in flight on the connection.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn drain_waits_for_calls() {
+        let shutdown = ShutdownHandle::new();
+        let mut state = shutdown.subscribe();
+        let connection = shutdown.connection(None);
+        let call = connection.call();
+        assert_eq!(shutdown.in_flight(), 1);
+
+        let drain = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.drain(Duration::from_secs(10)).await }
+        });
+
+        state.changed().await.unwrap();
+        assert_eq!(*state.borrow(), DrainState::Draining);
+        assert!(shutdown.is_draining());
+
+        drop(call);
+        assert_eq!(shutdown.in_flight(), 0);
+        drop(connection);
+        drain.await.unwrap();
+        assert_eq!(*state.borrow(), DrainState::Draining);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drain_closes_connections_after_deadline() {
+        let shutdown = ShutdownHandle::new();
+        let mut state = shutdown.subscribe();
+        let connection = shutdown.connection(None);
+        let call = connection.call();
+
+        let drain = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.drain(Duration::from_secs(10)).await }
+        });
+
+        state
+            .wait_for(|state| *state == DrainState::Closing)
+            .await
+            .unwrap();
+        assert_eq!(shutdown.connections()[0].in_flight(), 1);
+
+        // Closing the connection drops its calls.
+        drop(call);
+        drop(connection);
+        drain.await.unwrap();
+    }
+}
@@ -1,13 +1,17 @@
-use std::{fmt, time::Duration};
+use std::{fmt, sync::Arc, time::Duration};
 
 use super::service::TlsAcceptor;
-use crate::transport::tls::{Certificate, Identity};
+use crate::transport::{
+    CertificateProvider,
+    tls::{Certificate, Identity},
+};
 
 /// Configures TLS settings for servers.
 #[derive(Clone, Default)]
 pub struct ServerTlsConfig {
     identity: Option<Identity>,
     client_ca_root: Option<Certificate>,
+    certificate_provider: Option<Arc<dyn CertificateProvider>>,
     client_auth_optional: bool,
     ignore_client_order: bool,
     use_key_log: bool,
@@ -34,6 +38,19 @@ impl ServerTlsConfig {
         }
     }
 
+    /// Sets a [`CertificateProvider`] for the [`Identity`] of the server, so
+    /// that rotated certificates are used by new handshakes.
+    ///
+    /// The provider's identity is used instead of the one set with
+    /// [`ServerTlsConfig::identity`], and its CA certificates are also used to
+    /// validate client TLS certificates.
+    pub fn certificate_provider(self, provider: Arc<dyn CertificateProvider>) -> Self {
+        ServerTlsConfig {
+            certificate_provider: Some(provider),
+            ..self
+        }
+    }
+
     /// Sets a certificate against which to validate client TLS certificates.
     pub fn client_ca_root(self, cert: Certificate) -> Self {
         ServerTlsConfig {
@@ -84,8 +101,9 @@ impl ServerTlsConfig {
 
     pub(crate) fn tls_acceptor(&self) -> Result<TlsAcceptor, crate::BoxError> {
         TlsAcceptor::new(
-            self.identity.as_ref().unwrap(),
+            self.identity.as_ref(),
             self.client_ca_root.as_ref(),
+            self.certificate_provider.clone(),
             self.client_auth_optional,
             self.ignore_client_order,
             self.use_key_log,
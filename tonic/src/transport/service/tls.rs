@@ -1,8 +1,12 @@
-use std::{fmt, io::Cursor};
+use std::{
+    fmt,
+    io::Cursor,
+    sync::{Arc, Mutex},
+};
 
 use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject as _};
 
-use crate::transport::{Certificate, Identity};
+use crate::transport::{Certificate, CertificateProvider, Certificates, Identity};
 
 /// h2 alpn in plain format for rustls.
 pub(crate) const ALPN_H2: &[u8] = b"h2";
@@ -18,6 +22,8 @@ pub(crate) enum TlsError {
     HandshakeTimeout,
     #[cfg(feature = "channel")]
     VerifierConflict,
+    #[cfg(feature = "server")]
+    IdentityNotFound,
 }
 
 impl fmt::Display for TlsError {
@@ -41,6 +47,8 @@ impl fmt::Display for TlsError {
                  methods — those configure the default verifier, which is replaced by \
                  the custom one."
             ),
+            #[cfg(feature = "server")]
+            TlsError::IdentityNotFound => write!(f, "No TLS identity was provided."),
         }
     }
 }
@@ -63,3 +71,101 @@ pub(crate) fn convert_identity_to_pki_types(
         .map_err(|_| TlsError::PrivateKeyParseError)?;
     Ok((cert, key))
 }
+
+/// The rustls config of new handshakes, rebuilt whenever the certificates of
+/// its provider change.
+pub(crate) enum TlsConfig<C> {
+    Static(Arc<C>),
+    Provided(Arc<ProvidedConfig<C>>),
+}
+
+type BuildConfig<C> = Box<dyn Fn(&Certificates) -> Result<C, crate::BoxError> + Send + Sync>;
+
+pub(crate) struct ProvidedConfig<C> {
+    provider: Arc<dyn CertificateProvider>,
+    build: BuildConfig<C>,
+    current: Mutex<(Arc<Certificates>, Arc<C>)>,
+}
+
+impl<C> TlsConfig<C> {
+    /// Build the config from the certificates of `provider`, failing if the
+    /// current ones are invalid.
+    pub(crate) fn provided(
+        provider: Arc<dyn CertificateProvider>,
+        build: impl Fn(&Certificates) -> Result<C, crate::BoxError> + Send + Sync + 'static,
+    ) -> Result<Self, crate::BoxError> {
+        let certificates = provider.certificates();
+        let config = Arc::new(build(&certificates)?);
+        Ok(Self::Provided(Arc::new(ProvidedConfig {
+            provider,
+            build: Box::new(build),
+            current: Mutex::new((certificates, config)),
+        })))
+    }
+
+    pub(crate) fn get(&self) -> Arc<C> {
+        let provided = match self {
+            Self::Static(config) => return config.clone(),
+            Self::Provided(provided) => provided,
+        };
+
+        let certificates = provided.provider.certificates();
+        let mut current = provided.current.lock().unwrap();
+        if !Arc::ptr_eq(&current.0, &certificates) {
+            match (provided.build)(&certificates) {
+                Ok(config) => current.1 = Arc::new(config),
+                // Keep serving with the previous certificates rather than
+                // failing every handshake until they are fixed.
+                Err(e) => tracing::warn!("error building TLS config from new certificates: {e}"),
+            }
+            current.0 = certificates;
+        }
+        current.1.clone()
+    }
+}
+
+impl<C> Clone for TlsConfig<C> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Static(config) => Self::Static(config.clone()),
+            Self::Provided(provided) => Self::Provided(provided.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Provider(Mutex<Arc<Certificates>>);
+
+    impl CertificateProvider for Provider {
+        fn certificates(&self) -> Arc<Certificates> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    #[test]
+    fn provided_config_rebuilds_on_change() {
+        let provider = Arc::new(Provider(Mutex::new(Arc::new(Certificates::new()))));
+        let config = TlsConfig::provided(provider.clone(), |certificates| {
+            match certificates.ca_certificates.as_slice() {
+                [] => Ok(0),
+                [ca] if ca.get_ref() == b"invalid" => Err("invalid certificate".into()),
+                cas => Ok(cas.len()),
+            }
+        })
+        .unwrap();
+        assert_eq!(*config.get(), 0);
+
+        let rotated = Certificates::new().ca_certificate(Certificate::from_pem("ca"));
+        *provider.0.lock().unwrap() = Arc::new(rotated);
+        assert_eq!(*config.get(), 1);
+
+        // Invalid certificates keep the previous config.
+        let invalid = Certificates::new().ca_certificate(Certificate::from_pem("invalid"));
+        *provider.0.lock().unwrap() = Arc::new(invalid);
+        assert_eq!(*config.get(), 1);
+    }
+}